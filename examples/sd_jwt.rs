@@ -10,6 +10,7 @@ use josekit::jws::HS256;
 use josekit::jwt::JwtPayload;
 use josekit::jwt::{self};
 use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwsAlgorithm;
 use sd_jwt_payload::JwsSigner;
 use sd_jwt_payload::KeyBindingJwtBuilder;
 use sd_jwt_payload::SdJwt;
@@ -65,7 +66,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     .add_decoys("/nationalities", 1)?
     .add_decoys("", 2)?
     .require_key_binding(sd_jwt_payload::RequiredKeyBinding::Kid("key1".to_string()))
-    .finish(&signer, "HS256")
+    .finish(&signer, JwsAlgorithm::HS256)
     .await?;
 
   println!("raw object: {}", serde_json::to_string_pretty(sd_jwt.claims())?);
@@ -79,7 +80,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     .aud("https://verifier.example.com")
     .nonce("abcdefghi")
     .iat(164389238943)
-    .finish(&sd_jwt, &hasher, "HS256", &signer)
+    .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &signer)
     .await?;
 
   // The holder can withhold from a verifier any concealable claim by calling `conceal`.
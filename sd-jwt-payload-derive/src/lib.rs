@@ -0,0 +1,216 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derive macro for `sd_jwt_payload::SelectivelyDisclosable`, re-exported by `sd-jwt-payload` with its `derive`
+//! feature enabled.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Expr;
+use syn::ExprLit;
+use syn::Fields;
+use syn::Lit;
+use syn::LitInt;
+use syn::Meta;
+use syn::Token;
+
+/// Derives `SelectivelyDisclosable` for a struct with named fields, using the `#[sd]`, `#[sd(each)]`,
+/// `#[sd(nested)]` and `#[sd(decoys = N)]` field attributes.
+#[proc_macro_derive(SelectivelyDisclosable, attributes(sd))]
+pub fn derive_selectively_disclosable(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Concealment options of a field.
+#[derive(Default)]
+struct SdOptions {
+  each: bool,
+  nested: bool,
+  decoys: usize,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+  let Data::Struct(data) = &input.data else {
+    return Err(syn::Error::new_spanned(
+      &input.ident,
+      "SelectivelyDisclosable can only be derived for structs",
+    ));
+  };
+  let Fields::Named(fields) = &data.fields else {
+    return Err(syn::Error::new_spanned(
+      &input.ident,
+      "SelectivelyDisclosable can only be derived for structs with named fields",
+    ));
+  };
+  let rename_all = serde_value(&input.attrs, "rename_all")?;
+
+  let mut steps = vec![];
+  for field in &fields.named {
+    let Some(options) = sd_options(&field.attrs)? else {
+      continue;
+    };
+    let ident = field.ident.as_ref().expect("fields are named");
+    let flatten = serde_flag(&field.attrs, "flatten")?;
+    if flatten && !options.nested {
+      return Err(syn::Error::new_spanned(
+        ident,
+        "flattened fields can only be selectively disclosable with `#[sd(nested)]`",
+      ));
+    }
+    let name = match serde_value(&field.attrs, "rename")? {
+      Some(name) => name,
+      None => rename(&ident.to_string(), rename_all.as_deref(), ident)?,
+    };
+    // The claims of a flattened field belong to the object of the struct itself.
+    let path = if flatten {
+      String::new()
+    } else {
+      format!("/{}", name.replace('~', "~0").replace('/', "~1"))
+    };
+    // Decoys go into the array with `each`, and otherwise into the object of the struct, next to the field.
+    let (conceal, decoys_path) = if options.each {
+      (quote! { plan.conceal_each(#path, &self.#ident); }, path.as_str())
+    } else if options.nested {
+      (
+        quote! { plan.nest(#path, ::sd_jwt_payload::SelectivelyDisclosable::concealment_plan(&self.#ident)); },
+        "",
+      )
+    } else {
+      (quote! { plan.conceal(#path); }, "")
+    };
+    steps.push(conceal);
+    if options.decoys > 0 {
+      let decoys = options.decoys;
+      steps.push(quote! { plan.add_decoys(#decoys_path, #decoys); });
+    }
+  }
+
+  let ident = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+  Ok(quote! {
+    impl #impl_generics ::sd_jwt_payload::SelectivelyDisclosable for #ident #ty_generics #where_clause {
+      fn concealment_plan(&self) -> ::sd_jwt_payload::ConcealmentPlan {
+        let mut plan = ::sd_jwt_payload::ConcealmentPlan::new();
+        #(#steps)*
+        plan
+      }
+
+      fn from_disclosed_object(object: ::sd_jwt_payload::JsonObject) -> ::sd_jwt_payload::Result<Self> {
+        ::sd_jwt_payload::decode_disclosed_object(object)
+      }
+    }
+  })
+}
+
+/// Parses the `#[sd]` attribute of a field, if present.
+fn sd_options(attrs: &[Attribute]) -> syn::Result<Option<SdOptions>> {
+  let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("sd")) else {
+    return Ok(None);
+  };
+  let mut options = SdOptions::default();
+  if matches!(attr.meta, Meta::Path(_)) {
+    return Ok(Some(options));
+  }
+  attr.parse_nested_meta(|meta| {
+    if meta.path.is_ident("each") {
+      options.each = true;
+      Ok(())
+    } else if meta.path.is_ident("nested") {
+      options.nested = true;
+      Ok(())
+    } else if meta.path.is_ident("decoys") {
+      options.decoys = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+      Ok(())
+    } else {
+      Err(meta.error("expected `each`, `nested` or `decoys = N`"))
+    }
+  })?;
+  if options.each && options.nested {
+    return Err(syn::Error::new_spanned(attr, "`each` and `nested` can't be combined"));
+  }
+
+  Ok(Some(options))
+}
+
+/// Returns the string value of the `#[serde(<key> = "...")]` attribute, if present.
+fn serde_value(attrs: &[Attribute], key: &str) -> syn::Result<Option<String>> {
+  for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+    let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+    for meta in metas {
+      if let Meta::NameValue(name_value) = meta {
+        if let (
+          true,
+          Expr::Lit(ExprLit {
+            lit: Lit::Str(value), ..
+          }),
+        ) = (name_value.path.is_ident(key), &name_value.value)
+        {
+          return Ok(Some(value.value()));
+        }
+      }
+    }
+  }
+  Ok(None)
+}
+
+/// Returns whether the `#[serde(<key>)]` attribute is present.
+fn serde_flag(attrs: &[Attribute], key: &str) -> syn::Result<bool> {
+  for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+    let metas = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+    if metas
+      .iter()
+      .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident(key)))
+    {
+      return Ok(true);
+    }
+  }
+  Ok(false)
+}
+
+/// Renames the snake case field `name` according to serde's `rename_all` rule.
+fn rename(name: &str, rule: Option<&str>, span: &syn::Ident) -> syn::Result<String> {
+  let name = name.trim_start_matches("r#");
+  let pascal_case = || {
+    name
+      .split('_')
+      .map(|word| {
+        let mut chars = word.chars();
+        chars
+          .next()
+          .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+          .unwrap_or_default()
+      })
+      .collect::<String>()
+  };
+  let renamed = match rule {
+    None | Some("snake_case") => name.to_owned(),
+    Some("lowercase") => name.to_lowercase(),
+    Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => name.to_uppercase(),
+    Some("PascalCase") => pascal_case(),
+    Some("camelCase") => {
+      let pascal_case = pascal_case();
+      let mut chars = pascal_case.chars();
+      chars
+        .next()
+        .map(|first| first.to_lowercase().chain(chars).collect())
+        .unwrap_or_default()
+    }
+    Some("kebab-case") => name.replace('_', "-"),
+    Some("SCREAMING-KEBAB-CASE") => name.to_uppercase().replace('_', "-"),
+    Some(rule) => {
+      return Err(syn::Error::new_spanned(
+        span,
+        format!("unsupported `rename_all` rule \"{rule}\""),
+      ))
+    }
+  };
+
+  Ok(renamed)
+}
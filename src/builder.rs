@@ -2,35 +2,69 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
 
 use anyhow::Context as _;
 use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::Serialize;
 use serde_json::Value;
 
 use crate::jwt::Jwt;
+use crate::type_metadata::pointer_segments;
+use crate::ClaimDisclosability;
+use crate::ClaimMetadata;
+use crate::ConcealmentPlan;
 use crate::Disclosure;
+use crate::DynJwsSigner;
 use crate::Error;
 use crate::Hasher;
 use crate::JsonObject;
+use crate::JwsAlgorithm;
+use crate::JwsHeader;
+use crate::JwsSignature;
 use crate::JwsSigner;
+use crate::JwsSignerSync;
 use crate::RequiredKeyBinding;
+use crate::ResolvedTypeMetadata;
 use crate::Result;
+use crate::SaltGenerator;
+use crate::SaltRng;
 use crate::SdJwt;
 use crate::SdJwtClaims;
+use crate::SdObjectDecoder;
 use crate::SdObjectEncoder;
 #[cfg(feature = "sha")]
 use crate::Sha256Hasher;
+#[cfg(feature = "status-list")]
+use crate::StatusListReference;
+#[cfg(feature = "status-list")]
+use crate::StatusListStorage;
+use crate::ARRAY_DIGEST_KEY;
 use crate::DEFAULT_SALT_SIZE;
+use crate::DIGESTS_KEY;
 use crate::HEADER_TYP;
 
 /// Builder structure to create an issuable SD-JWT.
 #[derive(Debug)]
 pub struct SdJwtBuilder<H> {
   encoder: SdObjectEncoder<H>,
-  header: JsonObject,
+  header: JwsHeader,
   disclosures: Vec<Disclosure>,
   key_bind: Option<RequiredKeyBinding>,
+  concealed_paths: Vec<String>,
+  non_concealable_paths: Vec<String>,
+  /// Whether a status was allocated, whose `status` claim must then stay disclosed.
+  allocated_status: bool,
+  salts: HashMap<String, String>,
+  decoys: Vec<(String, usize)>,
+  decoy_policy: Option<DecoyPolicy>,
+  shuffle_disclosures: bool,
+  timestamp_obfuscation: Option<TimestampObfuscation>,
+  claim_rules: Vec<ClaimMetadata>,
 }
 
 #[cfg(feature = "sha")]
@@ -58,7 +92,16 @@ impl<H: Hasher> SdJwtBuilder<H> {
       encoder,
       disclosures: vec![],
       key_bind: None,
-      header: JsonObject::default(),
+      header: JwsHeader::default(),
+      concealed_paths: vec![],
+      non_concealable_paths: DEFAULT_NON_CONCEALABLE_PATHS.iter().map(ToString::to_string).collect(),
+      allocated_status: false,
+      salts: HashMap::new(),
+      decoys: vec![],
+      decoy_policy: None,
+      shuffle_disclosures: false,
+      timestamp_obfuscation: None,
+      claim_rules: vec![],
     })
   }
 
@@ -87,22 +130,180 @@ impl<H: Hasher> SdJwtBuilder<H> {
   ///   .make_concealable("/claim2/0").unwrap(); //conceals "val_1"
   /// ```
   /// 
+  /// ## Notes
+  /// - Values are only concealed by [`SdJwtBuilder::finish`], deepest first: a value and the values it contains can
+  ///   be made concealable in any order.
+  /// - A `*` segment is a wildcard matching every property of an object or element of an array, e.g.
+  ///   `/nationalities/*` or `/addresses/*/street`. Values lacking the segments following a wildcard are skipped, and
+  ///   so are the values that must not be concealed, see [`SdJwtBuilder::non_concealable_paths`].
+  ///
   /// ## Error
   /// * [`Error::InvalidPath`] if pointer is invalid.
+  /// * [`Error::NonConcealableClaim`] if the value must not be concealed, see
+  ///   [`SdJwtBuilder::non_concealable_paths`].
   /// * [`Error::DataTypeMismatch`] if existing SD format is invalid.
   pub fn make_concealable(mut self, path: &str) -> Result<Self> {
-    let disclosure = self.encoder.conceal(path)?;
-    self.disclosures.push(disclosure);
+    if path.split('/').any(|segment| segment == WILDCARD) {
+      let mut paths = vec![];
+      expand_wildcards(
+        &self.encoder.object,
+        path.split('/').skip(1),
+        String::new(),
+        false,
+        &mut paths,
+      )
+      .map_err(|_| Error::InvalidPath(path.to_string()))?;
+      paths.retain(|path| !self.is_non_concealable(path));
+      for path in paths {
+        self.mark_concealable(&path)?;
+      }
+      return Ok(self);
+    }
+    self.mark_concealable(path)?;
 
     Ok(self)
   }
 
+  /// Records the value at `path` as concealable, treating every segment literally.
+  fn mark_concealable(&mut self, path: &str) -> Result<()> {
+    if path.is_empty() || self.encoder.object.pointer(path).is_none() {
+      return Err(Error::InvalidPath(path.to_string()));
+    }
+    self.check_concealable(path)?;
+    if !self.concealed_paths.iter().any(|concealed| concealed == path) {
+      self.concealed_paths.push(path.to_owned());
+    }
+
+    Ok(())
+  }
+
+  /// Substitutes a value with the digest of its disclosure like [`SdJwtBuilder::make_concealable`] does, using
+  /// `salt` instead of a random one, e.g. to reproduce the examples of the specification.
+  ///
+  /// ## Notes
+  /// - Salts must be unpredictable for disclosures to stay hidden: only use known salts for testing.
+  ///
+  /// ## Error
+  /// [`Error::InvalidPath`] if pointer is invalid or contains wildcards.
+  pub fn make_concealable_with_salt(mut self, path: &str, salt: impl Into<String>) -> Result<Self> {
+    if path.split('/').any(|segment| segment == WILDCARD) {
+      return Err(Error::InvalidPath(path.to_string()));
+    }
+    self = self.make_concealable(path)?;
+    self.salts.insert(path.to_owned(), salt.into());
+
+    Ok(self)
+  }
+
+  /// Makes concealable every value for which `predicate`, given the value's JSON pointer and the value itself,
+  /// returns `true`. Values that must not be concealed, see [`SdJwtBuilder::non_concealable_paths`], are skipped.
+  ///
+  /// ## Example
+  /// ```rust
+  /// use sd_jwt_payload::json;
+  /// use sd_jwt_payload::SdJwtBuilder;
+  ///
+  /// let builder = SdJwtBuilder::new(json!({ "personal": { "name": "Alice", "age": 42 } }))
+  ///   .unwrap()
+  ///   // Conceals every string leaf under `/personal`.
+  ///   .make_concealable_where(|path, value| path.starts_with("/personal/") && value.is_string());
+  /// ```
+  pub fn make_concealable_where<F>(mut self, predicate: F) -> Self
+  where
+    F: Fn(&str, &Value) -> bool,
+  {
+    let mut paths = vec![];
+    collect_descendant_paths(&self.encoder.object, "", &mut paths);
+    for path in paths {
+      let value = self.encoder.object.pointer(&path).expect("path was just collected");
+      if predicate(&path, value) && !self.is_non_concealable(&path) && !self.concealed_paths.contains(&path) {
+        self.concealed_paths.push(path);
+      }
+    }
+
+    self
+  }
+
+  /// Makes the value at `path` concealable, together with every property and array element it contains, so that
+  /// each of them can be disclosed individually.
+  ///
+  /// Use `path` = "" to make every claim of the object recursively concealable. Contained values that must not be
+  /// concealed, see [`SdJwtBuilder::non_concealable_paths`], are skipped.
+  ///
+  /// ## Error
+  /// * [`Error::InvalidPath`] if pointer is invalid.
+  /// * [`Error::NonConcealableClaim`] if the value at `path` must not be concealed.
+  pub fn make_concealable_recursive(mut self, path: &str) -> Result<Self> {
+    let value = self
+      .encoder
+      .object
+      .pointer(path)
+      .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+    let mut paths = vec![];
+    collect_descendant_paths(value, path, &mut paths);
+    if !path.is_empty() {
+      self.mark_concealable(path)?;
+    }
+    paths.retain(|path| !self.is_non_concealable(path));
+    for path in paths {
+      self.mark_concealable(&path)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Makes concealable the claims listed by `plan` and adds its decoys, see
+  /// [`SelectivelyDisclosable`](crate::SelectivelyDisclosable).
+  ///
+  /// ## Notes
+  /// - Claims of the plan missing from the object, e.g. skipped `None` fields, are ignored.
+  ///
+  /// ## Error
+  /// Any error returned by [`SdJwtBuilder::make_concealable`] or [`SdJwtBuilder::add_decoys`].
+  pub fn apply_plan(mut self, plan: &ConcealmentPlan) -> Result<Self> {
+    for path in plan.paths() {
+      if self.encoder.object.pointer(path).is_some() {
+        self = self.make_concealable(path)?;
+      }
+    }
+    for (path, number_of_decoys) in plan.decoys() {
+      self = self.add_decoys(path, *number_of_decoys)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Sets the JSON pointers, possibly containing wildcards, to the values that must not be made concealable, which
+  /// default to [`DEFAULT_NON_CONCEALABLE_PATHS`].
+  ///
+  /// ## Notes
+  /// - The values containing, or contained in, a non-concealable value can't be made concealable either.
+  /// - Claims named `_sd` or `...`, which are reserved, can never be made concealable.
+  pub fn non_concealable_paths<I, P>(mut self, paths: I) -> Self
+  where
+    I: IntoIterator<Item = P>,
+    P: Into<String>,
+  {
+    self.non_concealable_paths = paths.into_iter().map(Into::into).collect();
+    self
+  }
+
+  /// Sets the source of the random data used for salts and decoys, which defaults to
+  /// [`ThreadRngSaltGenerator`](crate::ThreadRngSaltGenerator).
+  ///
+  /// ## Notes
+  /// Use a [`SeededSaltGenerator`](crate::SeededSaltGenerator) to issue reproducible SD-JWTs, e.g. in tests.
+  pub fn salt_generator<G: SaltGenerator + 'static>(mut self, salt_generator: G) -> Self {
+    self.encoder.set_salt_generator(salt_generator);
+    self
+  }
+
   /// Sets the JWT header.
   /// ## Notes
   /// - if [`SdJwtBuilder::header`] is not called, the default header is used: ```json { "typ": "sd-jwt", "alg":
   ///   "<algorithm used in SdJwtBuilder::finish>" } ```
   /// - `alg` is always replaced with the value passed to [`SdJwtBuilder::finish`].
-  pub fn header(mut self, header: JsonObject) -> Self {
+  pub fn header(mut self, header: JwsHeader) -> Self {
     self.header = header;
     self
   }
@@ -125,14 +326,101 @@ impl<H: Hasher> SdJwtBuilder<H> {
     Ok(self)
   }
 
+  /// Returns the object being built, before any of its values is concealed.
+  pub(crate) fn claims(&self) -> &JsonObject {
+    self
+      .encoder
+      .object
+      .as_object()
+      .expect("encoder::object is a JSON Object")
+  }
+
   /// Adds a decoy digest to the specified path.
   ///
   /// `path` indicates the pointer to the value that will be concealed using the syntax of
   /// [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901).
   ///
   /// Use `path` = "" to add decoys to the top level.
+  ///
+  /// ## Notes
+  /// - Decoys are only generated by [`SdJwtBuilder::finish`], before concealing any value.
+  ///
+  /// ## Error
+  /// [`Error::InvalidPath`] if pointer is invalid or doesn't point to an object or an array.
   pub fn add_decoys(mut self, path: &str, number_of_decoys: usize) -> Result<Self> {
-    self.encoder.add_decoys(path, number_of_decoys)?;
+    if !self
+      .encoder
+      .object
+      .pointer(path)
+      .is_some_and(|value| value.is_object() || value.is_array())
+    {
+      return Err(Error::InvalidPath(path.to_string()));
+    }
+    self.decoys.push((path.to_owned(), number_of_decoys));
+
+    Ok(self)
+  }
+
+  /// Sets the policy used to add decoy digests when finishing, to every object and array containing digests, on top
+  /// of the decoys added with [`SdJwtBuilder::add_decoys`].
+  pub fn decoy_policy(mut self, policy: DecoyPolicy) -> Self {
+    self.decoy_policy = Some(policy);
+    self
+  }
+
+  /// Shuffles the disclosures of the SD-JWT when finishing, so that their order doesn't reveal the order in which
+  /// the claims were concealed. The source of randomness is the builder's [`SaltGenerator`].
+  pub fn shuffle_disclosures(mut self) -> Self {
+    self.shuffle_disclosures = true;
+    self
+  }
+
+  /// Blurs the `iat`, `nbf` and `exp` claims when finishing, e.g. so that they can't link the copies issued with
+  /// [`SdJwtBuilder::finish_batch`].
+  pub fn obfuscate_timestamps(mut self, obfuscation: TimestampObfuscation) -> Self {
+    self.timestamp_obfuscation = Some(obfuscation);
+    self
+  }
+
+  /// Allocates an entry of the status list published at `uri` through `storage`, and references it in the `status`
+  /// claim as `status.status_list`.
+  ///
+  /// ## Notes
+  /// The `status` claim can't be made concealable from then on, whatever the
+  /// [`SdJwtBuilder::non_concealable_paths`], so that holders can't hide the credential's status.
+  ///
+  /// ## Error
+  /// Returns [`Error::StatusListFailure`] if `storage` fails to allocate an entry, [`Error::DataTypeMismatch`] if a
+  /// `status` claim that isn't a JSON object was already set, and [`Error::NonConcealableClaim`] if the `status`
+  /// claim was already made concealable.
+  #[cfg(feature = "status-list")]
+  pub async fn allocate_status<S>(mut self, storage: &S, uri: &str) -> Result<Self>
+  where
+    S: StatusListStorage,
+  {
+    self.allocated_status = true;
+    if let Some(path) = self.concealed_paths.iter().find(|path| self.is_non_concealable(path)) {
+      return Err(Error::NonConcealableClaim(path.clone()));
+    }
+    let idx = storage
+      .allocate_index(uri)
+      .await
+      .map_err(|e| Error::StatusListFailure(e.to_string()))?;
+    let status_list = serde_json::to_value(StatusListReference {
+      idx,
+      uri: uri.to_owned(),
+    })
+    .map_err(|e| Error::DeserializationError(e.to_string()))?;
+    self
+      .encoder
+      .object
+      .as_object_mut()
+      .expect("encoder::object is a JSON Object")
+      .entry("status")
+      .or_insert_with(|| Value::Object(JsonObject::default()))
+      .as_object_mut()
+      .ok_or_else(|| Error::DataTypeMismatch("the `status` claim must be a JSON object".to_string()))?
+      .insert("status_list".to_string(), status_list);
 
     Ok(self)
   }
@@ -146,53 +434,513 @@ impl<H: Hasher> SdJwtBuilder<H> {
     self
   }
 
+  /// Enforces the selective disclosability rules (`sd`) of the claims described by `metadata` when finishing.
+  ///
+  /// Claims marked `always` must have been made concealable with [`SdJwtBuilder::make_concealable`], while claims
+  /// marked `never` must not have been.
+  pub fn type_metadata(mut self, metadata: &ResolvedTypeMetadata) -> Self {
+    self.claim_rules = metadata.claims().into_iter().cloned().collect();
+    self
+  }
+
   /// Creates an SD-JWT with the provided data.
-  pub async fn finish<S>(self, signer: &S, alg: &str) -> Result<SdJwt>
+  /// ## Error
+  /// Returns [`Error::DisclosabilityViolation`] if the claims don't comply with the rules set by
+  /// [`SdJwtBuilder::type_metadata`].
+  pub async fn finish<S>(self, signer: &S, alg: JwsAlgorithm) -> Result<SdJwt>
+  where
+    S: JwsSigner,
+  {
+    let (mut header, object, disclosures) = self.into_parts()?;
+    header.alg = Some(alg);
+
+    let jws = sign(signer, &header, &object).await?;
+    assemble(header, object, jws, disclosures)
+  }
+
+  /// Creates an SD-JWT with the provided data, signed by a blocking `signer`.
+  /// ## Error
+  /// See [`SdJwtBuilder::finish`].
+  pub fn finish_sync<S>(self, signer: &S, alg: JwsAlgorithm) -> Result<SdJwt>
+  where
+    S: JwsSignerSync,
+  {
+    let (mut header, object, disclosures) = self.into_parts()?;
+    header.alg = Some(alg);
+
+    let jws = sign_sync(signer, &header, &object)?;
+    assemble(header, object, jws, disclosures)
+  }
+
+  /// Creates one SD-JWT for each key of `holder_keys`, bound to it, e.g. to provide a holder with one-time-use
+  /// copies of a credential. Every copy is concealed with its own salts and decoys, so that they can't be linked to
+  /// each other, and all copies are signed concurrently.
+  ///
+  /// ## Notes
+  /// - The key set with [`SdJwtBuilder::require_key_binding`], if any, is replaced by the holder keys.
+  /// - Use [`SdJwtBuilder::obfuscate_timestamps`] so that the copies' timestamps can't link them either.
+  ///
+  /// ## Errors
+  /// - [`Error::DataTypeMismatch`] is returned if salts were set with [`SdJwtBuilder::make_concealable_with_salt`], as
+  ///   every copy would share them.
+  /// - Any error returned by [`SdJwtBuilder::finish`].
+  pub async fn finish_batch<S>(
+    mut self,
+    signer: &S,
+    alg: JwsAlgorithm,
+    holder_keys: impl IntoIterator<Item = RequiredKeyBinding>,
+  ) -> Result<Vec<SdJwt>>
   where
     S: JwsSigner,
   {
-    let SdJwtBuilder {
-      mut encoder,
-      disclosures,
-      key_bind,
-      mut header,
-    } = self;
-    encoder.add_sd_alg_property();
-    let mut object = encoder.object;
+    if !self.salts.is_empty() {
+      return Err(Error::DataTypeMismatch(
+        "explicit salts cannot be used to issue unlinkable copies".to_string(),
+      ));
+    }
+    let mut copies = vec![];
+    for key_bind in holder_keys {
+      self.key_bind = Some(key_bind);
+      let (mut header, object, disclosures) = self.make_parts()?;
+      header.alg = Some(alg.clone());
+      copies.push((header, object, disclosures));
+    }
+
+    let signatures = copies.iter().map(|(header, object, _)| sign(signer, header, object));
+    let jwss = futures::future::try_join_all(signatures).await?;
+    copies
+      .into_iter()
+      .zip(jwss)
+      .map(|((header, object, disclosures), jws)| assemble(header, object, jws, disclosures))
+      .collect()
+  }
+
+  /// Creates an SD-JWT signed by every signer in `signers`, meant to be serialized with
+  /// [`JwsJsonSerialization::General`](crate::JwsJsonSerialization::General).
+  ///
+  /// Each signer is given this builder's header extended with the accompanying parameters, which must include
+  /// `alg` and will usually include a `kid`. The first signature becomes the issuer-signed JWT's one. Signers of
+  /// different types can be mixed, as any [`JwsSigner`] is a [`DynJwsSigner`].
+  ///
+  /// ## Notes
+  /// `typ`, `crit` and the parameters of this builder's header - but `alg` - are shared by every signature, and
+  /// can't be set by the accompanying parameters.
+  ///
+  /// ## Errors
+  /// - [`Error::DataTypeMismatch`] is returned if no signers are given, if a signer's parameters lack `alg`, or if they
+  ///   override a shared parameter.
+  /// - [`Error::JwsSignerFailure`] is returned if signing fails or if the signers encoded the payload differently.
+  /// - [`Error::DisclosabilityViolation`] is returned if the claims don't comply with the rules set by
+  ///   [`SdJwtBuilder::type_metadata`].
+  pub async fn finish_multi_signed(self, signers: &[(&dyn DynJwsSigner, JwsHeader)]) -> Result<SdJwt> {
+    if signers.is_empty() {
+      return Err(Error::DataTypeMismatch("at least one signer is required".to_string()));
+    }
+    let (header, object, disclosures) = self.into_parts()?;
+
+    let mut jwt: Option<Jwt<SdJwtClaims>> = None;
+    let mut additional_signatures = vec![];
+    for (signer, parameters) in signers {
+      if parameters.alg.is_none() {
+        return Err(Error::DataTypeMismatch(
+          "invalid header: every signer requires an \"alg\"".to_string(),
+        ));
+      }
+      let mut signer_header = header.to_json_object();
+      let parameters = parameters.to_json_object();
+      if let Some(name) = parameters
+        .keys()
+        .find(|name| ["typ", "crit"].contains(&name.as_str()) || (*name != "alg" && signer_header.contains_key(*name)))
+      {
+        return Err(Error::DataTypeMismatch(format!(
+          "invalid header: \"{name}\" is shared by every signature and can't be set per signer"
+        )));
+      }
+      signer_header.extend(parameters);
+      let signer_header = JwsHeader::try_from(signer_header)?;
+
+      let jws = into_jws(signer.sign_dyn(&signer_header.to_json_object(), &object).await)?;
+      let (payload, signature) =
+        JwsSignature::from_compact(&jws).map_err(|e| Error::JwsSignerFailure(e.to_string()))?;
+      match &jwt {
+        None => {
+          let claims = serde_json::from_value::<SdJwtClaims>(Value::Object(object.clone()))
+            .map_err(|e| Error::DeserializationError(format!("invalid SD-JWT claims: {e}")))?;
+          jwt = Some(Jwt {
+            header: signer_header,
+            claims,
+            jws,
+          });
+        }
+        Some(jwt) if JwsSignature::from_compact(&jwt.jws)?.0 == payload => additional_signatures.push(signature),
+        Some(_) => {
+          return Err(Error::JwsSignerFailure(
+            "signers encoded the payload differently".to_string(),
+          ))
+        }
+      }
+    }
+    let jwt = jwt.expect("there is at least one signer");
+
+    Ok(SdJwt::new(jwt, disclosures, None).with_additional_signatures(additional_signatures))
+  }
+
+  /// Finalizes the object to sign and the header shared by all signatures, which is still lacking `alg`.
+  fn into_parts(mut self) -> Result<(JwsHeader, JsonObject, Vec<Disclosure>)> {
+    self.make_parts()
+  }
+
+  /// Finalizes the object to sign and the header shared by all signatures, which is still lacking `alg`.
+  ///
+  /// The builder's object is left untouched, so that it can be concealed again with fresh salts and decoys.
+  fn make_parts(&mut self) -> Result<(JwsHeader, JsonObject, Vec<Disclosure>)> {
+    let object = self.encoder.object.clone();
+    let parts = self.conceal_object();
+    self.encoder.object = object;
+    self.disclosures.clear();
+
+    parts
+  }
+
+  /// Adds decoys to the builder's object and conceals its values, before finalizing it.
+  fn conceal_object(&mut self) -> Result<(JwsHeader, JsonObject, Vec<Disclosure>)> {
+    for (path, number_of_decoys) in &self.decoys {
+      self.encoder.add_decoys(path, *number_of_decoys)?;
+    }
+    self.conceal_paths()?;
+    self.apply_decoy_policy()?;
+    self.check_disclosability()?;
+    if self.shuffle_disclosures {
+      self
+        .disclosures
+        .shuffle(&mut SaltRng(self.encoder.salt_generator.as_mut()));
+    }
+    self.encoder.add_sd_alg_property();
+    let mut object = self.claims().clone();
+    self.obfuscate_timestamps_of(&mut object);
     // Add key binding requirement as `cnf`.
-    if let Some(key_bind) = key_bind {
+    if let Some(key_bind) = &self.key_bind {
       let key_bind = serde_json::to_value(key_bind).map_err(|e| Error::DeserializationError(e.to_string()))?;
-      object
-        .as_object_mut()
-        .expect("encoder::object is a JSON Object")
-        .insert("cnf".to_string(), key_bind);
+      object.insert("cnf".to_string(), key_bind);
     }
 
     // Check mandatory header properties or insert them.
-    if let Some(Value::String(typ)) = header.get("typ") {
+    let mut header = self.header.clone();
+    if let Some(typ) = &header.typ {
       if !typ.split('+').contains(&HEADER_TYP) {
         return Err(Error::DataTypeMismatch(
           "invalid header: \"typ\" must contain type \"sd-jwt\"".to_string(),
         ));
       }
     } else {
-      header.insert("typ".to_string(), Value::String(HEADER_TYP.to_string()));
+      header.typ = Some(HEADER_TYP.to_string());
     }
-    header.insert("alg".to_string(), Value::String(alg.to_string()));
 
-    let jws = signer
-      .sign(&header, object.as_object().expect("encoder::object is a JSON Object"))
-      .await
-      .map_err(|e| anyhow::anyhow!("jws failed: {e}"))
-      .and_then(|jws_bytes| String::from_utf8(jws_bytes).context("invalid JWS"))
-      .map_err(|e| Error::JwsSignerFailure(e.to_string()))?;
+    Ok((header, object, std::mem::take(&mut self.disclosures)))
+  }
+
+  /// Blurs the timestamps of `object` according to [`SdJwtBuilder::obfuscate_timestamps`].
+  fn obfuscate_timestamps_of(&mut self, object: &mut JsonObject) {
+    let Some(obfuscation) = self.timestamp_obfuscation else {
+      return;
+    };
+    let jitter = match obfuscation {
+      TimestampObfuscation::Jitter(max) => SaltRng(self.encoder.salt_generator.as_mut()).gen_range(0..=i64::from(max)),
+      TimestampObfuscation::Round(_) => 0,
+    };
+    for claim in ["iat", "nbf", "exp"] {
+      let Some(timestamp) = object.get(claim).and_then(Value::as_i64) else {
+        continue;
+      };
+      let timestamp = match obfuscation {
+        TimestampObfuscation::Round(seconds) if seconds > 0 => timestamp - timestamp.rem_euclid(i64::from(seconds)),
+        TimestampObfuscation::Round(_) => timestamp,
+        TimestampObfuscation::Jitter(_) => timestamp - jitter,
+      };
+      object.insert(claim.to_string(), timestamp.into());
+    }
+  }
+
+  /// Conceals the values made concealable, deepest first so that the values contained in a concealed value are
+  /// concealed beforehand.
+  fn conceal_paths(&mut self) -> Result<()> {
+    let mut paths = self.concealed_paths.clone();
+    for path in &paths {
+      self.check_concealable(path)?;
+    }
+    paths.sort_by_key(|path| std::cmp::Reverse(pointer_segments(path).len()));
+    for path in paths {
+      let disclosure = match self.salts.get(&path) {
+        Some(salt) => self.encoder.conceal_with_salt(&path, salt.clone())?,
+        None => self.encoder.conceal(&path)?,
+      };
+      self.disclosures.push(disclosure);
+    }
+
+    Ok(())
+  }
+
+  /// Adds the decoy digests required by the [`DecoyPolicy`], if any.
+  fn apply_decoy_policy(&mut self) -> Result<()> {
+    let Some(policy) = self.decoy_policy.clone() else {
+      return Ok(());
+    };
+    let mut containers = vec![];
+    collect_digest_containers(&self.encoder.object, "", &mut containers);
+    for (path, digests) in containers {
+      let number_of_decoys = match &policy {
+        DecoyPolicy::Bucket(size) if *size > 0 => (size - digests % size) % size,
+        DecoyPolicy::Random(range) if !range.is_empty() => {
+          SaltRng(self.encoder.salt_generator.as_mut()).gen_range(range.clone())
+        }
+        DecoyPolicy::Fixed(number_of_decoys) => *number_of_decoys,
+        DecoyPolicy::Bucket(_) | DecoyPolicy::Random(_) => 0,
+      };
+      self.encoder.add_decoys(&path, number_of_decoys)?;
+    }
+
+    Ok(())
+  }
+
+  /// Returns whether the value at `path` must not be concealed: because its name, or the name of a value containing
+  /// it, is reserved, or because it's related to one of the [`SdJwtBuilder::non_concealable_paths`] or to an
+  /// allocated status.
+  fn is_non_concealable(&self, path: &str) -> bool {
+    let segments = pointer_segments(path);
+    segments
+      .iter()
+      .any(|segment| segment == DIGESTS_KEY || segment == ARRAY_DIGEST_KEY)
+      || self
+        .non_concealable_paths
+        .iter()
+        .map(String::as_str)
+        .chain(self.allocated_status.then_some("/status"))
+        .any(|pattern| {
+          pointer_segments(pattern)
+            .iter()
+            .zip(&segments)
+            .all(|(pattern, segment)| pattern == WILDCARD || pattern == segment)
+        })
+  }
+
+  fn check_concealable(&self, path: &str) -> Result<()> {
+    if self.is_non_concealable(path) {
+      return Err(Error::NonConcealableClaim(path.to_string()));
+    }
+    Ok(())
+  }
+
+  /// Checks the concealed claims against the rules set by [`SdJwtBuilder::type_metadata`].
+  fn check_disclosability(&self) -> Result<()> {
+    if self.claim_rules.is_empty() {
+      return Ok(());
+    }
+    let is_concealed = |pointer: &str| {
+      let segments = pointer_segments(pointer);
+      self
+        .concealed_paths
+        .iter()
+        .any(|path| pointer_segments(path) == segments)
+    };
 
-    let claims = serde_json::from_value::<SdJwtClaims>(object)
-      .map_err(|e| Error::DeserializationError(format!("invalid SD-JWT claims: {e}")))?;
-    let jwt = Jwt { header, claims, jws };
+    let disclosures: HashMap<String, Disclosure> = self
+      .disclosures
+      .iter()
+      .map(|disclosure| {
+        (
+          self.encoder.hasher.encoded_digest(disclosure.as_str()),
+          disclosure.clone(),
+        )
+      })
+      .collect();
+    let object = SdObjectDecoder.decode(self.claims(), &disclosures)?;
+    let object = Value::Object(object);
 
-    Ok(SdJwt::new(jwt, disclosures, None))
+    for rule in &self.claim_rules {
+      match rule.sd {
+        ClaimDisclosability::Always => {
+          if let Some(pointer) = rule.select(&object).into_iter().find(|pointer| !is_concealed(pointer)) {
+            return Err(Error::DisclosabilityViolation(format!(
+              "claim \"{pointer}\" must be selectively disclosable"
+            )));
+          }
+        }
+        ClaimDisclosability::Never => {
+          if let Some(path) = self.concealed_paths.iter().find(|path| rule.selects(path)) {
+            return Err(Error::DisclosabilityViolation(format!(
+              "claim \"{path}\" must not be selectively disclosable"
+            )));
+          }
+        }
+        ClaimDisclosability::Allowed => (),
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// The JSON pointers to the claims that must not be made concealable by default: `_sd_alg` and `cnf`, which are
+/// needed to process the SD-JWT, and `status`, which holders must not be able to hide. See
+/// [`SdJwtBuilder::non_concealable_paths`].
+///
+/// ## Notes
+/// Claims like `iss` or `exp` are left out: SD-JWT lets issuers make them concealable, and profiles requiring them
+/// to be disclosed protect them on top of these, as [`SdJwtVcBuilder`](crate::SdJwtVcBuilder) does.
+pub const DEFAULT_NON_CONCEALABLE_PATHS: [&str; 3] = ["/_sd_alg", "/cnf", "/status"];
+
+/// How decoy digests are added to an SD-JWT, so that the number of digests doesn't reveal the number of concealed
+/// claims. See [`SdJwtBuilder::decoy_policy`].
+///
+/// The policy applies to every object with an `_sd` array and every array containing array element digests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecoyPolicy {
+  /// Pads the digests of every object or array to the next multiple of the given bucket size. A size of 0 adds no
+  /// decoys.
+  Bucket(usize),
+  /// Adds a random number of decoys, within the given bounds, to every object or array. An empty range adds no
+  /// decoys.
+  Random(RangeInclusive<usize>),
+  /// Adds the given number of decoys to every object or array.
+  Fixed(usize),
+}
+
+/// How the `iat`, `nbf` and `exp` claims are blurred, see [`SdJwtBuilder::obfuscate_timestamps`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampObfuscation {
+  /// Rounds the timestamps down to a multiple of the given number of seconds. A value of 0 leaves them untouched.
+  Round(u32),
+  /// Moves all the timestamps back by the same random number of seconds, up to the given one, which preserves the
+  /// validity period.
+  Jitter(u32),
+}
+
+/// Path segment matching every property of an object or element of an array.
+const WILDCARD: &str = "*";
+
+/// Appends to `paths` the JSON pointers matching the remaining `segments` of a path containing wildcards, starting
+/// from `value` found at `path`.
+///
+/// Fails if a segment preceding the first wildcard doesn't exist.
+fn expand_wildcards<'s>(
+  value: &Value,
+  mut segments: impl Iterator<Item = &'s str> + Clone,
+  path: String,
+  under_wildcard: bool,
+  paths: &mut Vec<String>,
+) -> std::result::Result<(), ()> {
+  let Some(segment) = segments.next() else {
+    paths.push(path);
+    return Ok(());
+  };
+  if segment == WILDCARD {
+    let mut children = vec![];
+    collect_children(value, &mut children);
+    for (child_segment, child) in children {
+      expand_wildcards(child, segments.clone(), format!("{path}/{child_segment}"), true, paths)?;
+    }
+    return Ok(());
+  }
+
+  let key = segment.replace("~1", "/").replace("~0", "~");
+  let child = match value {
+    Value::Object(object) => object.get(&key),
+    Value::Array(array) => key.parse::<usize>().ok().and_then(|index| array.get(index)),
+    _ => None,
+  };
+  match child {
+    Some(child) => expand_wildcards(child, segments, format!("{path}/{segment}"), under_wildcard, paths),
+    None if under_wildcard => Ok(()),
+    None => Err(()),
+  }
+}
+
+/// Appends the escaped JSON pointer segment of every property or element of `value`, together with its value.
+fn collect_children<'v>(value: &'v Value, children: &mut Vec<(String, &'v Value)>) {
+  match value {
+    Value::Object(object) => children.extend(
+      object
+        .iter()
+        .map(|(key, value)| (key.replace('~', "~0").replace('/', "~1"), value)),
+    ),
+    Value::Array(array) => children.extend(
+      array
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (index.to_string(), value)),
+    ),
+    _ => (),
+  }
+}
+
+/// Appends the JSON pointers to every property and array element contained in `value`, found at `path`.
+fn collect_descendant_paths(value: &Value, path: &str, paths: &mut Vec<String>) {
+  let mut children = vec![];
+  collect_children(value, &mut children);
+  for (segment, value) in children {
+    let path = format!("{path}/{segment}");
+    paths.push(path.clone());
+    collect_descendant_paths(value, &path, paths);
+  }
+}
+
+/// Appends the JSON pointer to every object with an `_sd` array and every array with array element digests contained
+/// in `value`, found at `path`, together with its number of digests.
+fn collect_digest_containers(value: &Value, path: &str, containers: &mut Vec<(String, usize)>) {
+  let digests = match value {
+    Value::Object(object) => object
+      .get(DIGESTS_KEY)
+      .and_then(Value::as_array)
+      .map(Vec::len)
+      .unwrap_or_default(),
+    Value::Array(array) => array
+      .iter()
+      .filter_map(Value::as_object)
+      .filter(|element| element.len() == 1 && element.contains_key(ARRAY_DIGEST_KEY))
+      .count(),
+    _ => 0,
+  };
+  if digests > 0 {
+    containers.push((path.to_owned(), digests));
   }
+
+  let mut children = vec![];
+  collect_children(value, &mut children);
+  for (segment, child) in children {
+    collect_digest_containers(child, &format!("{path}/{segment}"), containers);
+  }
+}
+
+/// Puts together the SD-JWT made of `object` signed as `jws`.
+fn assemble(header: JwsHeader, object: JsonObject, jws: String, disclosures: Vec<Disclosure>) -> Result<SdJwt> {
+  let claims = serde_json::from_value::<SdJwtClaims>(Value::Object(object))
+    .map_err(|e| Error::DeserializationError(format!("invalid SD-JWT claims: {e}")))?;
+  let jwt = Jwt { header, claims, jws };
+
+  Ok(SdJwt::new(jwt, disclosures, None))
+}
+
+/// Asks `signer` for a compact JWS of `object`.
+pub(crate) async fn sign<S>(signer: &S, header: &JwsHeader, object: &JsonObject) -> Result<String>
+where
+  S: JwsSigner,
+{
+  into_jws(signer.sign(&header.to_json_object(), object).await)
+}
+
+/// Asks the blocking `signer` for a compact JWS of `object`.
+pub(crate) fn sign_sync<S>(signer: &S, header: &JwsHeader, object: &JsonObject) -> Result<String>
+where
+  S: JwsSignerSync,
+{
+  into_jws(signer.sign_sync(&header.to_json_object(), object))
+}
+
+fn into_jws<E: Display>(signature: std::result::Result<Vec<u8>, E>) -> Result<String> {
+  signature
+    .map_err(|e| anyhow::anyhow!("jws failed: {e}"))
+    .and_then(|jws_bytes| String::from_utf8(jws_bytes).context("invalid JWS"))
+    .map_err(|e| Error::JwsSignerFailure(e.to_string()))
 }
 
 #[cfg(test)]
@@ -332,6 +1080,225 @@ mod test {
     }
   }
 
+  mod marking_properties_as_concealable_by_pattern {
+    use super::*;
+
+    #[test]
+    fn wildcards_match_every_array_element() {
+      let builder = SdJwtBuilder::new(json!({ "nationalities": ["US", "DE"] }))
+        .unwrap()
+        .make_concealable("/nationalities/*")
+        .unwrap();
+
+      assert_eq!(builder.concealed_paths, ["/nationalities/0", "/nationalities/1"]);
+    }
+
+    #[test]
+    fn wildcards_skip_values_lacking_the_following_segments() {
+      let builder = SdJwtBuilder::new(json!({
+        "addresses": [{ "street": "Main St" }, { "city": "Berlin" }, { "street": "High St" }]
+      }))
+      .unwrap()
+      .make_concealable("/addresses/*/street")
+      .unwrap();
+
+      assert_eq!(builder.concealed_paths, ["/addresses/0/street", "/addresses/2/street"]);
+    }
+
+    #[test]
+    fn wildcards_expand_properties_named_like_a_wildcard_once() {
+      let builder = SdJwtBuilder::new(json!({ "*": { "*": 1 } }))
+        .unwrap()
+        .make_concealable("/*")
+        .unwrap()
+        .make_concealable_recursive("")
+        .unwrap();
+
+      assert_eq!(builder.concealed_paths, ["/*", "/*/*"]);
+    }
+
+    #[test]
+    fn wildcards_require_an_existing_prefix() {
+      let result = SdJwtBuilder::new(json!({})).unwrap().make_concealable("/addresses/*");
+
+      assert_eq!(result.unwrap_err(), Error::InvalidPath("/addresses/*".to_string()));
+    }
+
+    #[test]
+    fn predicates_select_values_by_path_and_value() {
+      let builder = SdJwtBuilder::new(json!({
+        "id": "user_42",
+        "personal": { "name": "Alice", "age": 42, "address": { "city": "Berlin" } }
+      }))
+      .unwrap()
+      .make_concealable_where(|path, value| path.starts_with("/personal/") && value.is_string());
+      let mut paths = builder.concealed_paths;
+      paths.sort();
+
+      assert_eq!(paths, ["/personal/address/city", "/personal/name"]);
+    }
+  }
+
+  mod protecting_claims {
+    use super::*;
+
+    fn builder() -> SdJwtBuilder<Sha256Hasher> {
+      SdJwtBuilder::new(json!({
+        "iss": "https://issuer.example.com",
+        "cnf": { "jwk": { "kty": "EC" } },
+        "address": { "street": "Main St", "_sd": [] },
+        "nationalities": ["US", "DE"]
+      }))
+      .unwrap()
+    }
+
+    #[test]
+    fn key_bindings_are_protected_by_default() {
+      let result = builder().make_concealable("/cnf/jwk");
+
+      assert_eq!(result.unwrap_err(), Error::NonConcealableClaim("/cnf/jwk".to_string()));
+    }
+
+    #[test]
+    fn statuses_are_protected_by_default() {
+      let result =
+        SdJwtBuilder::new(json!({ "status": { "status_list": { "idx": 0, "uri": "https://example.com" } } }))
+          .unwrap()
+          .make_concealable("/status/status_list");
+
+      assert_eq!(
+        result.unwrap_err(),
+        Error::NonConcealableClaim("/status/status_list".to_string())
+      );
+    }
+
+    #[test]
+    fn reserved_claim_names_are_never_concealable() {
+      let result = builder()
+        .non_concealable_paths(Vec::<String>::new())
+        .make_concealable("/address/_sd");
+
+      assert_eq!(
+        result.unwrap_err(),
+        Error::NonConcealableClaim("/address/_sd".to_string())
+      );
+    }
+
+    #[test]
+    fn values_containing_protected_values_are_protected() {
+      let builder = builder().non_concealable_paths(["/address/street"]);
+
+      assert!(builder.make_concealable("/address").is_err());
+    }
+
+    #[test]
+    fn configured_wildcards_protect_every_match() {
+      let builder = builder().non_concealable_paths(["/nationalities/*"]);
+
+      assert!(builder.make_concealable("/nationalities/1").is_err());
+    }
+
+    #[test]
+    fn patterns_skip_protected_values() {
+      let builder = builder()
+        .non_concealable_paths(["/iss", "/cnf"])
+        .make_concealable("/*")
+        .unwrap()
+        .make_concealable_recursive("/nationalities")
+        .unwrap();
+
+      assert_eq!(
+        builder.concealed_paths,
+        ["/address", "/nationalities", "/nationalities/0", "/nationalities/1"]
+      );
+    }
+  }
+
+  mod applying_a_decoy_policy {
+    use super::*;
+    use crate::SeededSaltGenerator;
+
+    fn digest_counts(builder: SdJwtBuilder<Sha256Hasher>) -> (usize, usize) {
+      let (_, object, _) = builder.into_parts().unwrap();
+      let sd = object["_sd"].as_array().unwrap().len();
+      let nationalities = object["nationalities"].as_array().unwrap().len();
+      (sd, nationalities)
+    }
+
+    fn builder() -> SdJwtBuilder<Sha256Hasher> {
+      SdJwtBuilder::new(json!({ "name": "Alice", "email": "alice@example.com", "nationalities": ["US", "DE", "FR"] }))
+        .unwrap()
+        .make_concealable("/name")
+        .unwrap()
+        .make_concealable("/nationalities/0")
+        .unwrap()
+    }
+
+    #[test]
+    fn buckets_pad_digests_together_with_manual_decoys() {
+      let builder = builder()
+        .add_decoys("", 2)
+        .unwrap()
+        .decoy_policy(DecoyPolicy::Bucket(4));
+
+      // 1 digest and 2 manual decoys are padded to 4, and so is the array's digest, next to its 2 plaintext elements.
+      assert_eq!(digest_counts(builder), (4, 6));
+    }
+
+    #[test]
+    fn fixed_policies_add_decoys_to_every_container_with_digests() {
+      let builder = builder().decoy_policy(DecoyPolicy::Fixed(3));
+
+      assert_eq!(digest_counts(builder), (4, 6));
+    }
+
+    #[test]
+    fn random_policies_stay_within_bounds() {
+      for seed in 0..10 {
+        let builder = builder()
+          .salt_generator(SeededSaltGenerator::new(seed))
+          .decoy_policy(DecoyPolicy::Random(1..=3));
+        let (sd, nationalities) = digest_counts(builder);
+
+        assert!((2..=4).contains(&sd));
+        assert!((4..=6).contains(&nationalities));
+      }
+    }
+
+    #[test]
+    fn objects_and_arrays_without_digests_are_left_untouched() {
+      let builder = SdJwtBuilder::new(json!({ "name": "Alice", "address": { "country": "DE" }, "tags": ["a"] }))
+        .unwrap()
+        .make_concealable("/name")
+        .unwrap()
+        .decoy_policy(DecoyPolicy::Fixed(2));
+      let (_, object, _) = builder.into_parts().unwrap();
+
+      assert_eq!(object["_sd"].as_array().unwrap().len(), 3);
+      assert_eq!(object["address"], json!({ "country": "DE" }));
+      assert_eq!(object["tags"], json!(["a"]));
+    }
+  }
+
+  mod obfuscating_timestamps {
+    use super::*;
+
+    #[test]
+    fn jitter_moves_timestamps_back_preserving_the_validity_period() {
+      for _ in 0..10 {
+        let (_, object, _) = SdJwtBuilder::new(json!({ "iat": 1_700_000_000, "exp": 1_700_086_400 }))
+          .unwrap()
+          .obfuscate_timestamps(TimestampObfuscation::Jitter(600))
+          .into_parts()
+          .unwrap();
+        let iat = object["iat"].as_i64().unwrap();
+
+        assert!((1_699_999_400..=1_700_000_000).contains(&iat));
+        assert_eq!(object["exp"].as_i64().unwrap() - iat, 86_400);
+      }
+    }
+  }
+
   mod adding_decoys {
     use super::*;
 
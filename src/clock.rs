@@ -0,0 +1,42 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Debug;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Source of the current time used when validating time-dependent claims, e.g. `exp`, `nbf` and `iat`.
+pub trait Clock: Debug + Send + Sync {
+  /// Returns the current time as the number of seconds elapsed since the UNIX epoch, like JWT's `NumericDate`.
+  fn now(&self) -> i64;
+}
+
+/// A [`Clock`] backed by the system's time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> i64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_secs() as i64)
+      .unwrap_or_default()
+  }
+}
+
+/// A [`Clock`] that always returns the same time. Mostly useful for testing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(i64);
+
+impl FixedClock {
+  /// Creates a new [`FixedClock`] stopped at `timestamp`, expressed in seconds since the UNIX epoch.
+  pub fn new(timestamp: i64) -> Self {
+    Self(timestamp)
+  }
+}
+
+impl Clock for FixedClock {
+  fn now(&self) -> i64 {
+    self.0
+  }
+}
@@ -0,0 +1,304 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::cose::CoseSign1;
+use super::cose::HEADER_ALG;
+use super::cose::HEADER_TYP;
+use super::disclosure::MIN_SALT_SIZE;
+use super::hash_alg_id;
+use super::key_matches;
+use super::path_segments;
+use super::CborValue;
+use super::CoseSigner;
+use super::CwtDisclosure;
+use super::SdCwt;
+use super::CLAIM_CNF;
+use super::CNF_COSE_KEY;
+use super::HEADER_SD_ALG;
+use super::HEADER_SD_CLAIMS;
+use super::REDACTED_CLAIM_ELEMENT_TAG;
+use super::REDACTED_CLAIM_KEYS;
+use super::SD_CWT_TYP;
+use crate::Error;
+use crate::Hasher;
+use crate::Result;
+use crate::SaltGenerator;
+#[cfg(feature = "sha")]
+use crate::Sha256Hasher;
+use crate::ThreadRngSaltGenerator;
+
+/// Builder structure to create an issuable SD-CWT.
+#[derive(Debug)]
+pub struct SdCwtBuilder<H> {
+  claims: CborValue,
+  hasher: H,
+  salt_size: usize,
+  salt_generator: Box<dyn SaltGenerator>,
+  header: Vec<(CborValue, CborValue)>,
+  concealed_paths: Vec<String>,
+  decoys: Vec<(String, usize)>,
+  key_bind: Option<CborValue>,
+}
+
+#[cfg(feature = "sha")]
+impl SdCwtBuilder<Sha256Hasher> {
+  /// Creates a new [`SdCwtBuilder`] with `sha-256` hash function.
+  ///
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if `claims` is not a CBOR map.
+  pub fn new(claims: CborValue) -> Result<Self> {
+    Self::new_with_hasher(claims, Sha256Hasher::new())
+  }
+}
+
+impl<H: Hasher> SdCwtBuilder<H> {
+  /// Creates a new [`SdCwtBuilder`] with custom hash function to create digests.
+  pub fn new_with_hasher(claims: CborValue, hasher: H) -> Result<Self> {
+    Self::new_with_hasher_and_salt_size(claims, hasher, MIN_SALT_SIZE)
+  }
+
+  /// Creates a new [`SdCwtBuilder`] with custom hash function to create digests, and custom salt size.
+  ///
+  /// ## Error
+  /// - [`Error::DataTypeMismatch`] is returned if `claims` is not a CBOR map.
+  /// - [`Error::InvalidSaltSize`] is returned if `salt_size` is smaller than 16 bytes.
+  /// - [`Error::InvalidHasher`] is returned if `hasher` has no COSE algorithm identifier.
+  pub fn new_with_hasher_and_salt_size(claims: CborValue, hasher: H, salt_size: usize) -> Result<Self> {
+    if !matches!(claims, CborValue::Map(_)) {
+      return Err(Error::DataTypeMismatch(
+        "argument `claims` must be a CBOR map".to_string(),
+      ));
+    }
+    if salt_size < MIN_SALT_SIZE {
+      return Err(Error::InvalidSaltSize);
+    }
+    hash_alg_id(&hasher)?;
+
+    Ok(Self {
+      claims,
+      hasher,
+      salt_size,
+      salt_generator: Box::new(ThreadRngSaltGenerator),
+      header: vec![],
+      concealed_paths: vec![],
+      decoys: vec![],
+      key_bind: None,
+    })
+  }
+
+  /// Sets the source of the random data used for salts and decoys, which defaults to
+  /// [`ThreadRngSaltGenerator`].
+  ///
+  /// ## Notes
+  /// Use a [`SeededSaltGenerator`](crate::SeededSaltGenerator) to issue reproducible SD-CWTs, e.g. in tests.
+  pub fn salt_generator<G: SaltGenerator + 'static>(mut self, salt_generator: G) -> Self {
+    self.salt_generator = Box::new(salt_generator);
+    self
+  }
+
+  /// Substitutes a value with the digest of its disclosure.
+  ///
+  /// `path` indicates the pointer to the value that will be concealed using the syntax of
+  /// [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901), where integer map keys are addressed by their
+  /// decimal representation, e.g. `/501/0`.
+  ///
+  /// ## Notes
+  /// Values are only concealed by [`SdCwtBuilder::finish`], deepest first: a value and the values it contains can be
+  /// made concealable in any order.
+  ///
+  /// ## Error
+  /// Returns [`Error::InvalidPath`] if `path` doesn't point to an existing value.
+  pub fn make_concealable(mut self, path: &str) -> Result<Self> {
+    let segments = path_segments(path)?;
+    if segments.is_empty() || get_mut(&mut self.claims, &segments).is_none() {
+      return Err(Error::InvalidPath(path.to_string()));
+    }
+    if !self.concealed_paths.iter().any(|concealed| concealed == path) {
+      self.concealed_paths.push(path.to_owned());
+    }
+
+    Ok(self)
+  }
+
+  /// Adds a new protected header parameter, or replaces an existing one.
+  pub fn header(mut self, label: i64, value: CborValue) -> Self {
+    set_parameter(&mut self.header, label, value);
+    self
+  }
+
+  /// Adds decoy digests to the map or array at `path`. Use `path` = "" to add decoys to the top level.
+  ///
+  /// ## Error
+  /// Returns [`Error::InvalidPath`] if `path` doesn't point to an existing map or array.
+  pub fn add_decoys(mut self, path: &str, number_of_decoys: usize) -> Result<Self> {
+    let segments = if path.is_empty() { vec![] } else { path_segments(path)? };
+    if !matches!(
+      get_mut(&mut self.claims, &segments),
+      Some(CborValue::Map(_) | CborValue::Array(_))
+    ) {
+      return Err(Error::InvalidPath(path.to_string()));
+    }
+    self.decoys.push((path.to_owned(), number_of_decoys));
+
+    Ok(self)
+  }
+
+  /// Requires the holder to prove possession of `cose_key` through a [`super::KeyBindingToken`], by setting the
+  /// `cnf` claim.
+  pub fn require_key_binding(mut self, cose_key: CborValue) -> Self {
+    self.key_bind = Some(cose_key);
+    self
+  }
+
+  /// Creates an SD-CWT signed by `signer` with the COSE algorithm `alg`.
+  pub async fn finish<S>(mut self, signer: &S, alg: i64) -> Result<SdCwt>
+  where
+    S: CoseSigner,
+  {
+    let disclosures = self.conceal_claims()?;
+    let SdCwtBuilder {
+      mut claims,
+      hasher,
+      mut header,
+      key_bind,
+      ..
+    } = self;
+
+    // Add key binding requirement as `cnf`.
+    if let Some(cose_key) = key_bind {
+      let cnf = CborValue::Map(vec![(CborValue::from(CNF_COSE_KEY), cose_key)]);
+      let claims = claims.as_map_mut().expect("claims is a CBOR map");
+      claims.retain(|(key, _)| *key != CborValue::from(CLAIM_CNF));
+      claims.push((CborValue::from(CLAIM_CNF), cnf));
+    }
+
+    set_parameter(&mut header, HEADER_ALG, CborValue::from(alg));
+    set_parameter(&mut header, HEADER_SD_ALG, CborValue::from(hash_alg_id(&hasher)?));
+    if !header.iter().any(|(label, _)| *label == CborValue::from(HEADER_TYP)) {
+      header.push((CborValue::from(HEADER_TYP), CborValue::from(SD_CWT_TYP)));
+    }
+    let sd_claims = disclosures
+      .iter()
+      .map(|disclosure| CborValue::Bytes(disclosure.as_bytes().to_vec()))
+      .collect();
+    let unprotected_header = vec![(CborValue::from(HEADER_SD_CLAIMS), CborValue::Array(sd_claims))];
+
+    let sign1 = CoseSign1::sign(signer, header, unprotected_header, claims.to_vec()?).await?;
+    Ok(SdCwt::new(sign1, claims, disclosures))
+  }
+}
+
+impl<H: Hasher> SdCwtBuilder<H> {
+  /// Adds the decoys, then conceals the values made concealable, deepest first so that the values contained in a
+  /// concealed value are concealed beforehand.
+  fn conceal_claims(&mut self) -> Result<Vec<CwtDisclosure>> {
+    for (path, number_of_decoys) in std::mem::take(&mut self.decoys) {
+      let segments = if path.is_empty() { vec![] } else { path_segments(&path)? };
+      for _ in 0..number_of_decoys {
+        let salt = self.salt();
+        let digest = self.hasher.digest(&salt);
+        match get_mut(&mut self.claims, &segments) {
+          Some(CborValue::Map(map)) => add_redacted_key(map, digest)?,
+          Some(CborValue::Array(array)) => array.push(redacted_element(digest)),
+          _ => return Err(Error::InvalidPath(path.clone())),
+        }
+      }
+    }
+
+    let mut paths = std::mem::take(&mut self.concealed_paths);
+    paths.sort_by_key(|path| std::cmp::Reverse(path.split('/').count()));
+    paths
+      .iter()
+      .map(|path| {
+        let salt = self.salt();
+        self.conceal(path, salt)
+      })
+      .collect()
+  }
+
+  /// Replaces the value at `path` with the digest of its disclosure, salted with `salt`.
+  fn conceal(&mut self, path: &str, salt: Vec<u8>) -> Result<CwtDisclosure> {
+    let segments = path_segments(path)?;
+    let (element_key, parent_path) = segments
+      .split_last()
+      .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+
+    match get_mut(&mut self.claims, parent_path) {
+      Some(CborValue::Map(map)) => {
+        let index = map
+          .iter()
+          .position(|(key, _)| key_matches(key, element_key))
+          .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+        let (key, value) = map.remove(index);
+        let disclosure = CwtDisclosure::new(salt, Some(key), value)?;
+        add_redacted_key(map, disclosure.digest(&self.hasher))?;
+        Ok(disclosure)
+      }
+      Some(CborValue::Array(array)) => {
+        let element = element_key
+          .parse::<usize>()
+          .ok()
+          .and_then(|index| array.get_mut(index))
+          .ok_or_else(|| Error::InvalidPath(path.to_string()))?;
+        let disclosure = CwtDisclosure::new(salt, None, std::mem::replace(element, CborValue::Null))?;
+        *element = redacted_element(disclosure.digest(&self.hasher));
+        Ok(disclosure)
+      }
+      _ => Err(Error::InvalidPath(path.to_string())),
+    }
+  }
+
+  fn salt(&mut self) -> Vec<u8> {
+    let mut salt = vec![0; self.salt_size];
+    self.salt_generator.fill_bytes(&mut salt);
+    salt
+  }
+}
+
+/// Returns the value at `path`, addressing array elements by index and map entries by key.
+fn get_mut<'v, S: AsRef<str>>(value: &'v mut CborValue, path: &[S]) -> Option<&'v mut CborValue> {
+  path.iter().try_fold(value, |value, segment| match value {
+    CborValue::Map(map) => map
+      .iter_mut()
+      .find(|(key, _)| key_matches(key, segment.as_ref()))
+      .map(|(_, value)| value),
+    CborValue::Array(array) => segment
+      .as_ref()
+      .parse::<usize>()
+      .ok()
+      .and_then(|index| array.get_mut(index)),
+    _ => None,
+  })
+}
+
+/// Adds `digest` to the redacted claim keys of `map`, keeping them sorted.
+fn add_redacted_key(map: &mut Vec<(CborValue, CborValue)>, digest: Vec<u8>) -> Result<()> {
+  let Some((_, redacted)) = map.iter_mut().find(|(key, _)| *key == REDACTED_CLAIM_KEYS) else {
+    map.push((REDACTED_CLAIM_KEYS, CborValue::Array(vec![CborValue::Bytes(digest)])));
+    return Ok(());
+  };
+  let CborValue::Array(digests) = redacted else {
+    return Err(Error::DataTypeMismatch(
+      "invalid claims: existing redacted claim keys are not an array".to_string(),
+    ));
+  };
+  let index = digests
+    .iter()
+    .position(|value| value.as_bytes().is_some_and(|existing| existing > digest.as_slice()))
+    .unwrap_or(digests.len());
+  digests.insert(index, CborValue::Bytes(digest));
+
+  Ok(())
+}
+
+fn redacted_element(digest: Vec<u8>) -> CborValue {
+  CborValue::Tag(REDACTED_CLAIM_ELEMENT_TAG, Box::new(CborValue::Bytes(digest)))
+}
+
+fn set_parameter(header: &mut Vec<(CborValue, CborValue)>, label: i64, value: CborValue) {
+  let label = CborValue::from(label);
+  match header.iter_mut().find(|(key, _)| *key == label) {
+    Some((_, existing)) => *existing = value,
+    None => header.push((label, value)),
+  }
+}
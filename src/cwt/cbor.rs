@@ -0,0 +1,400 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use crate::Result;
+
+/// Maximum nesting of arrays, maps and tags accepted when decoding.
+const MAX_DEPTH: usize = 64;
+
+/// A [CBOR](https://www.rfc-editor.org/rfc/rfc8949.html) data item.
+///
+/// Unlike general purpose CBOR libraries, arbitrary simple values are supported, since SD-CWT uses `simple(59)` as
+/// the map key of redacted claims.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CborValue {
+  /// An integer in the range `-2^64..2^64`.
+  Integer(i128),
+  Bytes(Vec<u8>),
+  Text(String),
+  Array(Vec<CborValue>),
+  /// A map, whose entries are kept in insertion order.
+  Map(Vec<(CborValue, CborValue)>),
+  Tag(u64, Box<CborValue>),
+  Bool(bool),
+  Null,
+  /// A simple value other than `false`, `true` and `null`, i.e. in the range `0..=19`, `undefined` (23) or in the
+  /// range `32..=255`.
+  Simple(u8),
+  Float(f64),
+}
+
+impl CborValue {
+  /// Returns the value of the entry with key `key`, if this value is a map.
+  pub fn get(&self, key: &CborValue) -> Option<&CborValue> {
+    self
+      .as_map()
+      .and_then(|map| map.iter().find(|(k, _)| k == key).map(|(_, value)| value))
+  }
+
+  pub fn as_map(&self) -> Option<&Vec<(CborValue, CborValue)>> {
+    match self {
+      Self::Map(map) => Some(map),
+      _ => None,
+    }
+  }
+
+  pub fn as_map_mut(&mut self) -> Option<&mut Vec<(CborValue, CborValue)>> {
+    match self {
+      Self::Map(map) => Some(map),
+      _ => None,
+    }
+  }
+
+  pub fn as_array(&self) -> Option<&Vec<CborValue>> {
+    match self {
+      Self::Array(array) => Some(array),
+      _ => None,
+    }
+  }
+
+  pub fn as_bytes(&self) -> Option<&[u8]> {
+    match self {
+      Self::Bytes(bytes) => Some(bytes),
+      _ => None,
+    }
+  }
+
+  pub fn as_text(&self) -> Option<&str> {
+    match self {
+      Self::Text(text) => Some(text),
+      _ => None,
+    }
+  }
+
+  /// Returns this value as an `i64`, if it's an integer within range.
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      Self::Integer(integer) => i64::try_from(*integer).ok(),
+      _ => None,
+    }
+  }
+
+  /// Encodes this value, using definite lengths and the shortest form for integers and lengths.
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if this value contains an integer outside of the range `-2^64..2^64`, or a
+  /// simple value that's reserved or has its own variant.
+  pub fn to_vec(&self) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    self.encode(&mut bytes)?;
+    Ok(bytes)
+  }
+
+  /// Decodes a single CBOR data item spanning all of `bytes`.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `bytes` isn't well-formed CBOR, contains indefinite-length items or
+  /// trailing bytes.
+  pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+    let mut decoder = Decoder { bytes, offset: 0 };
+    let value = decoder.decode(0)?;
+    if decoder.offset != bytes.len() {
+      return Err(Error::DeserializationError("invalid CBOR: trailing bytes".to_string()));
+    }
+    Ok(value)
+  }
+
+  fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+    match self {
+      Self::Integer(integer) => {
+        let (major, argument) = if *integer >= 0 {
+          (0, *integer)
+        } else {
+          (1, -1 - *integer)
+        };
+        let argument = u64::try_from(argument)
+          .map_err(|_| Error::DataTypeMismatch(format!("invalid CBOR: integer {integer} is out of range")))?;
+        write_head(out, major, argument);
+      }
+      Self::Bytes(bytes) => {
+        write_head(out, 2, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+      }
+      Self::Text(text) => {
+        write_head(out, 3, text.len() as u64);
+        out.extend_from_slice(text.as_bytes());
+      }
+      Self::Array(array) => {
+        write_head(out, 4, array.len() as u64);
+        for value in array {
+          value.encode(out)?;
+        }
+      }
+      Self::Map(map) => {
+        write_head(out, 5, map.len() as u64);
+        for (key, value) in map {
+          key.encode(out)?;
+          value.encode(out)?;
+        }
+      }
+      Self::Tag(tag, value) => {
+        write_head(out, 6, *tag);
+        value.encode(out)?;
+      }
+      Self::Bool(false) => out.push(0xf4),
+      Self::Bool(true) => out.push(0xf5),
+      Self::Null => out.push(0xf6),
+      Self::Simple(simple @ (0..=19 | 23)) => out.push(0xe0 | simple),
+      Self::Simple(simple @ 32..) => out.extend_from_slice(&[0xf8, *simple]),
+      Self::Simple(simple) => {
+        return Err(Error::DataTypeMismatch(format!(
+          "invalid CBOR: simple value {simple} is reserved or not a `CborValue::Simple`"
+        )))
+      }
+      Self::Float(float) if (*float as f32) as f64 == *float || float.is_nan() => {
+        out.push(0xfa);
+        out.extend_from_slice(&(*float as f32).to_be_bytes());
+      }
+      Self::Float(float) => {
+        out.push(0xfb);
+        out.extend_from_slice(&float.to_be_bytes());
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl From<i64> for CborValue {
+  fn from(value: i64) -> Self {
+    Self::Integer(value.into())
+  }
+}
+
+impl From<&str> for CborValue {
+  fn from(value: &str) -> Self {
+    Self::Text(value.to_string())
+  }
+}
+
+impl From<String> for CborValue {
+  fn from(value: String) -> Self {
+    Self::Text(value)
+  }
+}
+
+impl From<Vec<u8>> for CborValue {
+  fn from(value: Vec<u8>) -> Self {
+    Self::Bytes(value)
+  }
+}
+
+impl From<bool> for CborValue {
+  fn from(value: bool) -> Self {
+    Self::Bool(value)
+  }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+  let major = major << 5;
+  if argument < 24 {
+    out.push(major | argument as u8);
+  } else if let Ok(argument) = u8::try_from(argument) {
+    out.extend_from_slice(&[major | 24, argument]);
+  } else if let Ok(argument) = u16::try_from(argument) {
+    out.push(major | 25);
+    out.extend_from_slice(&argument.to_be_bytes());
+  } else if let Ok(argument) = u32::try_from(argument) {
+    out.push(major | 26);
+    out.extend_from_slice(&argument.to_be_bytes());
+  } else {
+    out.push(major | 27);
+    out.extend_from_slice(&argument.to_be_bytes());
+  }
+}
+
+struct Decoder<'b> {
+  bytes: &'b [u8],
+  offset: usize,
+}
+
+impl<'b> Decoder<'b> {
+  fn take(&mut self, len: usize) -> Result<&'b [u8]> {
+    let end = self
+      .offset
+      .checked_add(len)
+      .filter(|end| *end <= self.bytes.len())
+      .ok_or_else(|| Error::DeserializationError("invalid CBOR: unexpected end of input".to_string()))?;
+    let bytes = &self.bytes[self.offset..end];
+    self.offset = end;
+    Ok(bytes)
+  }
+
+  /// Reads the initial byte and argument of a data item, returning its major type, additional information and
+  /// argument.
+  fn head(&mut self) -> Result<(u8, u8, u64)> {
+    let initial = self.take(1)?[0];
+    let (major, info) = (initial >> 5, initial & 0x1f);
+    let argument = match info {
+      0..=23 => info as u64,
+      24 => self.take(1)?[0] as u64,
+      25 => u16::from_be_bytes(self.take(2)?.try_into().expect("2 bytes")) as u64,
+      26 => u32::from_be_bytes(self.take(4)?.try_into().expect("4 bytes")) as u64,
+      27 => u64::from_be_bytes(self.take(8)?.try_into().expect("8 bytes")),
+      31 => {
+        return Err(Error::DeserializationError(
+          "invalid CBOR: indefinite-length items are not supported".to_string(),
+        ))
+      }
+      _ => {
+        return Err(Error::DeserializationError(
+          "invalid CBOR: reserved additional information".to_string(),
+        ))
+      }
+    };
+    Ok((major, info, argument))
+  }
+
+  fn length(&self, argument: u64) -> Result<usize> {
+    usize::try_from(argument)
+      .ok()
+      .filter(|len| *len <= self.bytes.len() - self.offset)
+      .ok_or_else(|| Error::DeserializationError("invalid CBOR: length exceeds input".to_string()))
+  }
+
+  fn decode(&mut self, depth: usize) -> Result<CborValue> {
+    if depth > MAX_DEPTH {
+      return Err(Error::DeserializationError(
+        "invalid CBOR: nesting is too deep".to_string(),
+      ));
+    }
+    let (major, info, argument) = self.head()?;
+    let value = match major {
+      0 => CborValue::Integer(argument.into()),
+      1 => CborValue::Integer(-1 - i128::from(argument)),
+      2 => {
+        let len = self.length(argument)?;
+        CborValue::Bytes(self.take(len)?.to_vec())
+      }
+      3 => {
+        let len = self.length(argument)?;
+        let text = std::str::from_utf8(self.take(len)?)
+          .map_err(|_| Error::DeserializationError("invalid CBOR: text is not UTF-8".to_string()))?;
+        CborValue::Text(text.to_string())
+      }
+      4 => {
+        let len = self.length(argument)?;
+        CborValue::Array((0..len).map(|_| self.decode(depth + 1)).collect::<Result<_>>()?)
+      }
+      5 => {
+        let len = self.length(argument)?;
+        CborValue::Map(
+          (0..len)
+            .map(|_| Ok((self.decode(depth + 1)?, self.decode(depth + 1)?)))
+            .collect::<Result<_>>()?,
+        )
+      }
+      6 => CborValue::Tag(argument, Box::new(self.decode(depth + 1)?)),
+      _ => match (info, argument) {
+        (20, _) => CborValue::Bool(false),
+        (21, _) => CborValue::Bool(true),
+        (22, _) => CborValue::Null,
+        (0..=23, simple) => CborValue::Simple(simple as u8),
+        (24, simple) if simple >= 32 => CborValue::Simple(simple as u8),
+        (25, half) => CborValue::Float(f16_to_f64(half as u16)),
+        (26, single) => CborValue::Float(f32::from_bits(single as u32) as f64),
+        (27, double) => CborValue::Float(f64::from_bits(double)),
+        _ => {
+          return Err(Error::DeserializationError(
+            "invalid CBOR: invalid simple value".to_string(),
+          ))
+        }
+      },
+    };
+    Ok(value)
+  }
+}
+
+fn f16_to_f64(bits: u16) -> f64 {
+  let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+  let exponent = ((bits >> 10) & 0x1f) as i32;
+  let mantissa = (bits & 0x3ff) as f64;
+  match exponent {
+    0 => sign * mantissa * 2f64.powi(-24),
+    31 if mantissa == 0.0 => sign * f64::INFINITY,
+    31 => f64::NAN,
+    _ => sign * (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::CborValue;
+
+  #[test]
+  fn round_trip() {
+    let value = CborValue::Map(vec![
+      (CborValue::from(1), CborValue::from("issuer")),
+      (CborValue::from(-70000), CborValue::Float(1.5)),
+      (
+        CborValue::Simple(59),
+        CborValue::Array(vec![CborValue::from(vec![0u8; 32])]),
+      ),
+      (
+        CborValue::from("list"),
+        CborValue::Array(vec![
+          CborValue::Tag(60, Box::new(CborValue::from(vec![1u8, 2, 3]))),
+          CborValue::Bool(true),
+          CborValue::Null,
+          CborValue::Float(0.1),
+        ]),
+      ),
+    ]);
+    assert_eq!(CborValue::from_slice(&value.to_vec().unwrap()).unwrap(), value);
+  }
+
+  #[test]
+  fn rfc8949_examples() {
+    // Examples from RFC 8949, Appendix A.
+    let examples: [(&str, CborValue); 7] = [
+      ("1903e8", CborValue::from(1000)),
+      ("3903e7", CborValue::from(-1000)),
+      ("f93c00", CborValue::Float(1.0)),
+      ("f8ff", CborValue::Simple(255)),
+      ("c11a514b67b0", CborValue::Tag(1, Box::new(CborValue::from(1363896240)))),
+      ("6449455446", CborValue::from("IETF")),
+      (
+        "a201020304",
+        CborValue::Map(vec![
+          (CborValue::from(1), CborValue::from(2)),
+          (CborValue::from(3), CborValue::from(4)),
+        ]),
+      ),
+    ];
+    for (hex, value) in examples {
+      let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect::<Vec<_>>();
+      assert_eq!(CborValue::from_slice(&bytes).unwrap(), value);
+    }
+  }
+
+  #[test]
+  fn malformed_input_is_rejected() {
+    assert!(CborValue::from_slice(&[0x9f, 0x01, 0xff]).is_err());
+    assert!(CborValue::from_slice(&[0x5a, 0xff, 0xff, 0xff, 0xff]).is_err());
+    assert!(CborValue::from_slice(&[0x01, 0x02]).is_err());
+    assert!(CborValue::from_slice(&[0x81; 100]).is_err());
+  }
+
+  #[test]
+  fn unencodable_values_are_rejected() {
+    assert!(CborValue::Integer(u64::MAX as i128 + 1).to_vec().is_err());
+    assert!(CborValue::Integer(-(u64::MAX as i128) - 2).to_vec().is_err());
+    assert_eq!(CborValue::Integer(-(u64::MAX as i128) - 1).to_vec().unwrap()[0], 0x3b);
+    for simple in [20, 22, 24, 31] {
+      assert!(CborValue::Simple(simple).to_vec().is_err());
+    }
+    assert_eq!(CborValue::Simple(23).to_vec().unwrap(), [0xf7]);
+  }
+}
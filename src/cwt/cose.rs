@@ -0,0 +1,183 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Display;
+
+use async_trait::async_trait;
+
+use super::CborValue;
+use crate::Error;
+use crate::Result;
+
+/// CBOR tag of a `COSE_Sign1` structure.
+pub(crate) const COSE_SIGN1_TAG: u64 = 18;
+/// COSE header parameter `alg`.
+pub(crate) const HEADER_ALG: i64 = 1;
+/// COSE header parameter `typ`, see [RFC 9596](https://www.rfc-editor.org/rfc/rfc9596.html).
+pub(crate) const HEADER_TYP: i64 = 16;
+
+/// CBOR Object Signing and Encryption (COSE) Signer.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait CoseSigner {
+  type Error: Display;
+  /// Creates the signature of a `COSE_Sign1` structure, with `alg` being its COSE algorithm identifier
+  /// (e.g. -7 for ES256) and `to_be_signed` the encoded `Sig_structure`.
+  async fn sign(&self, alg: i64, to_be_signed: &[u8]) -> std::result::Result<Vec<u8>, Self::Error>;
+}
+
+/// CBOR Object Signing and Encryption (COSE) Verifier.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait CoseVerifier {
+  type Error: Display;
+  /// Verifies `signature` over the encoded `Sig_structure` `to_be_signed`, created with the COSE algorithm `alg`.
+  ///
+  /// `key` is the public key, as a `COSE_Key`, the signature must be checked against, when the caller
+  /// was able to determine one. If `None` is passed, the verifier is expected to use its own key material.
+  async fn verify(
+    &self,
+    alg: i64,
+    to_be_signed: &[u8],
+    signature: &[u8],
+    key: Option<&CborValue>,
+  ) -> std::result::Result<(), Self::Error>;
+}
+
+/// A `COSE_Sign1` structure, see [RFC 9052](https://www.rfc-editor.org/rfc/rfc9052.html#section-4.2).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CoseSign1 {
+  /// The encoded protected header, which is part of the signed data.
+  pub protected: Vec<u8>,
+  pub protected_header: Vec<(CborValue, CborValue)>,
+  pub unprotected_header: Vec<(CborValue, CborValue)>,
+  pub payload: Vec<u8>,
+  pub signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+  /// Creates a `COSE_Sign1` structure signed by `signer`, using the algorithm set in `protected_header`.
+  pub async fn sign<S>(
+    signer: &S,
+    protected_header: Vec<(CborValue, CborValue)>,
+    unprotected_header: Vec<(CborValue, CborValue)>,
+    payload: Vec<u8>,
+  ) -> Result<Self>
+  where
+    S: CoseSigner,
+  {
+    let mut sign1 = Self {
+      protected: CborValue::Map(protected_header.clone()).to_vec()?,
+      protected_header,
+      unprotected_header,
+      payload,
+      signature: vec![],
+    };
+    let alg = sign1
+      .alg()
+      .ok_or_else(|| Error::DataTypeMismatch("invalid header: missing \"alg\"".to_string()))?;
+    sign1.signature = signer
+      .sign(alg, &sign1.to_be_signed())
+      .await
+      .map_err(|e| Error::CoseSignerFailure(e.to_string()))?;
+
+    Ok(sign1)
+  }
+
+  /// Verifies the signature with `verifier`, rejecting a missing `alg`.
+  pub async fn verify<V>(&self, verifier: &V, key: Option<&CborValue>) -> Result<()>
+  where
+    V: CoseVerifier,
+  {
+    let alg = self
+      .alg()
+      .ok_or_else(|| Error::CoseVerificationFailure("missing \"alg\" in protected header".to_string()))?;
+    verifier
+      .verify(alg, &self.to_be_signed(), &self.signature, key)
+      .await
+      .map_err(|e| Error::CoseVerificationFailure(e.to_string()))
+  }
+
+  pub fn alg(&self) -> Option<i64> {
+    self.protected_parameter(HEADER_ALG).and_then(CborValue::as_i64)
+  }
+
+  pub fn protected_parameter(&self, label: i64) -> Option<&CborValue> {
+    let label = CborValue::from(label);
+    self
+      .protected_header
+      .iter()
+      .find(|(key, _)| *key == label)
+      .map(|(_, value)| value)
+  }
+
+  /// The encoded `Sig_structure`, with empty external additional authenticated data.
+  pub fn to_be_signed(&self) -> Vec<u8> {
+    CborValue::Array(vec![
+      CborValue::from("Signature1"),
+      CborValue::Bytes(self.protected.clone()),
+      CborValue::Bytes(vec![]),
+      CborValue::Bytes(self.payload.clone()),
+    ])
+    .to_vec()
+    .expect("byte strings are encodable")
+  }
+
+  /// Returns the tagged `COSE_Sign1` structure.
+  pub fn to_value(&self) -> CborValue {
+    let sign1 = CborValue::Array(vec![
+      CborValue::Bytes(self.protected.clone()),
+      CborValue::Map(self.unprotected_header.clone()),
+      CborValue::Bytes(self.payload.clone()),
+      CborValue::Bytes(self.signature.clone()),
+    ]);
+    CborValue::Tag(COSE_SIGN1_TAG, Box::new(sign1))
+  }
+
+  /// Parses a - possibly untagged - `COSE_Sign1` structure with an attached payload.
+  pub fn from_value(value: &CborValue) -> Result<Self> {
+    let value = match value {
+      CborValue::Tag(COSE_SIGN1_TAG, value) => value.as_ref(),
+      CborValue::Tag(tag, _) => {
+        return Err(Error::DeserializationError(format!(
+          "invalid COSE_Sign1: unexpected tag {tag}"
+        )))
+      }
+      value => value,
+    };
+    let invalid = |reason: &str| Error::DeserializationError(format!("invalid COSE_Sign1: {reason}"));
+    let [protected, CborValue::Map(unprotected_header), payload, signature] = value
+      .as_array()
+      .map(Vec::as_slice)
+      .ok_or_else(|| invalid("not an array"))?
+    else {
+      return Err(invalid("expected 4 elements"));
+    };
+    let protected = protected
+      .as_bytes()
+      .ok_or_else(|| invalid("protected header is not a byte string"))?
+      .to_vec();
+    let protected_header = if protected.is_empty() {
+      vec![]
+    } else {
+      match CborValue::from_slice(&protected)? {
+        CborValue::Map(header) => header,
+        _ => return Err(invalid("protected header is not a map")),
+      }
+    };
+
+    Ok(Self {
+      protected,
+      protected_header,
+      unprotected_header: unprotected_header.clone(),
+      payload: payload
+        .as_bytes()
+        .ok_or_else(|| invalid("detached payloads are not supported"))?
+        .to_vec(),
+      signature: signature
+        .as_bytes()
+        .ok_or_else(|| invalid("signature is not a byte string"))?
+        .to_vec(),
+    })
+  }
+}
@@ -0,0 +1,103 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::CborValue;
+use crate::Error;
+use crate::Hasher;
+use crate::Result;
+
+/// Minimum salt size in bytes, i.e. 128 bits.
+pub(crate) const MIN_SALT_SIZE: usize = 16;
+
+/// A salted claim of an SD-CWT: the CBOR array `[salt, value, key]` for map entries, or `[salt, value]` for array
+/// elements.
+///
+/// Digests are computed over the encoded array, which is carried as a byte string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CwtDisclosure {
+  /// The salt.
+  pub salt: Vec<u8>,
+  /// The claim's map key, `None` for array elements.
+  pub claim_name: Option<CborValue>,
+  /// The claim's value.
+  pub claim_value: CborValue,
+  /// The encoded disclosure.
+  encoded: Vec<u8>,
+}
+
+impl CwtDisclosure {
+  /// Creates a new [`CwtDisclosure`].
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if the claim cannot be encoded, see [`CborValue::to_vec`].
+  pub(crate) fn new(salt: Vec<u8>, claim_name: Option<CborValue>, claim_value: CborValue) -> Result<Self> {
+    let mut salted_claim = vec![CborValue::Bytes(salt.clone()), claim_value.clone()];
+    salted_claim.extend(claim_name.clone());
+    Ok(Self {
+      salt,
+      claim_name,
+      claim_value,
+      encoded: CborValue::Array(salted_claim).to_vec()?,
+    })
+  }
+
+  /// Parses an encoded disclosure.
+  /// ## Error
+  /// Returns [`Error::InvalidDisclosure`] if `bytes` isn't a well-formed salted claim, e.g. because its salt is
+  /// shorter than 128 bits or its key is neither an integer nor a text string.
+  pub fn parse(bytes: &[u8]) -> Result<Self> {
+    let invalid = |reason: &str| Error::InvalidDisclosure(format!("invalid SD-CWT disclosure: {reason}"));
+    let value = CborValue::from_slice(bytes).map_err(|e| invalid(&e.to_string()))?;
+    let (salt, claim_value, claim_name) = match value.as_array().map(Vec::as_slice) {
+      Some([salt, value]) => (salt, value, None),
+      Some([salt, value, key @ (CborValue::Integer(_) | CborValue::Text(_))]) => (salt, value, Some(key)),
+      Some([_, _, _]) => return Err(invalid("the claim key must be an integer or a text string")),
+      _ => return Err(invalid("expected an array of 2 or 3 elements")),
+    };
+    let salt = salt
+      .as_bytes()
+      .filter(|salt| salt.len() >= MIN_SALT_SIZE)
+      .ok_or_else(|| invalid("the salt must be a byte string of at least 16 bytes"))?;
+
+    Ok(Self {
+      salt: salt.to_vec(),
+      claim_name: claim_name.cloned(),
+      claim_value: claim_value.clone(),
+      encoded: bytes.to_vec(),
+    })
+  }
+
+  /// Returns the encoded disclosure.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.encoded
+  }
+
+  /// Returns the digest of this disclosure computed with `hasher`.
+  pub fn digest(&self, hasher: &dyn Hasher) -> Vec<u8> {
+    hasher.digest(&self.encoded)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::CwtDisclosure;
+  use crate::cwt::CborValue;
+
+  #[test]
+  fn parsing_round_trip() {
+    let disclosure =
+      CwtDisclosure::new(vec![7; 16], Some(CborValue::from(501)), CborValue::from("ABCD-123456")).unwrap();
+    assert_eq!(CwtDisclosure::parse(disclosure.as_bytes()).unwrap(), disclosure);
+
+    let element = CwtDisclosure::new(vec![7; 16], None, CborValue::from("US")).unwrap();
+    assert_eq!(CwtDisclosure::parse(element.as_bytes()).unwrap(), element);
+  }
+
+  #[test]
+  fn invalid_disclosures_are_rejected() {
+    let short_salt = CwtDisclosure::new(vec![7; 8], None, CborValue::from("US")).unwrap();
+    assert!(CwtDisclosure::parse(short_salt.as_bytes()).is_err());
+
+    let invalid_key = CwtDisclosure::new(vec![7; 16], Some(CborValue::Bool(true)), CborValue::from("US")).unwrap();
+    assert!(CwtDisclosure::parse(invalid_key.as_bytes()).is_err());
+  }
+}
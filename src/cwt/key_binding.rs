@@ -0,0 +1,199 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use super::cose::CoseSign1;
+use super::cose::HEADER_ALG;
+use super::cose::HEADER_TYP;
+use super::CborValue;
+use super::CoseSigner;
+use super::CoseVerifier;
+use super::SdCwt;
+use super::CLAIM_AUD;
+use super::CLAIM_CNONCE;
+use super::CLAIM_IAT;
+use super::HEADER_KCWT;
+use super::KB_CWT_TYP;
+use crate::Error;
+use crate::KeyBindingJwtValidation;
+use crate::Result;
+
+/// Representation of an SD-CWT key binding token (SD-KBT): a `COSE_Sign1` structure signed by the holder, carrying
+/// the presented [`SdCwt`] in its protected header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindingToken {
+  sign1: CoseSign1,
+  sd_cwt: SdCwt,
+  claims: CborValue,
+}
+
+impl KeyBindingToken {
+  /// Returns a [`KeyBindingTokenBuilder`] that allows the creation of a [`KeyBindingToken`].
+  pub fn builder() -> KeyBindingTokenBuilder {
+    KeyBindingTokenBuilder::default()
+  }
+
+  /// Parses an encoded key binding token.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `bytes` isn't a `COSE_Sign1` structure of type
+  /// "application/kb+cwt" carrying an SD-CWT.
+  pub fn parse(bytes: &[u8]) -> Result<Self> {
+    let sign1 = CoseSign1::from_value(&CborValue::from_slice(bytes)?)?;
+    if sign1.protected_parameter(HEADER_TYP).and_then(CborValue::as_text) != Some(KB_CWT_TYP) {
+      return Err(Error::DeserializationError(format!(
+        "invalid key binding token: \"typ\" must be \"{KB_CWT_TYP}\""
+      )));
+    }
+    let sd_cwt = sign1
+      .protected_parameter(HEADER_KCWT)
+      .ok_or_else(|| Error::DeserializationError("invalid key binding token: missing \"kcwt\"".to_string()))
+      .and_then(SdCwt::from_value)?;
+    let claims = CborValue::from_slice(&sign1.payload)?;
+
+    Ok(Self { sign1, sd_cwt, claims })
+  }
+
+  /// Encodes this key binding token.
+  pub fn to_vec(&self) -> Vec<u8> {
+    self
+      .sign1
+      .to_value()
+      .to_vec()
+      .expect("the values of a signed or parsed token are encodable")
+  }
+
+  /// Returns the presented [`SdCwt`].
+  pub fn sd_cwt(&self) -> &SdCwt {
+    &self.sd_cwt
+  }
+
+  pub fn claims(&self) -> &CborValue {
+    &self.claims
+  }
+
+  pub fn aud(&self) -> Option<&str> {
+    self
+      .claims
+      .get(&CborValue::from(CLAIM_AUD))
+      .and_then(CborValue::as_text)
+  }
+
+  pub fn iat(&self) -> Option<i64> {
+    self.claims.get(&CborValue::from(CLAIM_IAT)).and_then(CborValue::as_i64)
+  }
+
+  pub fn cnonce(&self) -> Option<&[u8]> {
+    self
+      .claims
+      .get(&CborValue::from(CLAIM_CNONCE))
+      .and_then(CborValue::as_bytes)
+  }
+
+  /// Verifies this token's signature against the key required by the presented SD-CWT's `cnf` claim, and checks its
+  /// `aud`, `cnonce` and `iat` claims against `validation`, whose `nonce` is compared with `cnonce` as UTF-8 bytes.
+  ///
+  /// ## Notes
+  /// The issuer's signature of the SD-CWT isn't verified, use [`SdCwt::verify`] on [`KeyBindingToken::sd_cwt`] for
+  /// that.
+  /// ## Errors
+  /// - [`Error::CoseVerificationFailure`] is returned if the signature is invalid.
+  /// - [`Error::JwtNotYetValid`] is returned if `iat` is in the future.
+  /// - [`Error::InvalidKeyBindingToken`] is returned if the SD-CWT doesn't require a key binding or if any claim
+  ///   doesn't match its expected value.
+  pub async fn verify<V>(&self, verifier: &V, validation: &KeyBindingJwtValidation) -> Result<()>
+  where
+    V: CoseVerifier,
+  {
+    let holder_key = self
+      .sd_cwt
+      .required_key_binding()
+      .ok_or_else(|| Error::InvalidKeyBindingToken("the SD-CWT doesn't require a key binding".to_string()))?;
+    self.sign1.verify(verifier, Some(holder_key)).await?;
+
+    if self.cnonce() != Some(validation.nonce().as_bytes()) {
+      return Err(Error::InvalidKeyBindingToken("unexpected `cnonce`".to_string()));
+    }
+    if self.aud() != Some(validation.aud()) {
+      return Err(Error::InvalidKeyBindingToken(format!(
+        "expected `aud` \"{}\", found {:?}",
+        validation.aud(),
+        self.aud()
+      )));
+    }
+
+    let iat = self
+      .iat()
+      .ok_or_else(|| Error::InvalidKeyBindingToken("missing `iat`".to_string()))?;
+    validation.check_iat(iat).map_err(|e| match e {
+      Error::InvalidKeyBindingJwt(reason) => Error::InvalidKeyBindingToken(reason),
+      e => e,
+    })
+  }
+}
+
+/// Builder-style struct to ease the creation of a [`KeyBindingToken`].
+#[derive(Debug, Default, Clone)]
+pub struct KeyBindingTokenBuilder {
+  aud: Option<String>,
+  iat: Option<i64>,
+  cnonce: Option<Vec<u8>>,
+}
+
+impl KeyBindingTokenBuilder {
+  /// Creates a new [`KeyBindingTokenBuilder`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the `aud` claim.
+  pub fn aud(mut self, aud: impl Into<String>) -> Self {
+    self.aud = Some(aud.into());
+    self
+  }
+
+  /// Sets the `iat` claim.
+  pub fn iat(mut self, iat: i64) -> Self {
+    self.iat = Some(iat);
+    self
+  }
+
+  /// Sets the `cnonce` claim.
+  pub fn cnonce(mut self, cnonce: impl Into<Vec<u8>>) -> Self {
+    self.cnonce = Some(cnonce.into());
+    self
+  }
+
+  /// Builds a [`KeyBindingToken`] presenting `sd_cwt`, signed by `signer` with the COSE algorithm `alg`.
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if `aud` or `iat` haven't been set.
+  pub async fn finish<S>(self, sd_cwt: &SdCwt, signer: &S, alg: i64) -> Result<KeyBindingToken>
+  where
+    S: CoseSigner,
+  {
+    let (Some(aud), Some(iat)) = (self.aud, self.iat) else {
+      return Err(Error::DataTypeMismatch(
+        "a key binding token requires `aud` and `iat`".to_string(),
+      ));
+    };
+    let mut claims = vec![
+      (CborValue::from(CLAIM_AUD), CborValue::from(aud)),
+      (CborValue::from(CLAIM_IAT), CborValue::from(iat)),
+    ];
+    if let Some(cnonce) = self.cnonce {
+      claims.push((CborValue::from(CLAIM_CNONCE), CborValue::Bytes(cnonce)));
+    }
+    let claims = CborValue::Map(claims);
+
+    let header = vec![
+      (CborValue::from(HEADER_ALG), CborValue::from(alg)),
+      (CborValue::from(HEADER_TYP), CborValue::from(KB_CWT_TYP)),
+      (CborValue::from(HEADER_KCWT), sd_cwt.to_value()),
+    ];
+    let sign1 = CoseSign1::sign(signer, header, vec![], claims.to_vec()?).await?;
+
+    Ok(KeyBindingToken {
+      sign1,
+      sd_cwt: sd_cwt.clone(),
+      claims,
+    })
+  }
+}
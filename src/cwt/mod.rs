@@ -0,0 +1,89 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Selective disclosure over CBOR and COSE, following
+//! [SD-CWT](https://datatracker.ietf.org/doc/draft-ietf-spice-sd-cwt/).
+//!
+//! The pipeline mirrors the SD-JWT one: an [`SdCwtBuilder`] conceals claims of a CWT claims set and signs it into an
+//! [`SdCwt`], which the holder presents through an [`SdCwtPresentationBuilder`], optionally wrapped in a
+//! [`KeyBindingToken`].
+
+mod builder;
+mod cbor;
+mod cose;
+mod disclosure;
+mod key_binding;
+mod sd_cwt;
+
+pub use builder::*;
+pub use cbor::*;
+pub use cose::CoseSigner;
+pub use cose::CoseVerifier;
+pub use disclosure::*;
+pub use key_binding::*;
+pub use sd_cwt::*;
+
+use crate::Error;
+use crate::Hasher;
+use crate::Result;
+
+/// Map key under which the digests of redacted map entries are listed.
+pub const REDACTED_CLAIM_KEYS: CborValue = CborValue::Simple(59);
+/// Tag wrapping the digest of a redacted array element.
+pub const REDACTED_CLAIM_ELEMENT_TAG: u64 = 60;
+/// Unprotected header parameter carrying the disclosures.
+pub const HEADER_SD_CLAIMS: i64 = 17;
+/// Protected header parameter identifying the hash algorithm used for digests.
+pub const HEADER_SD_ALG: i64 = 18;
+/// Protected header parameter of a [`KeyBindingToken`] carrying the presented SD-CWT.
+pub const HEADER_KCWT: i64 = 13;
+/// `typ` of an SD-CWT.
+pub const SD_CWT_TYP: &str = "application/sd+cwt";
+/// `typ` of a [`KeyBindingToken`].
+pub const KB_CWT_TYP: &str = "application/kb+cwt";
+
+/// CWT claim keys, see [RFC 8392](https://www.rfc-editor.org/rfc/rfc8392.html#section-3).
+pub(crate) const CLAIM_AUD: i64 = 3;
+pub(crate) const CLAIM_IAT: i64 = 6;
+pub(crate) const CLAIM_CNF: i64 = 8;
+pub(crate) const CLAIM_CNONCE: i64 = 39;
+/// `cnf` member holding a `COSE_Key`, see [RFC 8747](https://www.rfc-editor.org/rfc/rfc8747.html).
+pub(crate) const CNF_COSE_KEY: i64 = 1;
+
+/// Returns the COSE algorithm identifier of the hash function implemented by `hasher`.
+/// ## Error
+/// Returns [`Error::InvalidHasher`] if the hash function has no COSE algorithm identifier known to this library.
+pub(crate) fn hash_alg_id(hasher: &dyn Hasher) -> Result<i64> {
+  match hasher.alg_name() {
+    "sha-256" => Ok(-16),
+    "sha-384" => Ok(-43),
+    "sha-512" => Ok(-44),
+    name => Err(Error::InvalidHasher(format!(
+      "hash function \"{name}\" has no known COSE algorithm identifier"
+    ))),
+  }
+}
+
+/// Splits a path using the syntax of [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) into its
+/// segments.
+pub(crate) fn path_segments(path: &str) -> Result<Vec<String>> {
+  let Some(path) = path.strip_prefix('/') else {
+    return Err(Error::InvalidPath(path.to_string()));
+  };
+  Ok(
+    path
+      .split('/')
+      .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+      .collect(),
+  )
+}
+
+/// Whether the map key `key` is addressed by the path segment `segment`: integer keys are addressed by their decimal
+/// representation, text keys by their content.
+pub(crate) fn key_matches(key: &CborValue, segment: &str) -> bool {
+  match key {
+    CborValue::Text(text) => text == segment,
+    CborValue::Integer(integer) => segment.parse::<i128>().is_ok_and(|segment| segment == *integer),
+    _ => false,
+  }
+}
@@ -0,0 +1,415 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::ops::Deref;
+
+use indexmap::IndexMap;
+use multibase::Base;
+
+use super::cose::CoseSign1;
+use super::hash_alg_id;
+use super::key_matches;
+use super::path_segments;
+use super::CborValue;
+use super::CoseVerifier;
+use super::CwtDisclosure;
+use super::CLAIM_CNF;
+use super::CNF_COSE_KEY;
+use super::HEADER_SD_ALG;
+use super::HEADER_SD_CLAIMS;
+use super::REDACTED_CLAIM_ELEMENT_TAG;
+use super::REDACTED_CLAIM_KEYS;
+use crate::Error;
+use crate::Hasher;
+use crate::Result;
+
+/// Representation of an SD-CWT: a `COSE_Sign1` structure over a CWT claims set with redacted claims, carrying the
+/// disclosures in its unprotected header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdCwt {
+  sign1: CoseSign1,
+  claims: CborValue,
+  disclosures: Vec<CwtDisclosure>,
+}
+
+impl SdCwt {
+  /// Creates an [`SdCwt`], with `disclosures` replacing any `sd_claims` header parameter of `sign1`.
+  pub(crate) fn new(mut sign1: CoseSign1, claims: CborValue, disclosures: Vec<CwtDisclosure>) -> Self {
+    let sd_claims = CborValue::from(HEADER_SD_CLAIMS);
+    sign1.unprotected_header.retain(|(label, _)| *label != sd_claims);
+    Self {
+      sign1,
+      claims,
+      disclosures,
+    }
+  }
+
+  /// Parses an encoded SD-CWT.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `bytes` isn't a `COSE_Sign1` structure over a CBOR map, or
+  /// [`Error::InvalidDisclosure`] if any of its disclosures is malformed.
+  pub fn parse(bytes: &[u8]) -> Result<Self> {
+    Self::from_value(&CborValue::from_slice(bytes)?)
+  }
+
+  pub(crate) fn from_value(value: &CborValue) -> Result<Self> {
+    let sign1 = CoseSign1::from_value(value)?;
+    let claims = CborValue::from_slice(&sign1.payload)?;
+    if !matches!(claims, CborValue::Map(_)) {
+      return Err(Error::DeserializationError(
+        "invalid SD-CWT: the payload is not a CBOR map".to_string(),
+      ));
+    }
+    let sd_claims = CborValue::from(HEADER_SD_CLAIMS);
+    let disclosures = match sign1.unprotected_header.iter().find(|(label, _)| *label == sd_claims) {
+      None => vec![],
+      Some((_, CborValue::Array(sd_claims))) => sd_claims
+        .iter()
+        .map(|disclosure| {
+          disclosure
+            .as_bytes()
+            .ok_or_else(|| Error::InvalidDisclosure("SD-CWT disclosures must be byte strings".to_string()))
+            .and_then(CwtDisclosure::parse)
+        })
+        .collect::<Result<_>>()?,
+      Some(_) => {
+        return Err(Error::DeserializationError(
+          "invalid SD-CWT: sd_claims is not an array".to_string(),
+        ))
+      }
+    };
+
+    Ok(Self::new(sign1, claims, disclosures))
+  }
+
+  /// Returns the tagged `COSE_Sign1` structure, carrying the current disclosures.
+  pub(crate) fn to_value(&self) -> CborValue {
+    let mut sign1 = self.sign1.clone();
+    let disclosures = self
+      .disclosures
+      .iter()
+      .map(|disclosure| CborValue::Bytes(disclosure.as_bytes().to_vec()))
+      .collect();
+    sign1
+      .unprotected_header
+      .push((CborValue::from(HEADER_SD_CLAIMS), CborValue::Array(disclosures)));
+
+    sign1.to_value()
+  }
+
+  /// Encodes this SD-CWT.
+  pub fn to_vec(&self) -> Vec<u8> {
+    self
+      .to_value()
+      .to_vec()
+      .expect("the values of a signed or parsed SD-CWT are encodable")
+  }
+
+  /// Returns the protected header parameters.
+  pub fn header(&self) -> &[(CborValue, CborValue)] {
+    &self.sign1.protected_header
+  }
+
+  /// Returns the claims, including the redacted ones' digests.
+  pub fn claims(&self) -> &CborValue {
+    &self.claims
+  }
+
+  pub fn disclosures(&self) -> &[CwtDisclosure] {
+    &self.disclosures
+  }
+
+  /// Returns the `COSE_Key` the holder must prove possession of, as required by the issuer through `cnf`.
+  pub fn required_key_binding(&self) -> Option<&CborValue> {
+    self
+      .claims
+      .get(&CborValue::from(CLAIM_CNF))
+      .and_then(|cnf| cnf.get(&CborValue::from(CNF_COSE_KEY)))
+  }
+
+  /// Prepares this [`SdCwt`] for a presentation, returning an [`SdCwtPresentationBuilder`].
+  /// ## Errors
+  /// - [`Error::InvalidHasher`] is returned if the provided `hasher`'s algorithm doesn't match the algorithm specified
+  ///   by the `sd_alg` header parameter. "sha-256" is used if the parameter is missing.
+  pub fn into_presentation(self, hasher: &dyn Hasher) -> Result<SdCwtPresentationBuilder> {
+    SdCwtPresentationBuilder::new(self, hasher)
+  }
+
+  /// Verifies the issuer's signature of this SD-CWT and returns the claims obtained by replacing all redacted
+  /// claims with their disclosures.
+  /// ## Errors
+  /// - [`Error::InvalidHasher`] is returned if the provided `hasher`'s algorithm doesn't match the algorithm specified
+  ///   by the `sd_alg` header parameter.
+  /// - [`Error::CoseVerificationFailure`] is returned if the signature is invalid.
+  /// - Any error returned by [`SdCwt::into_disclosed_claims`].
+  pub async fn verify<V>(&self, verifier: &V, hasher: &dyn Hasher) -> Result<CborValue>
+  where
+    V: CoseVerifier,
+  {
+    check_hasher(self, hasher)?;
+    self.sign1.verify(verifier, None).await?;
+
+    self.clone().into_disclosed_claims(hasher)
+  }
+
+  /// Returns the claims obtained by replacing all redacted claims with their disclosures.
+  pub fn into_disclosed_claims(self, hasher: &dyn Hasher) -> Result<CborValue> {
+    check_hasher(&self, hasher)?;
+    let disclosures: HashMap<Vec<u8>, CwtDisclosure> = self
+      .disclosures
+      .into_iter()
+      .map(|disclosure| (disclosure.digest(hasher), disclosure))
+      .collect();
+    let mut processed_digests = vec![];
+    let decoded = decode(&self.claims, &disclosures, &mut processed_digests)?;
+
+    if processed_digests.len() != disclosures.len() {
+      return Err(Error::UnusedDisclosures(
+        disclosures.len().saturating_sub(processed_digests.len()),
+      ));
+    }
+
+    Ok(decoded)
+  }
+}
+
+/// Builder structure to conceal claims of an [`SdCwt`] before presenting it.
+#[derive(Debug, Clone)]
+pub struct SdCwtPresentationBuilder {
+  sd_cwt: SdCwt,
+  disclosures: IndexMap<Vec<u8>, CwtDisclosure>,
+  removed_disclosures: Vec<CwtDisclosure>,
+}
+
+impl Deref for SdCwtPresentationBuilder {
+  type Target = SdCwt;
+  fn deref(&self) -> &Self::Target {
+    &self.sd_cwt
+  }
+}
+
+impl SdCwtPresentationBuilder {
+  pub fn new(mut sd_cwt: SdCwt, hasher: &dyn Hasher) -> Result<Self> {
+    check_hasher(&sd_cwt, hasher)?;
+    let disclosures = std::mem::take(&mut sd_cwt.disclosures)
+      .into_iter()
+      .map(|disclosure| (disclosure.digest(hasher), disclosure))
+      .collect();
+
+    Ok(Self {
+      sd_cwt,
+      disclosures,
+      removed_disclosures: vec![],
+    })
+  }
+
+  /// Removes the disclosure for the claim at `path`, concealing it.
+  ///
+  /// ## Notes
+  /// - When concealing a claim more than one disclosure may be removed: the disclosure for the claim itself and the
+  ///   disclosures for any concealable sub-claim.
+  pub fn conceal(mut self, path: &str) -> Result<Self> {
+    let segments = path_segments(path)?;
+    let digests_to_remove = conceal(&self.sd_cwt.claims, &segments, &self.disclosures)
+      .ok_or_else(|| Error::InvalidPath("element at path doesn't exist or is not disclosable".to_string()))?;
+
+    digests_to_remove
+      .into_iter()
+      .flat_map(|digest| self.disclosures.shift_remove(&digest))
+      .for_each(|disclosure| self.removed_disclosures.push(disclosure));
+
+    Ok(self)
+  }
+
+  /// Returns the resulting [`SdCwt`] together with all removed disclosures.
+  pub fn finish(self) -> (SdCwt, Vec<CwtDisclosure>) {
+    let SdCwtPresentationBuilder {
+      mut sd_cwt,
+      disclosures,
+      removed_disclosures,
+    } = self;
+    sd_cwt.disclosures = disclosures.into_values().collect();
+
+    (sd_cwt, removed_disclosures)
+  }
+}
+
+/// Checks that `hasher` implements the hash function required by the `sd_alg` header parameter.
+fn check_hasher(sd_cwt: &SdCwt, hasher: &dyn Hasher) -> Result<()> {
+  let required = sd_cwt
+    .sign1
+    .protected_parameter(HEADER_SD_ALG)
+    .map(|alg| {
+      alg
+        .as_i64()
+        .ok_or_else(|| Error::InvalidHasher("invalid sd_alg".to_string()))
+    })
+    .transpose()?
+    .unwrap_or(-16);
+  if hash_alg_id(hasher)? != required {
+    return Err(Error::InvalidHasher(format!(
+      "hasher \"{}\" was provided, but COSE algorithm {required} is required",
+      hasher.alg_name()
+    )));
+  }
+  Ok(())
+}
+
+/// Returns the digest of a redacted array element.
+fn redacted_digest(value: &CborValue) -> Option<&[u8]> {
+  match value {
+    CborValue::Tag(REDACTED_CLAIM_ELEMENT_TAG, digest) => digest.as_bytes(),
+    _ => None,
+  }
+}
+
+fn redacted_keys(map: &[(CborValue, CborValue)]) -> impl Iterator<Item = &[u8]> {
+  map
+    .iter()
+    .filter(|(key, _)| *key == REDACTED_CLAIM_KEYS)
+    .flat_map(|(_, digests)| digests.as_array().into_iter().flatten())
+    .filter_map(CborValue::as_bytes)
+}
+
+fn display_key(key: &CborValue) -> String {
+  match key {
+    CborValue::Text(text) => text.clone(),
+    CborValue::Integer(integer) => integer.to_string(),
+    key => format!("{key:?}"),
+  }
+}
+
+/// Returns the disclosure matching `digest`, if any, marking the digest as processed.
+fn take_disclosure<'d>(
+  digest: &[u8],
+  disclosures: &'d HashMap<Vec<u8>, CwtDisclosure>,
+  processed_digests: &mut Vec<Vec<u8>>,
+) -> Result<Option<&'d CwtDisclosure>> {
+  // Reject if any digests were found more than once.
+  if processed_digests.iter().any(|processed| processed == digest) {
+    return Err(Error::DuplicateDigestError(Base::Base64Url.encode(digest)));
+  }
+  let disclosure = disclosures.get(digest);
+  if disclosure.is_some() {
+    processed_digests.push(digest.to_vec());
+  }
+  Ok(disclosure)
+}
+
+/// Replaces redacted claims in `value` by their disclosed values, recursively.
+fn decode(
+  value: &CborValue,
+  disclosures: &HashMap<Vec<u8>, CwtDisclosure>,
+  processed_digests: &mut Vec<Vec<u8>>,
+) -> Result<CborValue> {
+  let decoded = match value {
+    CborValue::Map(map) => {
+      let mut disclosed = vec![];
+      for digest in redacted_keys(map) {
+        if let Some(disclosure) = take_disclosure(digest, disclosures, processed_digests)? {
+          let claim_name = disclosure.claim_name.clone().ok_or_else(|| {
+            Error::InvalidDisclosure("an array element disclosure was used for a map entry".to_string())
+          })?;
+          disclosed.push((claim_name, disclosure.claim_value.clone()));
+        }
+      }
+
+      let mut output: Vec<(CborValue, CborValue)> = vec![];
+      for (key, value) in map.iter().filter(|(key, _)| *key != REDACTED_CLAIM_KEYS) {
+        output.push((key.clone(), decode(value, disclosures, processed_digests)?));
+      }
+      for (key, value) in disclosed {
+        if output.iter().any(|(existing, _)| *existing == key) {
+          return Err(Error::ClaimCollisionError(display_key(&key)));
+        }
+        output.push((key, decode(&value, disclosures, processed_digests)?));
+      }
+      CborValue::Map(output)
+    }
+    CborValue::Array(array) => {
+      let mut output = vec![];
+      for element in array {
+        let Some(digest) = redacted_digest(element) else {
+          output.push(decode(element, disclosures, processed_digests)?);
+          continue;
+        };
+        if let Some(disclosure) = take_disclosure(digest, disclosures, processed_digests)? {
+          if disclosure.claim_name.is_some() {
+            return Err(Error::InvalidDisclosure(
+              "a map entry disclosure was used for an array element".to_string(),
+            ));
+          }
+          let claim_value = disclosure.claim_value.clone();
+          output.push(decode(&claim_value, disclosures, processed_digests)?);
+        }
+      }
+      CborValue::Array(output)
+    }
+    CborValue::Tag(tag, value) => CborValue::Tag(*tag, Box::new(decode(value, disclosures, processed_digests)?)),
+    value => value.clone(),
+  };
+
+  Ok(decoded)
+}
+
+/// Returns the digests of the disclosures to remove to conceal the claim at `path`: the claim's own and those of
+/// its concealable sub-claims.
+fn conceal(value: &CborValue, path: &[String], disclosures: &IndexMap<Vec<u8>, CwtDisclosure>) -> Option<Vec<Vec<u8>>> {
+  let (segment, rest) = path.split_first()?;
+  let (child, digest) = match value {
+    CborValue::Map(map) => match map.iter().find(|(key, _)| key_matches(key, segment)) {
+      Some((_, child)) => (child, None),
+      None => redacted_keys(map).find_map(|digest| {
+        disclosures
+          .get(digest)
+          .filter(|disclosure| {
+            disclosure
+              .claim_name
+              .as_ref()
+              .is_some_and(|key| key_matches(key, segment))
+          })
+          .map(|disclosure| (&disclosure.claim_value, Some(digest)))
+      })?,
+    },
+    CborValue::Array(array) => {
+      let element = array.get(segment.parse::<usize>().ok()?)?;
+      match redacted_digest(element).and_then(|digest| disclosures.get_key_value(digest)) {
+        Some((digest, disclosure)) => (&disclosure.claim_value, Some(digest.as_slice())),
+        None => (element, None),
+      }
+    }
+    _ => return None,
+  };
+
+  if rest.is_empty() {
+    let mut digests = vec![digest?.to_vec()];
+    nested_digests(child, disclosures, &mut digests);
+    Some(digests)
+  } else {
+    conceal(child, rest, disclosures)
+  }
+}
+
+fn nested_digests(value: &CborValue, disclosures: &IndexMap<Vec<u8>, CwtDisclosure>, digests: &mut Vec<Vec<u8>>) {
+  let add_disclosed = |digest: &[u8], digests: &mut Vec<Vec<u8>>| {
+    if let Some(disclosure) = disclosures.get(digest) {
+      digests.push(digest.to_vec());
+      nested_digests(&disclosure.claim_value, disclosures, digests);
+    }
+  };
+  match value {
+    CborValue::Map(map) => {
+      redacted_keys(map).for_each(|digest| add_disclosed(digest, digests));
+      map
+        .iter()
+        .for_each(|(_, value)| nested_digests(value, disclosures, digests));
+    }
+    CborValue::Array(array) => array.iter().for_each(|element| match redacted_digest(element) {
+      Some(digest) => add_disclosed(digest, digests),
+      None => nested_digests(element, disclosures, digests),
+    }),
+    CborValue::Tag(_, value) => nested_digests(value, disclosures, digests),
+    _ => {}
+  }
+}
@@ -6,7 +6,9 @@ use crate::DIGESTS_KEY;
 use crate::SD_ALG;
 
 use super::Disclosure;
+use crate::CheckKind;
 use crate::Error;
+use crate::VerificationReport;
 use serde_json::Map;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -22,17 +24,53 @@ impl SdObjectDecoder {
     object: &Map<String, Value>,
     disclosures: &HashMap<String, Disclosure>,
   ) -> Result<Map<String, Value>, crate::Error> {
+    self.decode_inner(object, disclosures, &mut None)
+  }
+
+  /// Decodes `object` like [`SdObjectDecoder::decode`] does, but instead of stopping at the first invalid
+  /// digest or disclosure, records every one of them in `report` and leaves it out of the decoded object.
+  pub fn decode_with_report(
+    &self,
+    object: &Map<String, Value>,
+    disclosures: &HashMap<String, Disclosure>,
+    report: &mut VerificationReport,
+  ) -> Map<String, Value> {
+    self
+      .decode_inner(object, disclosures, &mut Some(report))
+      .expect("failures are recorded in the report")
+  }
+
+  fn decode_inner(
+    &self,
+    object: &Map<String, Value>,
+    disclosures: &HashMap<String, Disclosure>,
+    report: &mut Option<&mut VerificationReport>,
+  ) -> Result<Map<String, Value>, Error> {
     // `processed_digests` are kept track of in case one digest appears more than once which
     // renders the SD-JWT invalid.
     let mut processed_digests: Vec<String> = vec![];
 
     // Decode the object recursively.
-    let mut decoded = self.decode_object(object, disclosures, &mut processed_digests)?;
+    let mut decoded = self.decode_object(object, disclosures, &mut processed_digests, "", report)?;
 
     if processed_digests.len() != disclosures.len() {
-      return Err(crate::Error::UnusedDisclosures(
-        disclosures.len().saturating_sub(processed_digests.len()),
-      ));
+      match report {
+        Some(report) => {
+          for digest in disclosures.keys().filter(|digest| !processed_digests.contains(digest)) {
+            report.fail(
+              CheckKind::UnusedDisclosure,
+              None,
+              Some(digest.clone()),
+              Error::UnusedDisclosures(1),
+            );
+          }
+        }
+        None => {
+          return Err(crate::Error::UnusedDisclosures(
+            disclosures.len().saturating_sub(processed_digests.len()),
+          ))
+        }
+      }
     }
 
     // Remove `_sd_alg` in case it exists.
@@ -46,46 +84,60 @@ impl SdObjectDecoder {
     object: &Map<String, Value>,
     disclosures: &HashMap<String, Disclosure>,
     processed_digests: &mut Vec<String>,
+    path: &str,
+    report: &mut Option<&mut VerificationReport>,
   ) -> Result<Map<String, Value>, Error> {
     let mut output: Map<String, Value> = object.clone();
     for (key, value) in object.iter() {
       match value {
         Value::Array(sd_array) if key == DIGESTS_KEY => {
           for digest in sd_array {
-            let digest_str = digest
-              .as_str()
-              .ok_or(Error::DataTypeMismatch(format!("{} is not a string", digest)))?
-              .to_string();
+            let Some(digest_str) = digest.as_str().map(ToString::to_string) else {
+              let error = Error::DataTypeMismatch(format!("{} is not a string", digest));
+              failure(report, CheckKind::Disclosure, path, None, error)?;
+              continue;
+            };
 
             // Reject if any digests were found more than once.
             if processed_digests.contains(&digest_str) {
-              return Err(Error::DuplicateDigestError(digest_str));
+              let error = Error::DuplicateDigestError(digest_str.clone());
+              failure(report, CheckKind::DuplicateDigest, path, Some(digest_str), error)?;
+              continue;
             }
 
             // Check if a disclosure of this digest is available
             // and insert its claim name and value in the object.
             if let Some(disclosure) = disclosures.get(&digest_str) {
-              let claim_name = disclosure.claim_name.clone().ok_or(Error::DataTypeMismatch(format!(
-                "disclosure type error: {}",
-                disclosure
-              )))?;
+              let Some(claim_name) = disclosure.claim_name.clone() else {
+                processed_digests.push(digest_str.clone());
+                let error = Error::DataTypeMismatch(format!("disclosure type error: {}", disclosure));
+                failure(report, CheckKind::Disclosure, path, Some(digest_str), error)?;
+                continue;
+              };
 
+              let claim_path = child_path(path, &claim_name);
               if output.contains_key(&claim_name) {
-                return Err(Error::ClaimCollisionError(claim_name));
+                processed_digests.push(digest_str.clone());
+                let error = Error::ClaimCollisionError(claim_name);
+                failure(report, CheckKind::ClaimCollision, &claim_path, Some(digest_str), error)?;
+                continue;
               }
               processed_digests.push(digest_str.clone());
 
               let recursively_decoded = match disclosure.claim_value {
                 Value::Array(ref sub_arr) => {
-                  Value::Array(self.decode_array(sub_arr, disclosures, processed_digests)?)
+                  Value::Array(self.decode_array(sub_arr, disclosures, processed_digests, &claim_path, report)?)
                 }
                 Value::Object(ref sub_obj) => {
-                  Value::Object(self.decode_object(sub_obj, disclosures, processed_digests)?)
+                  Value::Object(self.decode_object(sub_obj, disclosures, processed_digests, &claim_path, report)?)
                 }
                 _ => disclosure.claim_value.clone(),
               };
 
               output.insert(claim_name, recursively_decoded);
+              if let Some(report) = report {
+                report.pass(CheckKind::Disclosure, Some(claim_path), Some(digest_str));
+              }
             }
           }
           if output
@@ -97,13 +149,15 @@ impl SdObjectDecoder {
           }
         }
         Value::Object(object) => {
-          let decoded_object = self.decode_object(object, disclosures, processed_digests)?;
+          let decoded_object =
+            self.decode_object(object, disclosures, processed_digests, &child_path(path, key), report)?;
           if !decoded_object.is_empty() {
             output.insert(key.to_string(), Value::Object(decoded_object));
           }
         }
         Value::Array(array) => {
-          let decoded_array = self.decode_array(array, disclosures, processed_digests)?;
+          let decoded_array =
+            self.decode_array(array, disclosures, processed_digests, &child_path(path, key), report)?;
           if !decoded_array.is_empty() {
             output.insert(key.to_string(), Value::Array(decoded_array));
           }
@@ -120,52 +174,82 @@ impl SdObjectDecoder {
     array: &[Value],
     disclosures: &HashMap<String, Disclosure>,
     processed_digests: &mut Vec<String>,
+    path: &str,
+    report: &mut Option<&mut VerificationReport>,
   ) -> Result<Vec<Value>, Error> {
     let mut output: Vec<Value> = vec![];
-    for value in array.iter() {
+    for (index, value) in array.iter().enumerate() {
+      let element_path = child_path(path, &index.to_string());
       if let Some(object) = value.as_object() {
         for (key, value) in object.iter() {
           if key == ARRAY_DIGEST_KEY {
             if object.keys().len() != 1 {
-              return Err(Error::InvalidArrayDisclosureObject);
+              failure(
+                report,
+                CheckKind::Disclosure,
+                &element_path,
+                None,
+                Error::InvalidArrayDisclosureObject,
+              )?;
+              break;
             }
 
-            let digest_in_array = value
-              .as_str()
-              .ok_or(Error::DataTypeMismatch(format!("{} is not a string", key)))?
-              .to_string();
+            let Some(digest_in_array) = value.as_str().map(ToString::to_string) else {
+              let error = Error::DataTypeMismatch(format!("{} is not a string", key));
+              failure(report, CheckKind::Disclosure, &element_path, None, error)?;
+              break;
+            };
 
             // Reject if any digests were found more than once.
             if processed_digests.contains(&digest_in_array) {
-              return Err(Error::DuplicateDigestError(digest_in_array));
+              let error = Error::DuplicateDigestError(digest_in_array.clone());
+              failure(
+                report,
+                CheckKind::DuplicateDigest,
+                &element_path,
+                Some(digest_in_array),
+                error,
+              )?;
+              break;
             }
             if let Some(disclosure) = disclosures.get(&digest_in_array) {
+              processed_digests.push(digest_in_array.clone());
               if disclosure.claim_name.is_some() {
-                return Err(Error::InvalidDisclosure("array length must be 2".to_string()));
+                let error = Error::InvalidDisclosure("array length must be 2".to_string());
+                failure(
+                  report,
+                  CheckKind::Disclosure,
+                  &element_path,
+                  Some(digest_in_array),
+                  error,
+                )?;
+                break;
               }
-              processed_digests.push(digest_in_array.clone());
               // Recursively decoded the disclosed values.
               let recursively_decoded = match disclosure.claim_value {
                 Value::Array(ref sub_arr) => {
-                  Value::Array(self.decode_array(sub_arr, disclosures, processed_digests)?)
+                  Value::Array(self.decode_array(sub_arr, disclosures, processed_digests, &element_path, report)?)
                 }
                 Value::Object(ref sub_obj) => {
-                  Value::Object(self.decode_object(sub_obj, disclosures, processed_digests)?)
+                  Value::Object(self.decode_object(sub_obj, disclosures, processed_digests, &element_path, report)?)
                 }
                 _ => disclosure.claim_value.clone(),
               };
 
               output.push(recursively_decoded);
+              if let Some(report) = report {
+                report.pass(CheckKind::Disclosure, Some(element_path.clone()), Some(digest_in_array));
+              }
             }
           } else {
-            let decoded_object = self.decode_object(object, disclosures, processed_digests)?;
+            let decoded_object = self.decode_object(object, disclosures, processed_digests, &element_path, report)?;
             output.push(Value::Object(decoded_object));
             break;
           }
         }
       } else if let Some(arr) = value.as_array() {
         // Nested arrays need to be decoded too.
-        let decoded = self.decode_array(arr, disclosures, processed_digests)?;
+        let decoded = self.decode_array(arr, disclosures, processed_digests, &element_path, report)?;
         output.push(Value::Array(decoded));
       } else {
         // Append the rest of the values.
@@ -177,6 +261,28 @@ impl SdObjectDecoder {
   }
 }
 
+/// Records `error` in `report` when decoding with a report, returns it otherwise.
+fn failure(
+  report: &mut Option<&mut VerificationReport>,
+  kind: CheckKind,
+  path: &str,
+  digest: Option<String>,
+  error: Error,
+) -> Result<(), Error> {
+  match report {
+    Some(report) => {
+      report.fail(kind, Some(path.to_string()), digest, error);
+      Ok(())
+    }
+    None => Err(error),
+  }
+}
+
+/// Appends `key` to the JSON pointer `path`, escaping it as per RFC 6901.
+fn child_path(path: &str, key: &str) -> String {
+  format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
 #[cfg(test)]
 mod test {
   use std::collections::HashMap;
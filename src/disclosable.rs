@@ -0,0 +1,144 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::Result;
+
+/// A typed credential payload that knows which of its claims are selectively disclosable.
+///
+/// Usually derived with `#[derive(SelectivelyDisclosable)]` - requires the `derive` feature - where the fields to
+/// conceal are marked with:
+/// - `#[sd]`: the field is selectively disclosable.
+/// - `#[sd(each)]`: every element of the array held by the field is selectively disclosable.
+/// - `#[sd(nested)]`: the claims of the field's value, itself [`SelectivelyDisclosable`], are selectively disclosable
+///   as its own concealment plan describes. The field itself isn't concealed.
+/// - `#[sd(decoys = N)]`: `N` decoy digests are added to the object of the struct, next to the field - or to the array
+///   when combined with `each`.
+///
+/// Field names follow the `#[serde(rename = "...")]` and `#[serde(rename_all = "...")]` attributes. Fields with
+/// `#[serde(flatten)]` can only be combined with `#[sd(nested)]`, whose claims are then those of the struct itself.
+///
+/// ## Example
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize, SelectivelyDisclosable)]
+/// struct Identity {
+///   iss: String,
+///   #[sd(decoys = 2)]
+///   given_name: String,
+///   #[sd(each)]
+///   nationalities: Vec<String>,
+///   #[sd(nested)]
+///   address: Address,
+/// }
+///
+/// #[derive(Serialize, Deserialize, SelectivelyDisclosable)]
+/// struct Address {
+///   #[sd]
+///   street_address: String,
+///   country: String,
+/// }
+///
+/// let builder = SdJwtBuilder::new(&identity)?.apply_plan(&identity.concealment_plan())?;
+/// ```
+pub trait SelectivelyDisclosable: Serialize + DeserializeOwned {
+  /// Returns the claims of this value to make concealable, and the decoys to add.
+  fn concealment_plan(&self) -> ConcealmentPlan;
+
+  /// Decodes a disclosed object - see [`crate::SdJwt::verify`] - back into this type.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `object` doesn't match this type, e.g. because a required claim
+  /// wasn't disclosed.
+  fn from_disclosed_object(object: JsonObject) -> Result<Self>;
+}
+
+/// An absent value has no claim to conceal.
+impl<T: SelectivelyDisclosable> SelectivelyDisclosable for Option<T> {
+  fn concealment_plan(&self) -> ConcealmentPlan {
+    self
+      .as_ref()
+      .map(SelectivelyDisclosable::concealment_plan)
+      .unwrap_or_default()
+  }
+
+  fn from_disclosed_object(object: JsonObject) -> Result<Self> {
+    T::from_disclosed_object(object).map(Some)
+  }
+}
+
+/// The claims of an object to make concealable, and the decoys to add to it.
+/// See [`crate::SdJwtBuilder::apply_plan`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConcealmentPlan {
+  paths: Vec<String>,
+  decoys: Vec<(String, usize)>,
+}
+
+impl ConcealmentPlan {
+  /// Creates a new empty [`ConcealmentPlan`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Makes the claim at `path` - a [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) - concealable.
+  pub fn conceal(&mut self, path: impl Into<String>) -> &mut Self {
+    self.paths.push(path.into());
+    self
+  }
+
+  /// Makes every element of the array at `path`, whose value is `array`, concealable.
+  /// ## Notes
+  /// Nothing is concealed if `array` doesn't serialize to a JSON array.
+  pub fn conceal_each<T>(&mut self, path: &str, array: &T) -> &mut Self
+  where
+    T: Serialize + ?Sized,
+  {
+    if let Ok(Value::Array(elements)) = serde_json::to_value(array) {
+      self
+        .paths
+        .extend((0..elements.len()).map(|index| format!("{path}/{index}")));
+    }
+    self
+  }
+
+  /// Adds `number_of_decoys` decoy digests to the object or array at `path`.
+  pub fn add_decoys(&mut self, path: impl Into<String>, number_of_decoys: usize) -> &mut Self {
+    self.decoys.push((path.into(), number_of_decoys));
+    self
+  }
+
+  /// Adds the claims and decoys of `plan`, which describes the value at `path`, to this plan.
+  pub fn nest(&mut self, path: &str, plan: ConcealmentPlan) -> &mut Self {
+    self
+      .paths
+      .extend(plan.paths.into_iter().map(|nested_path| format!("{path}{nested_path}")));
+    self.decoys.extend(
+      plan
+        .decoys
+        .into_iter()
+        .map(|(nested_path, number_of_decoys)| (format!("{path}{nested_path}"), number_of_decoys)),
+    );
+    self
+  }
+
+  /// Returns the paths of the claims to make concealable, in order.
+  pub fn paths(&self) -> &[String] {
+    &self.paths
+  }
+
+  /// Returns the paths decoys are added to, together with their number.
+  pub fn decoys(&self) -> &[(String, usize)] {
+    &self.decoys
+  }
+}
+
+/// Decodes `object` into `T`, for the implementations of [`SelectivelyDisclosable::from_disclosed_object`].
+#[doc(hidden)]
+pub fn decode_disclosed_object<T: DeserializeOwned>(object: JsonObject) -> Result<T> {
+  serde_json::from_value(Value::Object(object))
+    .map_err(|e| Error::DeserializationError(format!("the disclosed object doesn't match the expected type: {e}")))
+}
@@ -2,9 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::Error;
+use crate::ARRAY_DIGEST_KEY;
+use crate::DIGESTS_KEY;
+use serde::Serialize;
 use serde_json::json;
+use serde_json::ser::Formatter;
+use serde_json::Serializer;
 use serde_json::Value;
 use std::fmt::Display;
+use std::io;
 
 /// A disclosable value.
 /// Both object properties and array elements disclosures are supported.
@@ -32,6 +38,9 @@ impl Disclosure {
   /// Creates a new instance of [`Disclosure`].
   ///
   /// Use `.to_string()` to get the actual disclosure.
+  ///
+  /// ## Notes
+  /// The disclosure's JSON array is formatted like in the specification's examples, so that they can be reproduced.
   pub(crate) fn new(salt: String, claim_name: Option<String>, claim_value: Value) -> Self {
     let string_encoded = {
       let json_input = if let Some(name) = claim_name.as_deref() {
@@ -40,7 +49,12 @@ impl Disclosure {
         json!([salt, claim_value])
       };
 
-      multibase::Base::Base64Url.encode(json_input.to_string())
+      let mut json = vec![];
+      json_input
+        .serialize(&mut Serializer::with_formatter(&mut json, SpecFormatter))
+        .expect("serializing a JSON value into a vector cannot fail");
+
+      multibase::Base::Base64Url.encode(json)
     };
     Self {
       salt,
@@ -91,6 +105,18 @@ impl Disclosure {
         unparsed: disclosure.to_string(),
       })
     } else if decoded.len() == 3 {
+      let claim_name = decoded
+        .get(1)
+        .ok_or(Error::InvalidDisclosure("invalid claim name".to_string()))?
+        .as_str()
+        .ok_or(Error::InvalidDisclosure(
+          "claim name could not be parsed as a string".to_string(),
+        ))?;
+      if claim_name == DIGESTS_KEY || claim_name == ARRAY_DIGEST_KEY {
+        return Err(Error::InvalidDisclosure(format!(
+          "claim name \"{claim_name}\" is reserved"
+        )));
+      }
       Ok(Self {
         salt: decoded
           .first()
@@ -100,16 +126,7 @@ impl Disclosure {
             "salt could not be parsed as a string".to_string(),
           ))?
           .to_owned(),
-        claim_name: Some(
-          decoded
-            .get(1)
-            .ok_or(Error::InvalidDisclosure("invalid claim name".to_string()))?
-            .as_str()
-            .ok_or(Error::InvalidDisclosure(
-              "claim name could not be parsed as a string".to_string(),
-            ))?
-            .to_owned(),
-        ),
+        claim_name: Some(claim_name.to_owned()),
         claim_value: decoded
           .get(2)
           .ok_or(Error::InvalidDisclosure("invalid claim name".to_string()))?
@@ -141,6 +158,41 @@ impl PartialEq for Disclosure {
   }
 }
 
+/// Formats JSON the way the specification's examples - generated with Python's `json.dumps` - do: with a space
+/// after `,` and `:`, and with non-ASCII characters escaped.
+struct SpecFormatter;
+
+impl Formatter for SpecFormatter {
+  fn begin_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+    if first {
+      Ok(())
+    } else {
+      writer.write_all(b", ")
+    }
+  }
+
+  fn begin_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+    self.begin_array_value(writer, first)
+  }
+
+  fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+    writer.write_all(b": ")
+  }
+
+  fn write_string_fragment<W: ?Sized + io::Write>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()> {
+    for c in fragment.chars() {
+      if c.is_ascii() {
+        writer.write_all(&[c as u8])?;
+      } else {
+        for unit in c.encode_utf16(&mut [0; 2]) {
+          write!(writer, "\\u{unit:04x}")?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::Disclosure;
@@ -158,5 +210,34 @@ mod test {
     let parsed =
       Disclosure::parse("WyIyR0xDNDJzS1F2ZUNmR2ZyeU5STjl3IiwgInRpbWUiLCAiMjAxMi0wNC0yM1QxODoyNVoiXQ").unwrap();
     assert_eq!(parsed, disclosure);
+    assert_eq!(parsed.as_str(), disclosure.as_str());
+  }
+
+  // Test values from the examples of the specification, non-ASCII characters included.
+  #[test]
+  fn disclosures_are_formatted_like_the_specification_examples() {
+    let disclosure = Disclosure::new(
+      "G02NSrQfjFXQ7Io09syajA".to_string(),
+      Some("region".to_owned()),
+      "港区".to_owned().into(),
+    );
+    assert_eq!(
+      disclosure.as_str(),
+      "WyJHMDJOU3JRZmpGWFE3SW8wOXN5YWpBIiwgInJlZ2lvbiIsICJcdTZlMmZcdTUzM2EiXQ"
+    );
+
+    let disclosure = Disclosure::new("lklxF5jMYlGTPUovMNIvCA".to_string(), None, "FR".to_owned().into());
+    assert_eq!(disclosure.as_str(), "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgIkZSIl0");
+  }
+
+  #[test]
+  fn reserved_claim_names_are_rejected() {
+    for name in ["_sd", "..."] {
+      let disclosure = Disclosure::new("2GLC42sKQveCfGfryNRN9w".to_string(), Some(name.to_owned()), 42.into());
+      assert!(matches!(
+        Disclosure::parse(disclosure.as_str()),
+        Err(crate::Error::InvalidDisclosure(_))
+      ));
+    }
   }
 }
@@ -0,0 +1,311 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::Payload;
+use aes_gcm::Aes128Gcm;
+use aes_gcm::Aes256Gcm;
+use aes_gcm::KeyInit;
+use aes_gcm::Nonce;
+use async_trait::async_trait;
+use multibase::Base;
+use p256::ecdh::EphemeralSecret;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::PublicKey;
+use p256::SecretKey;
+use serde_json::Value;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::JweDecrypter;
+use crate::JweEncrypter;
+use crate::Result;
+
+/// Key management algorithm implemented by [`EcdhEsEncrypter`] and [`EcdhEsDecrypter`].
+pub const ECDH_ES: &str = "ECDH-ES";
+
+const IV_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// A [`JweEncrypter`] encrypting to a P-256 public key of the recipient, using direct key agreement with an ephemeral
+/// key - "ECDH-ES" - and "A128GCM" or "A256GCM" as content encryption algorithm, see
+/// [RFC 7518](https://www.rfc-editor.org/rfc/rfc7518.html#section-4.6).
+#[derive(Debug, Clone)]
+pub struct EcdhEsEncrypter {
+  recipient: PublicKey,
+  kid: Option<String>,
+}
+
+impl EcdhEsEncrypter {
+  /// Creates a new [`EcdhEsEncrypter`] encrypting to the recipient's public key `jwk`, an EC JWK on curve "P-256".
+  /// The key's `kid`, if any, is added to the header of created JWEs.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `jwk` isn't a valid P-256 public key.
+  pub fn from_jwk(jwk: &JsonObject) -> Result<Self> {
+    Ok(Self {
+      recipient: public_key_from_jwk(jwk)?,
+      kid: jwk.get("kid").and_then(Value::as_str).map(ToOwned::to_owned),
+    })
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JweEncrypter for EcdhEsEncrypter {
+  type Error = Error;
+
+  async fn encrypt(&self, header: &JsonObject, plaintext: &[u8]) -> Result<String> {
+    if header.get("alg").and_then(Value::as_str) != Some(ECDH_ES) {
+      return Err(Error::JweEncryptionFailure(format!("\"alg\" must be \"{ECDH_ES}\"")));
+    }
+    let enc = header.get("enc").and_then(Value::as_str).unwrap_or_default();
+    let key_size = content_key_size(enc).map_err(Error::JweEncryptionFailure)?;
+
+    let ephemeral_secret = EphemeralSecret::random(&mut rand::thread_rng());
+    let mut header = header.clone();
+    header.insert(
+      "epk".to_string(),
+      public_key_to_jwk(&ephemeral_secret.public_key()).into(),
+    );
+    if let Some(kid) = &self.kid {
+      header.entry("kid").or_insert_with(|| kid.clone().into());
+    }
+
+    let shared_secret = ephemeral_secret.diffie_hellman(&self.recipient);
+    let cek = concat_kdf(shared_secret.raw_secret_bytes(), &header, enc, key_size)?;
+    let encoded_header = Base::Base64Url.encode(
+      serde_json::to_vec(&header)
+        .map_err(|e| Error::JweEncryptionFailure(format!("header serialization failed: {e}")))?,
+    );
+    let iv = crate::random_bytes(IV_SIZE);
+    let payload = Payload {
+      msg: plaintext,
+      aad: encoded_header.as_bytes(),
+    };
+    let mut ciphertext = encrypt_content(&cek, &iv, payload)
+      .map_err(|_| Error::JweEncryptionFailure("content encryption failed".to_string()))?;
+    let tag = ciphertext.split_off(ciphertext.len() - TAG_SIZE);
+
+    Ok(format!(
+      "{encoded_header}..{}.{}.{}",
+      Base::Base64Url.encode(iv),
+      Base::Base64Url.encode(ciphertext),
+      Base::Base64Url.encode(tag)
+    ))
+  }
+}
+
+/// A [`JweDecrypter`] for JWEs created by an [`EcdhEsEncrypter`], holding the recipient's P-256 private key.
+#[derive(Clone)]
+pub struct EcdhEsDecrypter {
+  secret: SecretKey,
+}
+
+impl std::fmt::Debug for EcdhEsDecrypter {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("EcdhEsDecrypter").finish_non_exhaustive()
+  }
+}
+
+impl EcdhEsDecrypter {
+  /// Creates a new [`EcdhEsDecrypter`] with a freshly generated key, e.g. as the ephemeral key of a verifier.
+  pub fn generate() -> Self {
+    Self {
+      secret: SecretKey::random(&mut rand::thread_rng()),
+    }
+  }
+
+  /// Creates a new [`EcdhEsDecrypter`] from the private key `jwk`, an EC JWK on curve "P-256" carrying `d`.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if `jwk` isn't a valid P-256 private key.
+  pub fn from_jwk(jwk: &JsonObject) -> Result<Self> {
+    let d = jwk_coordinate(jwk, "d")?;
+    let secret =
+      SecretKey::from_slice(&d).map_err(|_| Error::DeserializationError("invalid P-256 private key".to_string()))?;
+    if public_key_from_jwk(jwk)? != secret.public_key() {
+      return Err(Error::DeserializationError(
+        "the public key doesn't match the private key".to_string(),
+      ));
+    }
+
+    Ok(Self { secret })
+  }
+
+  /// Returns the public key, as a JWK, JWEs must be encrypted to.
+  pub fn public_jwk(&self) -> JsonObject {
+    public_key_to_jwk(&self.secret.public_key())
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JweDecrypter for EcdhEsDecrypter {
+  type Error = Error;
+
+  async fn decrypt(&self, header: &JsonObject, jwe: &str) -> Result<Vec<u8>> {
+    if header.get("alg").and_then(Value::as_str) != Some(ECDH_ES) {
+      return Err(Error::JweDecryptionFailure(format!("\"alg\" must be \"{ECDH_ES}\"")));
+    }
+    let enc = header.get("enc").and_then(Value::as_str).unwrap_or_default();
+    let key_size = content_key_size(enc).map_err(Error::JweDecryptionFailure)?;
+    let epk = header
+      .get("epk")
+      .and_then(Value::as_object)
+      .ok_or_else(|| Error::JweDecryptionFailure("missing \"epk\"".to_string()))?;
+    let epk = public_key_from_jwk(epk).map_err(|e| Error::JweDecryptionFailure(format!("invalid \"epk\": {e}")))?;
+
+    let [encoded_header, encrypted_key, iv, ciphertext, tag] = jwe.split('.').collect::<Vec<_>>()[..] else {
+      return Err(Error::JweDecryptionFailure("expected 5 segments".to_string()));
+    };
+    if !encrypted_key.is_empty() {
+      return Err(Error::JweDecryptionFailure(
+        "direct key agreement doesn't use an encrypted key".to_string(),
+      ));
+    }
+    let decode = |segment: &str| {
+      Base::Base64Url
+        .decode(segment)
+        .map_err(|_| Error::JweDecryptionFailure("invalid base64url encoding".to_string()))
+    };
+    let iv = decode(iv)?;
+    if iv.len() != IV_SIZE {
+      return Err(Error::JweDecryptionFailure("invalid initialization vector".to_string()));
+    }
+    let mut ciphertext = decode(ciphertext)?;
+    ciphertext.extend(decode(tag)?);
+
+    let shared_secret = p256::ecdh::diffie_hellman(self.secret.to_nonzero_scalar(), epk.as_affine());
+    let cek = concat_kdf(shared_secret.raw_secret_bytes(), header, enc, key_size)?;
+    let payload = Payload {
+      msg: &ciphertext,
+      aad: encoded_header.as_bytes(),
+    };
+    decrypt_content(&cek, &iv, payload)
+      .map_err(|_| Error::JweDecryptionFailure("content decryption failed".to_string()))
+  }
+}
+
+/// Returns the key size, in bytes, of the content encryption algorithm `enc`.
+fn content_key_size(enc: &str) -> std::result::Result<usize, String> {
+  match enc {
+    "A128GCM" => Ok(16),
+    "A256GCM" => Ok(32),
+    _ => Err(format!("unsupported content encryption algorithm \"{enc}\"")),
+  }
+}
+
+/// Encrypts `payload` with AES-GCM, using the key size of `cek`, returning the ciphertext followed by the tag.
+fn encrypt_content(cek: &[u8], iv: &[u8], payload: Payload) -> std::result::Result<Vec<u8>, aes_gcm::Error> {
+  match cek.len() {
+    16 => Aes128Gcm::new_from_slice(cek)
+      .expect("valid key size")
+      .encrypt(Nonce::from_slice(iv), payload),
+    _ => Aes256Gcm::new_from_slice(cek)
+      .expect("valid key size")
+      .encrypt(Nonce::from_slice(iv), payload),
+  }
+}
+
+/// Decrypts `payload`, the ciphertext followed by the tag, with AES-GCM, using the key size of `cek`.
+fn decrypt_content(cek: &[u8], iv: &[u8], payload: Payload) -> std::result::Result<Vec<u8>, aes_gcm::Error> {
+  match cek.len() {
+    16 => Aes128Gcm::new_from_slice(cek)
+      .expect("valid key size")
+      .decrypt(Nonce::from_slice(iv), payload),
+    _ => Aes256Gcm::new_from_slice(cek)
+      .expect("valid key size")
+      .decrypt(Nonce::from_slice(iv), payload),
+  }
+}
+
+/// Derives the content encryption key from the shared secret `z` with the Concat KDF, see
+/// [RFC 7518](https://www.rfc-editor.org/rfc/rfc7518.html#section-4.6.2).
+fn concat_kdf(z: &[u8], header: &JsonObject, enc: &str, key_size: usize) -> Result<Vec<u8>> {
+  let party_info = |name: &str| -> Result<Vec<u8>> {
+    header
+      .get(name)
+      .and_then(Value::as_str)
+      .map(|info| {
+        Base::Base64Url
+          .decode(info)
+          .map_err(|_| Error::DeserializationError(format!("\"{name}\" is not base64url encoded")))
+      })
+      .transpose()
+      .map(Option::unwrap_or_default)
+  };
+  let mut other_info = vec![];
+  for info in [enc.as_bytes().to_vec(), party_info("apu")?, party_info("apv")?] {
+    other_info.extend((info.len() as u32).to_be_bytes());
+    other_info.extend(info);
+  }
+  other_info.extend(((key_size * 8) as u32).to_be_bytes());
+
+  // A single round of SHA-256 produces enough key material for both supported key sizes.
+  let digest = Sha256::new()
+    .chain_update(1u32.to_be_bytes())
+    .chain_update(z)
+    .chain_update(other_info)
+    .finalize();
+  Ok(digest[..key_size].to_vec())
+}
+
+fn jwk_coordinate(jwk: &JsonObject, name: &str) -> Result<Vec<u8>> {
+  jwk
+    .get(name)
+    .and_then(Value::as_str)
+    .and_then(|value| Base::Base64Url.decode(value).ok())
+    .filter(|value| value.len() == 32)
+    .ok_or_else(|| Error::DeserializationError(format!("invalid or missing JWK parameter \"{name}\"")))
+}
+
+fn public_key_from_jwk(jwk: &JsonObject) -> Result<PublicKey> {
+  if jwk.get("kty").and_then(Value::as_str) != Some("EC") || jwk.get("crv").and_then(Value::as_str) != Some("P-256") {
+    return Err(Error::DeserializationError(
+      "expected an EC JWK on curve \"P-256\"".to_string(),
+    ));
+  }
+  let mut point = vec![0x04];
+  point.extend(jwk_coordinate(jwk, "x")?);
+  point.extend(jwk_coordinate(jwk, "y")?);
+
+  PublicKey::from_sec1_bytes(&point).map_err(|_| Error::DeserializationError("invalid P-256 public key".to_string()))
+}
+
+fn public_key_to_jwk(public_key: &PublicKey) -> JsonObject {
+  let point = public_key.to_encoded_point(false);
+  let mut jwk = JsonObject::new();
+  jwk.insert("kty".to_string(), "EC".into());
+  jwk.insert("crv".to_string(), "P-256".into());
+  jwk.insert(
+    "x".to_string(),
+    Base::Base64Url.encode(point.x().expect("uncompressed point")).into(),
+  );
+  jwk.insert(
+    "y".to_string(),
+    Base::Base64Url.encode(point.y().expect("uncompressed point")).into(),
+  );
+
+  jwk
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn concat_kdf_matches_rfc7518_example() {
+    // https://www.rfc-editor.org/rfc/rfc7518.html#appendix-C
+    let z = [
+      158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49, 110, 163, 218, 128, 106, 72,
+      246, 218, 167, 121, 140, 254, 144, 196,
+    ];
+    let mut header = JsonObject::new();
+    header.insert("apu".to_string(), "QWxpY2U".into());
+    header.insert("apv".to_string(), "Qm9i".into());
+
+    let cek = concat_kdf(&z, &header, "A128GCM", 16).unwrap();
+    assert_eq!(Base::Base64Url.encode(cek), "VqqN6vgjbSBcIijNcacQGg");
+  }
+}
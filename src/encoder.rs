@@ -7,6 +7,9 @@ use super::Hasher;
 use super::Sha256Hasher;
 use crate::Error;
 use crate::Result;
+use crate::SaltGenerator;
+use crate::SaltRng;
+use crate::ThreadRngSaltGenerator;
 use json_pointer::JsonPointer;
 use rand::Rng;
 use serde_json::json;
@@ -21,7 +24,7 @@ pub const HEADER_TYP: &str = "sd-jwt";
 
 /// Transforms a JSON object into an SD-JWT object by substituting selected values
 /// with their corresponding disclosure digests.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct SdObjectEncoder<H> {
   /// The object in JSON format.
   pub(crate) object: Value,
@@ -30,6 +33,8 @@ pub struct SdObjectEncoder<H> {
   pub(crate) salt_size: usize,
   /// The hash function used to create digests.
   pub(crate) hasher: H,
+  /// The source of the random data of salts and decoys.
+  pub(crate) salt_generator: Box<dyn SaltGenerator>,
 }
 
 #[cfg(feature = "sha")]
@@ -53,9 +58,15 @@ impl<H: Hasher> SdObjectEncoder<H> {
       object,
       salt_size,
       hasher,
+      salt_generator: Box::new(ThreadRngSaltGenerator),
     })
   }
 
+  /// Replaces the source of the random data of salts and decoys.
+  pub fn set_salt_generator<G: SaltGenerator + 'static>(&mut self, salt_generator: G) {
+    self.salt_generator = Box::new(salt_generator);
+  }
+
   /// Substitutes a value with the digest of its disclosure.
   ///
   /// `path` indicates the pointer to the value that will be concealed using the syntax of
@@ -65,9 +76,15 @@ impl<H: Hasher> SdObjectEncoder<H> {
   /// * [`Error::InvalidPath`] if pointer is invalid.
   /// * [`Error::DataTypeMismatch`] if existing SD format is invalid.
   pub fn conceal(&mut self, path: &str) -> Result<Disclosure> {
-    // Determine salt.
-    let salt = Self::gen_rand(self.salt_size);
+    let salt = Self::gen_rand(self.salt_generator.as_mut(), self.salt_size);
+    self.conceal_with_salt(path, salt)
+  }
 
+  /// Substitutes a value with the digest of its disclosure, using `salt` instead of a random one.
+  ///
+  /// ## Error
+  /// See [`SdObjectEncoder::conceal`].
+  pub fn conceal_with_salt(&mut self, path: &str, salt: String) -> Result<Disclosure> {
     let element_pointer = path
       .parse::<JsonPointer<_, _>>()
       .map_err(|_| Error::InvalidPath(path.to_string()))?;
@@ -152,11 +169,11 @@ impl<H: Hasher> SdObjectEncoder<H> {
       .get_mut(&mut self.object)
       .map_err(|_| Error::InvalidPath(path.to_string()))?;
     if let Some(object) = value.as_object_mut() {
-      let (_, hash) = Self::random_digest(&self.hasher, self.salt_size, false);
+      let (_, hash) = Self::random_digest(&self.hasher, self.salt_generator.as_mut(), self.salt_size, false);
       Self::add_digest_to_object(object, hash)?;
       Ok(())
     } else if let Some(array) = value.as_array_mut() {
-      let (_, hash) = Self::random_digest(&self.hasher, self.salt_size, true);
+      let (_, hash) = Self::random_digest(&self.hasher, self.salt_generator.as_mut(), self.salt_size, true);
       let tripledot = json!({ARRAY_DIGEST_KEY: hash});
       array.push(tripledot);
       Ok(())
@@ -173,7 +190,7 @@ impl<H: Hasher> SdObjectEncoder<H> {
         let idx = value
           .iter()
           .enumerate()
-          .find(|(_, value)| value.as_str().is_some_and(|s| s > &digest))
+          .find(|(_, value)| value.as_str().is_some_and(|s| s > digest.as_str()))
           .map(|(pos, _)| pos)
           .unwrap_or(value.len());
         value.insert(idx, Value::String(digest));
@@ -188,31 +205,43 @@ impl<H: Hasher> SdObjectEncoder<H> {
     Ok(())
   }
 
-  fn random_digest(hasher: &dyn Hasher, salt_len: usize, array_entry: bool) -> (Disclosure, String) {
-    let mut rng = rand::thread_rng();
-    let salt = Self::gen_rand(salt_len);
-    let decoy_value_length = rng.gen_range(20..=100);
+  fn random_digest(
+    hasher: &dyn Hasher,
+    salt_generator: &mut dyn SaltGenerator,
+    salt_len: usize,
+    array_entry: bool,
+  ) -> (Disclosure, String) {
+    let salt = Self::gen_rand(salt_generator, salt_len);
+    let decoy_value_length = SaltRng(salt_generator).gen_range(20..=100);
     let decoy_claim_name = if array_entry {
       None
     } else {
-      let decoy_claim_name_length = rng.gen_range(4..=10);
-      Some(Self::gen_rand(decoy_claim_name_length))
+      let decoy_claim_name_length = SaltRng(salt_generator).gen_range(4..=10);
+      Some(Self::gen_rand(salt_generator, decoy_claim_name_length))
     };
-    let decoy_value = Self::gen_rand(decoy_value_length);
+    let decoy_value = Self::gen_rand(salt_generator, decoy_value_length);
     let disclosure = Disclosure::new(salt, decoy_claim_name, Value::String(decoy_value));
     let hash = hasher.encoded_digest(disclosure.as_str());
     (disclosure, hash)
   }
 
-  fn gen_rand(len: usize) -> String {
+  fn gen_rand(salt_generator: &mut dyn SaltGenerator, len: usize) -> String {
     let mut bytes = vec![0; len];
-    let mut rng = rand::thread_rng();
-    rng.fill(&mut bytes[..]);
-
+    salt_generator.fill_bytes(&mut bytes);
     multibase::Base::Base64Url.encode(bytes)
   }
 }
 
+/// Returns `len` random bytes, as used for initialization vectors.
+#[cfg(feature = "ecdh-es")]
+pub(crate) fn random_bytes(len: usize) -> Vec<u8> {
+  let mut bytes = vec![0; len];
+  let mut rng = rand::thread_rng();
+  rng.fill(&mut bytes[..]);
+
+  bytes
+}
+
 #[cfg(test)]
 mod test {
 
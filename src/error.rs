@@ -4,7 +4,7 @@
 /// Alias for a `Result` with the error type [`Error`].
 pub type Result<T> = ::core::result::Result<T, Error>;
 
-#[derive(Debug, thiserror::Error, strum::IntoStaticStr, PartialEq)]
+#[derive(Debug, Clone, thiserror::Error, strum::IntoStaticStr, PartialEq)]
 #[non_exhaustive]
 pub enum Error {
   #[error("invalid input: {0}")]
@@ -43,6 +43,75 @@ pub enum Error {
   #[error("JWS creation failure: {0}")]
   JwsSignerFailure(String),
 
+  #[error("JWS verification failure: {0}")]
+  JwsVerificationFailure(String),
+
   #[error("Missing required KB-JWT")]
   MissingKeyBindingJwt,
+
+  #[error("invalid KB-JWT: {0}")]
+  InvalidKeyBindingJwt(String),
+
+  #[error("the JWT expired at {0}")]
+  ExpiredJwt(i64),
+
+  #[error("the JWT is not valid before {0}")]
+  JwtNotYetValid(i64),
+
+  #[error("verification policy violation: {0}")]
+  PolicyViolation(String),
+
+  #[error("key resolution failure: {0}")]
+  KeyResolutionFailure(String),
+
+  #[error("invalid X.509 certificate chain: {0}")]
+  InvalidX509Chain(String),
+
+  #[error("COSE signature creation failure: {0}")]
+  CoseSignerFailure(String),
+
+  #[error("COSE signature verification failure: {0}")]
+  CoseVerificationFailure(String),
+
+  #[error("invalid key binding token: {0}")]
+  InvalidKeyBindingToken(String),
+
+  #[error("JWE encryption failure: {0}")]
+  JweEncryptionFailure(String),
+
+  #[error("JWE decryption failure: {0}")]
+  JweDecryptionFailure(String),
+
+  #[error("invalid SD-JWT VC: {0}")]
+  InvalidSdJwtVc(String),
+
+  #[error("type metadata resolution failure: {0}")]
+  TypeMetadataResolutionFailure(String),
+
+  #[error("integrity check failure: {0}")]
+  IntegrityCheckFailure(String),
+
+  #[error("JSON schema validation failure: {0}")]
+  SchemaValidationFailure(String),
+
+  #[error("selective disclosability violation: {0}")]
+  DisclosabilityViolation(String),
+
+  #[error("claim \"{0}\" must not be selectively disclosable")]
+  NonConcealableClaim(String),
+
+  #[error("invalid status list: {0}")]
+  InvalidStatusList(String),
+
+  #[error("status list failure: {0}")]
+  StatusListFailure(String),
+
+  #[error("the credential has been revoked")]
+  CredentialRevoked,
+
+  #[error("the credential is suspended")]
+  CredentialSuspended,
+
+  #[error("unknown JWS algorithm \"{0}\"")]
+  UnknownAlgorithm(String),
 }
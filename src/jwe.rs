@@ -0,0 +1,47 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use multibase::Base;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::Result;
+
+/// JSON Web Encryption (JWE) Encrypter.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JweEncrypter {
+  type Error: Display;
+  /// Encrypts `plaintext` into a JWE in compact serialization, with `header` as its protected header.
+  /// The key management and content encryption algorithms must be read from the `header.alg` and `header.enc`
+  /// properties.
+  async fn encrypt(&self, header: &JsonObject, plaintext: &[u8]) -> std::result::Result<String, Self::Error>;
+}
+
+/// JSON Web Encryption (JWE) Decrypter.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JweDecrypter {
+  type Error: Display;
+  /// Decrypts the JWE in compact serialization `jwe`, whose decoded protected header is `header`, returning its
+  /// plaintext.
+  async fn decrypt(&self, header: &JsonObject, jwe: &str) -> std::result::Result<Vec<u8>, Self::Error>;
+}
+
+/// Decodes the protected header of the JWE in compact serialization `jwe`.
+pub(crate) fn decode_jwe_header(jwe: &str) -> Result<JsonObject> {
+  let segments: Vec<&str> = jwe.split('.').collect();
+  if segments.len() != 5 {
+    return Err(Error::DeserializationError(
+      "JWE compact serialization must have 5 segments".to_string(),
+    ));
+  }
+  let header = Base::Base64Url
+    .decode(segments[0])
+    .map_err(|_| Error::DeserializationError("JWE header is not base64url encoded".to_string()))?;
+
+  serde_json::from_slice(&header).map_err(|e| Error::DeserializationError(format!("invalid JWE header: {e}")))
+}
@@ -0,0 +1,162 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::Result;
+
+/// A JSON Web Key Set, as defined in [RFC7517](https://www.rfc-editor.org/rfc/rfc7517.html#section-5).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwkSet {
+  keys: Vec<JsonObject>,
+}
+
+impl JwkSet {
+  /// Creates a new empty [`JwkSet`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Reads a [`JwkSet`] from the JSON file at `path`.
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+    let path = path.as_ref();
+    let json = std::fs::read(path).map_err(|e| Error::Unspecified(format!("cannot read {}: {e}", path.display())))?;
+    serde_json::from_slice(&json).map_err(|e| Error::DeserializationError(format!("invalid JWK Set: {e}")))
+  }
+
+  /// Writes this [`JwkSet`] as JSON to the file at `path`.
+  pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_vec_pretty(self).map_err(|e| Error::Unspecified(e.to_string()))?;
+    std::fs::write(path, json).map_err(|e| Error::Unspecified(format!("cannot write {}: {e}", path.display())))
+  }
+
+  /// Returns the keys of this set.
+  pub fn keys(&self) -> &[JsonObject] {
+    &self.keys
+  }
+
+  /// Returns the key identified by `kid`, if any.
+  pub fn get(&self, kid: &str) -> Option<&JsonObject> {
+    self.keys.iter().find(|jwk| key_id(jwk) == Some(kid))
+  }
+
+  /// Adds `jwk` to this set, replacing the key with the same `kid` - if any - which is returned.
+  ///
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if `jwk` has no `kid`.
+  pub fn insert(&mut self, jwk: JsonObject) -> Result<Option<JsonObject>> {
+    let kid = key_id(&jwk)
+      .ok_or_else(|| Error::DataTypeMismatch("a JWK must have a \"kid\" to be part of a JWK Set".to_string()))?
+      .to_owned();
+    let replaced = self.remove(&kid);
+    self.keys.push(jwk);
+
+    Ok(replaced)
+  }
+
+  /// Removes the key identified by `kid` from this set, returning it.
+  pub fn remove(&mut self, kid: &str) -> Option<JsonObject> {
+    let idx = self.keys.iter().position(|jwk| key_id(jwk) == Some(kid))?;
+    Some(self.keys.remove(idx))
+  }
+
+  /// Returns the number of keys in this set.
+  pub fn len(&self) -> usize {
+    self.keys.len()
+  }
+
+  /// Returns `true` if this set contains no keys.
+  pub fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+}
+
+impl FromIterator<JsonObject> for JwkSet {
+  fn from_iter<T: IntoIterator<Item = JsonObject>>(iter: T) -> Self {
+    Self {
+      keys: iter.into_iter().collect(),
+    }
+  }
+}
+
+fn key_id(jwk: &JsonObject) -> Option<&str> {
+  jwk.get("kid").and_then(Value::as_str)
+}
+
+/// Retrieves the [`JwkSet`] published at a given URL, e.g. the `jwu` of [`crate::RequiredKeyBinding::Jwu`].
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JwkSetFetcher {
+  type Error: Display;
+  /// Fetches the [`JwkSet`] published at `url`.
+  async fn fetch(&self, url: &str) -> std::result::Result<JwkSet, Self::Error>;
+}
+
+/// In-memory [`JwkSetFetcher`] mapping URLs to [`JwkSet`]s, useful as a local stand-in for remote key sets.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JwkSetFetcher for HashMap<String, JwkSet> {
+  type Error = Error;
+  async fn fetch(&self, url: &str) -> Result<JwkSet> {
+    self
+      .get(url)
+      .cloned()
+      .ok_or_else(|| Error::KeyResolutionFailure(format!("no JWK Set is available at \"{url}\"")))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_json::json;
+
+  use super::JwkSet;
+  use crate::JsonObject;
+
+  fn jwk(kid: &str, x: &str) -> JsonObject {
+    json!({ "kty": "OKP", "crv": "Ed25519", "kid": kid, "x": x })
+      .as_object()
+      .unwrap()
+      .clone()
+  }
+
+  #[test]
+  fn keys_are_rotated_by_kid() {
+    let mut jwks = JwkSet::from_iter([jwk("key-1", "AAAA"), jwk("key-2", "BBBB")]);
+    let replaced = jwks.insert(jwk("key-1", "CCCC")).unwrap();
+
+    assert_eq!(replaced, Some(jwk("key-1", "AAAA")));
+    assert_eq!(jwks.len(), 2);
+    assert_eq!(jwks.get("key-1"), Some(&jwk("key-1", "CCCC")));
+    assert!(jwks.remove("key-2").is_some());
+    assert!(jwks.get("key-2").is_none());
+  }
+
+  #[test]
+  fn keys_without_kid_are_rejected() {
+    let mut jwks = JwkSet::new();
+    let mut key = jwk("key-1", "AAAA");
+    key.remove("kid");
+    assert!(jwks.insert(key).is_err());
+  }
+
+  #[test]
+  fn round_trip_file() {
+    let path = std::env::temp_dir().join(format!("sd-jwt-jwks-{}.json", std::process::id()));
+    let jwks = JwkSet::from_iter([jwk("key-1", "AAAA")]);
+    jwks.write_to_file(&path).unwrap();
+    let read = JwkSet::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.unwrap(), jwks);
+  }
+}
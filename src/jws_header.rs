@@ -0,0 +1,307 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Display;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::Result;
+
+/// Header parameters registered by [RFC 7515](https://www.rfc-editor.org/rfc/rfc7515.html#section-4.1), which must
+/// not be listed in `crit`.
+const REGISTERED_HEADER_PARAMETERS: [&str; 11] = [
+  "alg", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty", "crit",
+];
+
+/// The algorithm a JWS is signed with, i.e. the value of its `alg` header parameter.
+///
+/// ## Notes
+/// Algorithms not listed here can be used through [`JwsAlgorithm::custom`], which parsing with [`FromStr`] never
+/// returns: only deserialized headers map unknown algorithms to it. Algorithms are compared by their `alg` value, so
+/// that a [`JwsAlgorithm::Custom`] naming a listed algorithm still equals it.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub enum JwsAlgorithm {
+  /// HMAC using SHA-256.
+  HS256,
+  /// HMAC using SHA-384.
+  HS384,
+  /// HMAC using SHA-512.
+  HS512,
+  /// RSASSA-PKCS1-v1_5 using SHA-256.
+  RS256,
+  /// RSASSA-PKCS1-v1_5 using SHA-384.
+  RS384,
+  /// RSASSA-PKCS1-v1_5 using SHA-512.
+  RS512,
+  /// RSASSA-PSS using SHA-256 and MGF1 with SHA-256.
+  PS256,
+  /// RSASSA-PSS using SHA-384 and MGF1 with SHA-384.
+  PS384,
+  /// RSASSA-PSS using SHA-512 and MGF1 with SHA-512.
+  PS512,
+  /// ECDSA using P-256 and SHA-256.
+  ES256,
+  /// ECDSA using P-384 and SHA-384.
+  ES384,
+  /// ECDSA using P-521 and SHA-512.
+  ES512,
+  /// ECDSA using secp256k1 and SHA-256.
+  ES256K,
+  /// EdDSA, with the curve given by the key.
+  EdDSA,
+  /// EdDSA using Ed25519.
+  Ed25519,
+  /// EdDSA using Ed448.
+  Ed448,
+  /// Any other algorithm, reserved for the names not listed above. Create it with [`JwsAlgorithm::custom`].
+  Custom(String),
+}
+
+impl JwsAlgorithm {
+  /// Creates the algorithm identified by `alg`: one of the listed variants if it names one, and
+  /// [`JwsAlgorithm::Custom`] otherwise.
+  pub fn custom(alg: impl Into<String>) -> Self {
+    let alg = alg.into();
+    alg.parse().unwrap_or(Self::Custom(alg))
+  }
+
+  /// Returns the value of the `alg` header parameter identifying this algorithm.
+  pub fn as_str(&self) -> &str {
+    match self {
+      Self::HS256 => "HS256",
+      Self::HS384 => "HS384",
+      Self::HS512 => "HS512",
+      Self::RS256 => "RS256",
+      Self::RS384 => "RS384",
+      Self::RS512 => "RS512",
+      Self::PS256 => "PS256",
+      Self::PS384 => "PS384",
+      Self::PS512 => "PS512",
+      Self::ES256 => "ES256",
+      Self::ES384 => "ES384",
+      Self::ES512 => "ES512",
+      Self::ES256K => "ES256K",
+      Self::EdDSA => "EdDSA",
+      Self::Ed25519 => "Ed25519",
+      Self::Ed448 => "Ed448",
+      Self::Custom(alg) => alg,
+    }
+  }
+
+  /// Returns whether this is the `none` algorithm, i.e. the JWS isn't signed.
+  pub fn is_none(&self) -> bool {
+    self.as_str() == "none"
+  }
+}
+
+impl PartialEq for JwsAlgorithm {
+  fn eq(&self, other: &Self) -> bool {
+    self.as_str() == other.as_str()
+  }
+}
+
+impl Eq for JwsAlgorithm {}
+
+impl Hash for JwsAlgorithm {
+  fn hash<S: Hasher>(&self, state: &mut S) {
+    self.as_str().hash(state);
+  }
+}
+
+impl Display for JwsAlgorithm {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl FromStr for JwsAlgorithm {
+  type Err = Error;
+  /// Parses one of the algorithms listed by [`JwsAlgorithm`].
+  ///
+  /// ## Error
+  /// Returns [`Error::UnknownAlgorithm`] for any other value, e.g. a misspelled algorithm.
+  fn from_str(s: &str) -> Result<Self> {
+    Ok(match s {
+      "HS256" => Self::HS256,
+      "HS384" => Self::HS384,
+      "HS512" => Self::HS512,
+      "RS256" => Self::RS256,
+      "RS384" => Self::RS384,
+      "RS512" => Self::RS512,
+      "PS256" => Self::PS256,
+      "PS384" => Self::PS384,
+      "PS512" => Self::PS512,
+      "ES256" => Self::ES256,
+      "ES384" => Self::ES384,
+      "ES512" => Self::ES512,
+      "ES256K" => Self::ES256K,
+      "EdDSA" => Self::EdDSA,
+      "Ed25519" => Self::Ed25519,
+      "Ed448" => Self::Ed448,
+      alg => return Err(Error::UnknownAlgorithm(alg.to_string())),
+    })
+  }
+}
+
+impl Serialize for JwsAlgorithm {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.as_str())
+  }
+}
+
+impl<'de> Deserialize<'de> for JwsAlgorithm {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    String::deserialize(deserializer).map(Self::custom)
+  }
+}
+
+/// The protected header of a JWS, see [RFC 7515](https://www.rfc-editor.org/rfc/rfc7515.html#section-4).
+///
+/// ## Notes
+/// `alg` is only optional so that headers can be prepared before the algorithm is chosen: the builders always set
+/// it, and verification fails without it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwsHeader {
+  /// The algorithm the JWS is signed with.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub alg: Option<JwsAlgorithm>,
+  /// The media type of the JWS.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub typ: Option<String>,
+  /// The identifier of the key the JWS is signed with.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub kid: Option<String>,
+  /// The public key, as a JWK, the JWS is signed with.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub jwk: Option<JsonObject>,
+  /// The base64-encoded X.509 certificate chain of the key the JWS is signed with, leaf first.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub x5c: Option<Vec<String>>,
+  /// The [OpenID Federation](https://openid.net/specs/openid-federation-1_0.html#name-trust_chain-header-parameter)
+  /// trust chain of the signer, as entity statements.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub trust_chain: Option<Vec<String>>,
+  /// The extensions, among [`JwsHeader::extensions`], that must be understood to process the JWS.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub crit: Option<Vec<String>>,
+  /// The other header parameters.
+  #[serde(flatten)]
+  pub extensions: JsonObject,
+}
+
+impl JwsHeader {
+  /// Creates a new, empty [`JwsHeader`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns this header as a JSON object, e.g. to be signed.
+  pub fn to_json_object(&self) -> JsonObject {
+    match serde_json::to_value(self).expect("header is serializable") {
+      Value::Object(header) => header,
+      _ => unreachable!("header is an object"),
+    }
+  }
+
+  /// Checks that the extensions listed in `crit` are present in this header and understood, according to
+  /// `understands`.
+  ///
+  /// ## Error
+  /// Returns [`Error::JwsVerificationFailure`] if `crit` is empty, lists a registered header parameter, or lists an
+  /// extension that is missing or not understood.
+  pub(crate) fn check_critical_extensions(&self, understands: impl Fn(&str) -> bool) -> Result<()> {
+    let Some(crit) = &self.crit else {
+      return Ok(());
+    };
+    if crit.is_empty() {
+      return Err(Error::JwsVerificationFailure("\"crit\" must not be empty".to_string()));
+    }
+    for name in crit {
+      if REGISTERED_HEADER_PARAMETERS.contains(&name.as_str()) || !self.to_json_object().contains_key(name) {
+        return Err(Error::JwsVerificationFailure(format!(
+          "invalid critical header parameter \"{name}\""
+        )));
+      }
+      if !understands(name) {
+        return Err(Error::JwsVerificationFailure(format!(
+          "unsupported critical header parameter \"{name}\""
+        )));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl TryFrom<JsonObject> for JwsHeader {
+  type Error = Error;
+  fn try_from(header: JsonObject) -> Result<Self> {
+    serde_json::from_value(Value::Object(header))
+      .map_err(|e| Error::DeserializationError(format!("invalid JWS header: {e}")))
+  }
+}
+
+impl From<JwsHeader> for JsonObject {
+  fn from(header: JwsHeader) -> Self {
+    header.to_json_object()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn algorithms_round_trip() {
+    assert_eq!("ES256".parse::<JwsAlgorithm>().unwrap(), JwsAlgorithm::ES256);
+    assert_eq!(
+      "ES265".parse::<JwsAlgorithm>().unwrap_err(),
+      Error::UnknownAlgorithm("ES265".to_string())
+    );
+    assert_eq!(
+      serde_json::from_value::<JwsAlgorithm>(json!("ES265")).unwrap(),
+      JwsAlgorithm::Custom("ES265".to_string())
+    );
+    assert!(matches!(JwsAlgorithm::custom("ES256"), JwsAlgorithm::ES256));
+    assert_eq!(JwsAlgorithm::Custom("ES256".to_string()), JwsAlgorithm::ES256);
+    assert_eq!(serde_json::to_value(JwsAlgorithm::EdDSA).unwrap(), json!("EdDSA"));
+  }
+
+  #[test]
+  fn headers_keep_their_extensions() {
+    let json = json!({ "alg": "ES256", "kid": "key-1", "crit": ["exp"], "exp": 1683000000 });
+    let header = JwsHeader::try_from(json.as_object().unwrap().clone()).unwrap();
+
+    assert_eq!(header.alg, Some(JwsAlgorithm::ES256));
+    assert_eq!(header.extensions.get("exp"), Some(&json!(1683000000)));
+    assert_eq!(Value::Object(header.to_json_object()), json);
+  }
+
+  #[test]
+  fn critical_extensions_must_be_understood() {
+    let header = JwsHeader {
+      crit: Some(vec!["exp".to_string()]),
+      extensions: json!({ "exp": 1683000000 }).as_object().unwrap().clone(),
+      ..Default::default()
+    };
+
+    assert!(header.check_critical_extensions(|name| name == "exp").is_ok());
+    assert!(header.check_critical_extensions(|_| false).is_err());
+    let header = JwsHeader {
+      crit: Some(vec!["kid".to_string()]),
+      kid: Some("key-1".to_string()),
+      ..Default::default()
+    };
+    assert!(header.check_critical_extensions(|_| true).is_err());
+  }
+}
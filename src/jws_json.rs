@@ -0,0 +1,95 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use multibase::Base;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::JwsHeader;
+use crate::Result;
+
+/// The [JWS JSON Serialization](https://www.rfc-editor.org/rfc/rfc7515.html#section-7.2) forms an [`crate::SdJwt`]
+/// can be serialized to, besides the compact `~`-separated one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwsJsonSerialization {
+  /// Flattened JWS JSON Serialization, supporting a single signature.
+  Flattened,
+  /// General JWS JSON Serialization, supporting multiple signatures.
+  General,
+}
+
+/// A signature of an SD-JWT in the general JWS JSON Serialization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JwsSignature {
+  /// The base64url-encoded protected header.
+  pub protected: String,
+  /// The unprotected header.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub header: Option<JsonObject>,
+  /// The base64url-encoded signature.
+  pub signature: String,
+}
+
+impl JwsSignature {
+  /// Decodes the protected header.
+  pub fn protected_header(&self) -> Result<JwsHeader> {
+    Base::Base64Url
+      .decode(&self.protected)
+      .map_err(|e| e.to_string())
+      .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+      .map_err(|e| Error::DeserializationError(format!("invalid protected header: {e}")))
+  }
+
+  /// Splits a compact JWS into its payload and its signature.
+  pub(crate) fn from_compact(jws: &str) -> Result<(String, Self)> {
+    let mut segments = jws.split('.');
+    let (Some(protected), Some(payload), Some(signature), None) =
+      (segments.next(), segments.next(), segments.next(), segments.next())
+    else {
+      return Err(Error::DeserializationError(
+        "invalid JWS: expected 3 segments".to_string(),
+      ));
+    };
+
+    Ok((
+      payload.to_string(),
+      Self {
+        protected: protected.to_string(),
+        header: None,
+        signature: signature.to_string(),
+      },
+    ))
+  }
+
+  pub(crate) fn to_compact(&self, payload: &str) -> String {
+    format!("{}.{payload}.{}", self.protected, self.signature)
+  }
+}
+
+/// The unprotected header carrying an SD-JWT's disclosures and KB-JWT.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SdJwtUnprotectedHeader {
+  #[serde(default)]
+  pub disclosures: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub kb_jwt: Option<String>,
+  #[serde(flatten)]
+  pub properties: JsonObject,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct FlattenedJws {
+  #[serde(default)]
+  pub header: SdJwtUnprotectedHeader,
+  pub payload: String,
+  pub protected: String,
+  pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GeneralJws {
+  pub payload: String,
+  pub signatures: Vec<JwsSignature>,
+}
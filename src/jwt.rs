@@ -11,10 +11,13 @@ use serde::Serialize;
 
 use crate::Error;
 use crate::JsonObject;
+use crate::JwsHeader;
+use crate::JwsVerifier;
+use crate::JwsVerifierSync;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Jwt<T> {
-  pub header: JsonObject,
+  pub header: JwsHeader,
   pub claims: T,
   pub jws: String,
 }
@@ -39,7 +42,7 @@ where
       .next()
       .context("missing header segment")
       .and_then(|b64| Base::Base64Url.decode(b64).context("not Base64Url-encoded"))
-      .and_then(|json_bytes| serde_json::from_slice::<JsonObject>(&json_bytes).context("invalid JWT header properties"))
+      .and_then(|json_bytes| serde_json::from_slice::<JwsHeader>(&json_bytes).context("invalid JWT header properties"))
       .map_err(|e| Error::DeserializationError(format!("invalid JWT: {e}")))?;
     let claims = segments
       .next()
@@ -69,7 +72,6 @@ where
 }
 
 impl<T> Jwt<T> {
-  #[allow(dead_code)]
   pub fn signature(&self) -> &str {
     self
       .jws
@@ -78,6 +80,63 @@ impl<T> Jwt<T> {
       // Safety: jws is a valid JWS.
       .unwrap()
   }
+
+  /// Returns the JWS signing input, i.e. `<base64url header>.<base64url payload>`.
+  pub fn signing_input(&self) -> &str {
+    self
+      .jws
+      .rsplit_once('.')
+      .map(|(signing_input, _)| signing_input)
+      // Safety: jws is a valid JWS.
+      .unwrap()
+  }
+
+  /// Verifies this JWT's signature using `verifier`.
+  ///
+  /// ## Error
+  /// Returns [`Error::JwsVerificationFailure`] if `alg` is missing or set to "none", if `crit` lists extensions
+  /// `verifier` doesn't understand, or if `verifier` rejects the signature.
+  pub(crate) async fn verify<V>(&self, verifier: &V, key: Option<&JsonObject>) -> Result<(), Error>
+  where
+    V: JwsVerifier,
+  {
+    let signature = self.check_header(|name| verifier.understands_critical_extension(name))?;
+
+    verifier
+      .verify(&self.header, self.signing_input().as_bytes(), &signature, key)
+      .await
+      .map_err(|e| Error::JwsVerificationFailure(e.to_string()))
+  }
+
+  /// Verifies this JWT's signature using the blocking `verifier`.
+  ///
+  /// ## Error
+  /// See [`Jwt::verify`].
+  pub(crate) fn verify_sync<V>(&self, verifier: &V, key: Option<&JsonObject>) -> Result<(), Error>
+  where
+    V: JwsVerifierSync,
+  {
+    let signature = self.check_header(|name| JwsVerifierSync::understands_critical_extension(verifier, name))?;
+
+    verifier
+      .verify_sync(&self.header, self.signing_input().as_bytes(), &signature, key)
+      .map_err(|e| Error::JwsVerificationFailure(e.to_string()))
+  }
+
+  /// Checks `alg` and `crit` before verification, returning the decoded signature.
+  fn check_header(&self, understands: impl Fn(&str) -> bool) -> Result<Vec<u8>, Error> {
+    let valid_alg = self.header.alg.as_ref().is_some_and(|alg| !alg.is_none());
+    if !valid_alg {
+      return Err(Error::JwsVerificationFailure(
+        "alg must be set and cannot be \"none\"".to_string(),
+      ));
+    }
+    self.header.check_critical_extensions(understands)?;
+
+    Base::Base64Url
+      .decode(self.signature())
+      .map_err(|e| Error::JwsVerificationFailure(format!("invalid signature encoding: {e}")))
+  }
 }
 
 #[cfg(test)]
@@ -100,4 +159,10 @@ mod tests {
     let jwt = JWT.parse::<Jwt<TestClaims>>().unwrap();
     assert_eq!(&jwt.to_string(), JWT);
   }
+
+  #[test]
+  fn signing_input() {
+    let jwt = JWT.parse::<Jwt<TestClaims>>().unwrap();
+    assert_eq!(format!("{}.{}", jwt.signing_input(), jwt.signature()), JWT);
+  }
 }
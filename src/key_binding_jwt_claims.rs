@@ -1,14 +1,23 @@
 // Copyright 2020-2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::builder::sign;
+use crate::builder::sign_sync;
 use crate::jwt::Jwt;
+use crate::sd_jwt::check_hasher;
+use crate::Clock;
 use crate::Error;
 use crate::Hasher;
 use crate::JsonObject;
+use crate::JwsAlgorithm;
+use crate::JwsHeader;
 use crate::JwsSigner;
+use crate::JwsSignerSync;
+use crate::JwsVerifier;
+use crate::JwsVerifierSync;
 use crate::SdJwt;
+use crate::SystemClock;
 use crate::SHA_ALG_NAME;
-use anyhow::Context as _;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -16,6 +25,8 @@ use std::borrow::Cow;
 use std::fmt::Display;
 use std::ops::Deref;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub const KB_JWT_HEADER_TYP: &str = "kb+jwt";
 
@@ -33,13 +44,13 @@ impl FromStr for KeyBindingJwt {
   type Err = Error;
   fn from_str(s: &str) -> Result<Self, Self::Err> {
     let jwt = Jwt::<KeyBindingJwtClaims>::from_str(s)?;
-    let valid_jwt_type = jwt.header.get("typ").is_some_and(|typ| typ == KB_JWT_HEADER_TYP);
+    let valid_jwt_type = jwt.header.typ.as_deref() == Some(KB_JWT_HEADER_TYP);
     if !valid_jwt_type {
       return Err(Error::DeserializationError(format!(
         "invalid KB-JWT: typ must be \"{KB_JWT_HEADER_TYP}\""
       )));
     }
-    let valid_alg = jwt.header.get("alg").is_some_and(|alg| alg != "none");
+    let valid_alg = jwt.header.alg.as_ref().is_some_and(|alg| !alg.is_none());
     if !valid_alg {
       return Err(Error::DeserializationError(
         "invalid KB-JWT: alg must be set and cannot be \"none\"".to_string(),
@@ -59,12 +70,168 @@ impl KeyBindingJwt {
   pub fn claims(&self) -> &KeyBindingJwtClaims {
     &self.0.claims
   }
+
+  /// Verifies this [`KeyBindingJwt`] as the KB-JWT of `sd_jwt`.
+  ///
+  /// The signature is checked with `verifier` using `holder_key`, after which `sd_hash` is recomputed over
+  /// `sd_jwt`'s presentation - omitting any attached KB-JWT - and the `nonce`, `aud` and `iat` claims are checked
+  /// against `validation`.
+  /// ## Errors
+  /// - [`Error::InvalidHasher`] is returned if `hasher` doesn't match `sd_jwt`'s `_sd_alg` claim.
+  /// - [`Error::JwsVerificationFailure`] is returned if the signature is invalid.
+  /// - [`Error::JwtNotYetValid`] is returned if `iat` is in the future.
+  /// - [`Error::InvalidKeyBindingJwt`] is returned if any other claim doesn't match its expected value.
+  pub async fn verify<V>(
+    &self,
+    sd_jwt: &SdJwt,
+    hasher: &dyn Hasher,
+    verifier: &V,
+    holder_key: Option<&JsonObject>,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<(), Error>
+  where
+    V: JwsVerifier,
+  {
+    check_hasher(sd_jwt.claims(), hasher)?;
+    self.0.verify(verifier, holder_key).await?;
+    self.check_claims(sd_jwt, hasher, validation)
+  }
+
+  /// Verifies this [`KeyBindingJwt`] like [`KeyBindingJwt::verify`] does, with a blocking `verifier`.
+  /// ## Errors
+  /// See [`KeyBindingJwt::verify`].
+  pub fn verify_sync<V>(
+    &self,
+    sd_jwt: &SdJwt,
+    hasher: &dyn Hasher,
+    verifier: &V,
+    holder_key: Option<&JsonObject>,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<(), Error>
+  where
+    V: JwsVerifierSync,
+  {
+    check_hasher(sd_jwt.claims(), hasher)?;
+    self.0.verify_sync(verifier, holder_key)?;
+    self.check_claims(sd_jwt, hasher, validation)
+  }
+
+  /// Checks the claims of this [`KeyBindingJwt`] against `sd_jwt` and `validation`.
+  fn check_claims(
+    &self,
+    sd_jwt: &SdJwt,
+    hasher: &dyn Hasher,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<(), Error> {
+    let claims = self.claims();
+    let sd_hash = hasher.encoded_digest(&sd_jwt.presentation_without_key_binding());
+    if claims.sd_hash != sd_hash {
+      return Err(Error::InvalidKeyBindingJwt(
+        "`sd_hash` doesn't match the presented SD-JWT".to_string(),
+      ));
+    }
+    if claims.nonce != validation.nonce {
+      return Err(Error::InvalidKeyBindingJwt(format!(
+        "expected `nonce` \"{}\", found \"{}\"",
+        validation.nonce, claims.nonce
+      )));
+    }
+    if claims.aud != validation.aud {
+      return Err(Error::InvalidKeyBindingJwt(format!(
+        "expected `aud` \"{}\", found \"{}\"",
+        validation.aud, claims.aud
+      )));
+    }
+
+    validation.check_iat(claims.iat)
+  }
+}
+
+/// Expected values a [`KeyBindingJwt`] is checked against during verification.
+#[derive(Debug, Clone)]
+pub struct KeyBindingJwtValidation {
+  aud: String,
+  nonce: String,
+  max_age: Duration,
+  leeway: Duration,
+  clock: Arc<dyn Clock>,
+}
+
+impl KeyBindingJwtValidation {
+  /// How far in the past a KB-JWT's `iat` may be, if not specified otherwise.
+  pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(300);
+
+  /// Creates a new [`KeyBindingJwtValidation`] expecting the given `aud` and `nonce`.
+  pub fn new<'a, A, N>(aud: A, nonce: N) -> Self
+  where
+    A: Into<Cow<'a, str>>,
+    N: Into<Cow<'a, str>>,
+  {
+    Self {
+      aud: aud.into().into_owned(),
+      nonce: nonce.into().into_owned(),
+      max_age: Self::DEFAULT_MAX_AGE,
+      leeway: Duration::ZERO,
+      clock: Arc::new(SystemClock),
+    }
+  }
+
+  /// Sets how far in the past the KB-JWT's `iat` may be.
+  pub fn max_age(mut self, max_age: Duration) -> Self {
+    self.max_age = max_age;
+    self
+  }
+
+  /// Sets the tolerated clock skew between holder and verifier, applied to both ends of the `iat` window.
+  pub fn leeway(mut self, leeway: Duration) -> Self {
+    self.leeway = leeway;
+    self
+  }
+
+  /// Sets the [`Clock`] the `iat` window is computed from. Defaults to [`SystemClock`].
+  pub fn clock<C>(mut self, clock: C) -> Self
+  where
+    C: Clock + 'static,
+  {
+    self.clock = Arc::new(clock);
+    self
+  }
+
+  /// Returns the expected `aud`.
+  pub fn aud(&self) -> &str {
+    &self.aud
+  }
+
+  /// Returns the expected `nonce`.
+  pub fn nonce(&self) -> &str {
+    &self.nonce
+  }
+
+  /// Checks that `iat` is neither in the future nor older than the maximum age, give or take the leeway.
+  /// ## Errors
+  /// - [`Error::JwtNotYetValid`] is returned if `iat` is in the future.
+  /// - [`Error::InvalidKeyBindingJwt`] is returned if `iat` is too old.
+  pub(crate) fn check_iat(&self, iat: i64) -> Result<(), Error> {
+    let now = self.clock.now();
+    let leeway = self.leeway.as_secs() as i64;
+    let max_age = self.max_age.as_secs() as i64;
+    if iat > now + leeway {
+      return Err(Error::JwtNotYetValid(iat));
+    }
+    if iat < now - max_age - leeway {
+      return Err(Error::InvalidKeyBindingJwt(format!(
+        "`iat` is older than {max_age} seconds"
+      )));
+    }
+
+    Ok(())
+  }
 }
 
 /// Builder-style struct to ease the creation of an [`KeyBindingJwt`].
 #[derive(Debug, Default, Clone)]
 pub struct KeyBindingJwtBuilder {
-  header: JsonObject,
+  header: JwsHeader,
   payload: JsonObject,
 }
 
@@ -77,13 +244,13 @@ impl KeyBindingJwtBuilder {
   /// Creates a new [`KeyBindingJwtBuilder`] using `object` as its payload.
   pub fn from_object(object: JsonObject) -> Self {
     Self {
-      header: JsonObject::default(),
+      header: JwsHeader::default(),
       payload: object,
     }
   }
 
   /// Sets the JWT's header.
-  pub fn header(mut self, header: JsonObject) -> Self {
+  pub fn header(mut self, header: JwsHeader) -> Self {
     self.header = header;
     self
   }
@@ -125,14 +292,52 @@ impl KeyBindingJwtBuilder {
     self,
     sd_jwt: &SdJwt,
     hasher: &dyn Hasher,
-    alg: &str,
+    alg: JwsAlgorithm,
     signer: &S,
   ) -> Result<KeyBindingJwt, Error>
   where
     S: JwsSigner,
   {
+    let (header, claims, parsed_claims) = self.into_parts(sd_jwt, hasher, alg)?;
+    let jws = sign(signer, &header, &claims).await?;
+
+    Ok(KeyBindingJwt(Jwt {
+      header,
+      claims: parsed_claims,
+      jws,
+    }))
+  }
+
+  /// Builds an [`KeyBindingJwt`] from the data provided to builder, signed by a blocking `signer`.
+  pub fn finish_sync<S>(
+    self,
+    sd_jwt: &SdJwt,
+    hasher: &dyn Hasher,
+    alg: JwsAlgorithm,
+    signer: &S,
+  ) -> Result<KeyBindingJwt, Error>
+  where
+    S: JwsSignerSync,
+  {
+    let (header, claims, parsed_claims) = self.into_parts(sd_jwt, hasher, alg)?;
+    let jws = sign_sync(signer, &header, &claims)?;
+
+    Ok(KeyBindingJwt(Jwt {
+      header,
+      claims: parsed_claims,
+      jws,
+    }))
+  }
+
+  /// Returns the header and the claims to sign, and the parsed claims.
+  fn into_parts(
+    self,
+    sd_jwt: &SdJwt,
+    hasher: &dyn Hasher,
+    alg: JwsAlgorithm,
+  ) -> Result<(JwsHeader, JsonObject, KeyBindingJwtClaims), Error> {
     let mut claims = self.payload;
-    if alg == "none" {
+    if alg.is_none() {
       return Err(Error::DataTypeMismatch(
         "A KeyBindingJwt cannot use algorithm \"none\"".to_string(),
       ));
@@ -152,26 +357,14 @@ impl KeyBindingJwtBuilder {
     claims.insert("sd_hash".to_string(), sd_hash.into());
 
     let mut header = self.header;
-    header.insert("alg".to_string(), alg.to_owned().into());
-    header
-      .entry("typ")
-      .or_insert_with(|| KB_JWT_HEADER_TYP.to_owned().into());
+    header.alg = Some(alg);
+    header.typ.get_or_insert_with(|| KB_JWT_HEADER_TYP.to_owned());
 
     // Validate claims
     let parsed_claims = serde_json::from_value::<KeyBindingJwtClaims>(claims.clone().into())
       .map_err(|e| Error::DeserializationError(format!("invalid KB-JWT claims: {e}")))?;
-    let jws = signer
-      .sign(&header, &claims)
-      .await
-      .map_err(|e| anyhow::anyhow!("{e}"))
-      .and_then(|jws_bytes| String::from_utf8(jws_bytes).context("invalid JWS"))
-      .map_err(|e| Error::JwsSignerFailure(e.to_string()))?;
 
-    Ok(KeyBindingJwt(Jwt {
-      header,
-      claims: parsed_claims,
-      jws,
-    }))
+    Ok((header, claims, parsed_claims))
   }
 }
 
@@ -214,3 +407,26 @@ pub enum RequiredKeyBinding {
   #[serde(untagged)]
   Custom(Value),
 }
+
+impl RequiredKeyBinding {
+  /// Returns the holder's key as a JWK.
+  /// ## Errors
+  /// - [`Error::KeyResolutionFailure`] is returned when the key is only referenced - i.e. [`RequiredKeyBinding::Kid`]
+  ///   and [`RequiredKeyBinding::Jwu`] - or bound through another confirmation method - i.e.
+  ///   [`RequiredKeyBinding::Custom`] - since it must be resolved with a [`KeyResolver`](crate::KeyResolver).
+  /// - [`Error::InvalidKeyBindingJwt`] is returned for encrypted keys.
+  pub fn to_jwk(&self) -> Result<JsonObject, Error> {
+    match self {
+      Self::Jwk(jwk) => Ok(jwk.clone()),
+      Self::Kid(_) | Self::Jwu { .. } => Err(Error::KeyResolutionFailure(
+        "`cnf` only references the holder's key, which must be resolved with a `KeyResolver`".to_string(),
+      )),
+      Self::Jwe(_) => Err(Error::InvalidKeyBindingJwt(
+        "encrypted `cnf` keys are not supported".to_string(),
+      )),
+      Self::Custom(_) => Err(Error::KeyResolutionFailure(
+        "`cnf` uses an unsupported confirmation method, which must be resolved with a `KeyResolver`".to_string(),
+      )),
+    }
+  }
+}
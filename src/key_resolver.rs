@@ -0,0 +1,363 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use multibase::Base;
+use serde_json::Value;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::JwkSet;
+use crate::JwkSetFetcher;
+use crate::JwsHeader;
+use crate::JwsVerifier;
+use crate::RequiredKeyBinding;
+use crate::Result;
+
+/// Finds the public keys SD-JWTs and KB-JWTs must be verified with.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait KeyResolver {
+  /// Returns, as a JWK, the issuer's public key an issuer-signed JWT with the given `header` and `claims` must be
+  /// verified with.
+  async fn resolve_issuer_key(&self, header: &JwsHeader, claims: &JsonObject) -> Result<JsonObject>;
+
+  /// Returns, as a JWK, the holder's public key referenced by the `cnf` claim of an SD-JWT.
+  async fn resolve_holder_key(&self, cnf: &RequiredKeyBinding) -> Result<JsonObject>;
+}
+
+/// A [`KeyResolver`] backed by a local [`JwkSet`], optionally persisted to a file.
+///
+/// Issuer keys are looked up by the `kid` header parameter or, without one, by the `jwk` header parameter, which
+/// must be one of the set's keys. Holder keys referenced through [`RequiredKeyBinding::Kid`] are looked up in the same
+/// set, while [`RequiredKeyBinding::Jwu`] references are retrieved with a [`JwkSetFetcher`].
+#[derive(Debug)]
+pub struct JwkSetKeyResolver<F = HashMap<String, JwkSet>> {
+  keys: RwLock<JwkSet>,
+  path: Option<PathBuf>,
+  issuer: Option<String>,
+  fetcher: F,
+}
+
+impl JwkSetKeyResolver {
+  /// Creates a new in-memory [`JwkSetKeyResolver`] using `keys`.
+  pub fn new(keys: JwkSet) -> Self {
+    Self {
+      keys: RwLock::new(keys),
+      path: None,
+      issuer: None,
+      fetcher: HashMap::default(),
+    }
+  }
+
+  /// Creates a new [`JwkSetKeyResolver`] backed by the JWK Set stored at `path`.
+  /// Key rotations are written back to the file.
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+    let path = path.as_ref().to_owned();
+    Ok(Self {
+      keys: RwLock::new(JwkSet::from_file(&path)?),
+      path: Some(path),
+      issuer: None,
+      fetcher: HashMap::default(),
+    })
+  }
+}
+
+impl<F> JwkSetKeyResolver<F> {
+  /// Sets the [`JwkSetFetcher`] used to retrieve the JWK Sets referenced by [`RequiredKeyBinding::Jwu`].
+  pub fn with_fetcher<F2>(self, fetcher: F2) -> JwkSetKeyResolver<F2> {
+    JwkSetKeyResolver {
+      keys: self.keys,
+      path: self.path,
+      issuer: self.issuer,
+      fetcher,
+    }
+  }
+
+  /// Only resolves the issuer keys of JWTs whose `iss` claim is `issuer`, i.e. the keys of the set belong to
+  /// `issuer`.
+  pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+    self.issuer = Some(issuer.into());
+    self
+  }
+
+  /// Returns a copy of the keys currently in use.
+  pub fn keys(&self) -> JwkSet {
+    self.keys.read().expect("lock is not poisoned").clone()
+  }
+
+  /// Adds `jwk`, replacing the key with the same `kid` - if any - which is returned.
+  pub fn rotate(&self, jwk: JsonObject) -> Result<Option<JsonObject>> {
+    self.update(|keys| keys.insert(jwk))
+  }
+
+  /// Removes the key identified by `kid`, returning it.
+  pub fn remove(&self, kid: &str) -> Result<Option<JsonObject>> {
+    self.update(|keys| Ok(keys.remove(kid)))
+  }
+
+  /// Reloads the keys from the backing file, if any.
+  pub fn reload(&self) -> Result<()> {
+    if let Some(path) = self.path.as_deref() {
+      *self.keys.write().expect("lock is not poisoned") = JwkSet::from_file(path)?;
+    }
+    Ok(())
+  }
+
+  fn update<T>(&self, f: impl FnOnce(&mut JwkSet) -> Result<T>) -> Result<T> {
+    let mut keys = self.keys.write().expect("lock is not poisoned");
+    let output = f(&mut keys)?;
+    if let Some(path) = self.path.as_deref() {
+      keys.write_to_file(path)?;
+    }
+    Ok(output)
+  }
+
+  fn find(&self, kid: &str) -> Result<JsonObject> {
+    self
+      .keys
+      .read()
+      .expect("lock is not poisoned")
+      .get(kid)
+      .cloned()
+      .ok_or_else(|| Error::KeyResolutionFailure(format!("unknown key \"{kid}\"")))
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F> KeyResolver for JwkSetKeyResolver<F>
+where
+  F: JwkSetFetcher + Send + Sync,
+{
+  async fn resolve_issuer_key(&self, header: &JwsHeader, claims: &JsonObject) -> Result<JsonObject> {
+    if let Some(issuer) = &self.issuer {
+      if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        return Err(Error::KeyResolutionFailure(format!(
+          "the JWT isn't issued by \"{issuer}\""
+        )));
+      }
+    }
+    let jwk = header.jwk.as_ref();
+    let key = match (header.kid.as_deref(), jwk) {
+      (Some(kid), _) => self.find(kid)?,
+      (None, Some(jwk)) => self
+        .keys
+        .read()
+        .expect("lock is not poisoned")
+        .keys()
+        .iter()
+        .find(|key| same_public_key(key, jwk))
+        .cloned()
+        .ok_or_else(|| Error::KeyResolutionFailure("the JWT header's \"jwk\" is not a known key".to_string()))?,
+      // Without a `kid` or a `jwk`, a set made of a single key is unambiguous.
+      (None, None) => match self.keys.read().expect("lock is not poisoned").keys() {
+        [key] => key.clone(),
+        _ => {
+          return Err(Error::KeyResolutionFailure(
+            "the JWT header has no \"kid\" to select the issuer's key with".to_string(),
+          ))
+        }
+      },
+    };
+    if jwk.is_some_and(|jwk| !same_public_key(&key, jwk)) {
+      return Err(Error::KeyResolutionFailure(
+        "the JWT header's \"jwk\" doesn't match the key identified by \"kid\"".to_string(),
+      ));
+    }
+
+    Ok(key)
+  }
+
+  async fn resolve_holder_key(&self, cnf: &RequiredKeyBinding) -> Result<JsonObject> {
+    match cnf {
+      RequiredKeyBinding::Jwk(jwk) => Ok(jwk.clone()),
+      RequiredKeyBinding::Kid(kid) => self.find(kid),
+      RequiredKeyBinding::Jwu { jwu, kid } => self
+        .fetcher
+        .fetch(jwu)
+        .await
+        .map_err(|e| Error::KeyResolutionFailure(format!("cannot fetch JWK Set \"{jwu}\": {e}")))?
+        .get(kid)
+        .cloned()
+        .ok_or_else(|| Error::KeyResolutionFailure(format!("JWK Set \"{jwu}\" has no key \"{kid}\""))),
+      _ => Err(Error::KeyResolutionFailure("unsupported `cnf` key".to_string())),
+    }
+  }
+}
+
+/// A [`JwsVerifier`] that consults a [`KeyResolver`] for the key to verify a signature with, before delegating the
+/// actual verification to another [`JwsVerifier`].
+///
+/// It can be used wherever a [`JwsVerifier`] is expected, e.g. in [`crate::SdJwt::verify`]: when no key is provided,
+/// the issuer's key is resolved from the JWT's header and claims, while provided keys are used as they are.
+///
+/// ## Notes
+/// Holder keys only referenced by `cnf` aren't given to verifiers, set the [`KeyResolver`] with
+/// [`crate::VerificationOptions::key_resolver`] to verify KB-JWTs against them.
+#[derive(Debug, Clone)]
+pub struct KeyResolvingVerifier<R, V> {
+  resolver: R,
+  verifier: V,
+}
+
+impl<R, V> KeyResolvingVerifier<R, V> {
+  /// Creates a new [`KeyResolvingVerifier`] resolving keys with `resolver` and verifying signatures with `verifier`.
+  pub fn new(resolver: R, verifier: V) -> Self {
+    Self { resolver, verifier }
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<R, V> JwsVerifier for KeyResolvingVerifier<R, V>
+where
+  R: KeyResolver + Sync,
+  V: JwsVerifier + Sync,
+{
+  type Error = Error;
+  async fn verify(
+    &self,
+    header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<()> {
+    let key = match key {
+      None => {
+        let claims = decode_claims(signing_input)?;
+        self.resolver.resolve_issuer_key(header, &claims).await?
+      }
+      Some(jwk) => jwk.clone(),
+    };
+
+    self
+      .verifier
+      .verify(header, signing_input, signature, Some(&key))
+      .await
+      .map_err(|e| Error::JwsVerificationFailure(e.to_string()))
+  }
+
+  fn understands_critical_extension(&self, name: &str) -> bool {
+    self.verifier.understands_critical_extension(name)
+  }
+}
+
+fn decode_claims(signing_input: &[u8]) -> Result<JsonObject> {
+  std::str::from_utf8(signing_input)
+    .ok()
+    .and_then(|signing_input| signing_input.split_once('.'))
+    .and_then(|(_, payload)| Base::Base64Url.decode(payload).ok())
+    .and_then(|json| serde_json::from_slice(&json).ok())
+    .ok_or_else(|| Error::DeserializationError("invalid JWS signing input".to_string()))
+}
+
+/// Whether the JWKs `a` and `b` represent the same public key, comparing the members that define it - see
+/// [RFC 7638](https://www.rfc-editor.org/rfc/rfc7638.html#section-3.2).
+fn same_public_key(a: &JsonObject, b: &JsonObject) -> bool {
+  a.contains_key("kty")
+    && ["kty", "crv", "x", "y", "n", "e", "k"]
+      .iter()
+      .all(|member| a.get(*member) == b.get(*member))
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::HashMap;
+
+  use serde_json::json;
+
+  use super::*;
+
+  fn jwk(kid: &str) -> JsonObject {
+    json!({ "kty": "OKP", "crv": "Ed25519", "kid": kid, "x": "AAAA" })
+      .as_object()
+      .unwrap()
+      .clone()
+  }
+
+  #[tokio::test]
+  async fn issuer_keys_are_resolved_by_kid() {
+    let resolver = JwkSetKeyResolver::new(JwkSet::from_iter([jwk("key-1"), jwk("key-2")]));
+    let header = JwsHeader {
+      kid: Some("key-2".to_string()),
+      ..Default::default()
+    };
+
+    let key = resolver.resolve_issuer_key(&header, &JsonObject::new()).await.unwrap();
+    assert_eq!(key, jwk("key-2"));
+
+    resolver.remove("key-2").unwrap();
+    assert!(resolver.resolve_issuer_key(&header, &JsonObject::new()).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn issuer_keys_are_checked_against_jwk_and_iss() {
+    let mut other_key = jwk("key-2");
+    other_key.insert("x".to_string(), json!("BBBB"));
+    let resolver =
+      JwkSetKeyResolver::new(JwkSet::from_iter([jwk("key-1"), other_key.clone()])).issuer("https://issuer.example.com");
+    let claims = json!({ "iss": "https://issuer.example.com" })
+      .as_object()
+      .unwrap()
+      .clone();
+
+    // Without `kid`, the key is selected by `jwk`.
+    let header = JwsHeader {
+      jwk: Some(other_key.clone()),
+      ..Default::default()
+    };
+    assert_eq!(resolver.resolve_issuer_key(&header, &claims).await.unwrap(), other_key);
+    // `jwk` must match the key identified by `kid`, and be part of the set.
+    let header = JwsHeader {
+      kid: Some("key-1".to_string()),
+      jwk: Some(other_key.clone()),
+      ..Default::default()
+    };
+    assert!(resolver.resolve_issuer_key(&header, &claims).await.is_err());
+    let mut unknown_key = jwk("key-1");
+    unknown_key.insert("x".to_string(), json!("CCCC"));
+    let header = JwsHeader {
+      jwk: Some(unknown_key),
+      ..Default::default()
+    };
+    assert!(resolver.resolve_issuer_key(&header, &claims).await.is_err());
+
+    // Keys are only resolved for their issuer.
+    let header = JwsHeader {
+      kid: Some("key-1".to_string()),
+      ..Default::default()
+    };
+    assert!(resolver.resolve_issuer_key(&header, &claims).await.is_ok());
+    let claims = json!({ "iss": "https://other.example.com" })
+      .as_object()
+      .unwrap()
+      .clone();
+    assert!(resolver.resolve_issuer_key(&header, &claims).await.is_err());
+  }
+
+  #[tokio::test]
+  async fn holder_keys_are_fetched_from_jwu() {
+    let fetcher = HashMap::from([(
+      "https://holder.example.com/jwks.json".to_string(),
+      JwkSet::from_iter([jwk("holder-key")]),
+    )]);
+    let resolver = JwkSetKeyResolver::new(JwkSet::new()).with_fetcher(fetcher);
+    let cnf = RequiredKeyBinding::Jwu {
+      jwu: "https://holder.example.com/jwks.json".to_string(),
+      kid: "holder-key".to_string(),
+    };
+
+    assert_eq!(resolver.resolve_holder_key(&cnf).await.unwrap(), jwk("holder-key"));
+    let unknown = RequiredKeyBinding::Jwu {
+      jwu: "https://unknown.example.com/jwks.json".to_string(),
+      kid: "holder-key".to_string(),
+    };
+    assert!(resolver.resolve_holder_key(&unknown).await.is_err());
+  }
+}
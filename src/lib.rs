@@ -2,25 +2,67 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod builder;
+mod clock;
+#[cfg(feature = "cwt")]
+pub mod cwt;
 mod decoder;
+mod disclosable;
 mod disclosure;
+#[cfg(feature = "ecdh-es")]
+mod ecdh_es;
 mod encoder;
 mod error;
 mod hasher;
+mod jwe;
+mod jwk_set;
+mod jws_header;
+mod jws_json;
 mod jwt;
 mod key_binding_jwt_claims;
+mod key_resolver;
+mod policy;
+mod report;
+mod salt;
 mod sd_jwt;
+mod sd_jwt_vc;
 mod signer;
+#[cfg(feature = "status-list")]
+mod status_list;
+mod type_metadata;
+mod verifier;
+#[cfg(feature = "x509")]
+mod x509;
 
 pub use builder::*;
+pub use clock::*;
 pub(crate) use decoder::*;
+pub use disclosable::*;
 pub use disclosure::*;
+#[cfg(feature = "ecdh-es")]
+pub use ecdh_es::*;
 pub(crate) use encoder::*;
 pub use error::*;
 pub use hasher::*;
+pub use jwe::*;
+pub use jwk_set::*;
+pub use jws_header::*;
+pub use jws_json::*;
 pub use key_binding_jwt_claims::*;
+pub use key_resolver::*;
+pub use policy::*;
+pub use report::*;
+pub use salt::*;
 pub use sd_jwt::*;
+#[cfg(feature = "derive")]
+pub use sd_jwt_payload_derive::SelectivelyDisclosable;
+pub use sd_jwt_vc::*;
 pub use serde_json::json;
 pub use serde_json::Map;
 pub use serde_json::Value;
 pub use signer::*;
+#[cfg(feature = "status-list")]
+pub use status_list::*;
+pub use type_metadata::*;
+pub use verifier::*;
+#[cfg(feature = "x509")]
+pub use x509::*;
@@ -0,0 +1,241 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::str::FromStr;
+
+use json_pointer::JsonPointer;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+use crate::JsonObject;
+use crate::JwsAlgorithm;
+use crate::Result;
+use crate::SdJwt;
+use crate::SHA_ALG_NAME;
+
+/// Declarative rules an [`SdJwt`] presentation must satisfy to be accepted by a verifier.
+///
+/// Empty lists don't restrict the corresponding property.
+///
+/// ## Example
+/// ```rust
+/// use sd_jwt_payload::VerificationPolicy;
+///
+/// let policy: VerificationPolicy = r#"{
+///   "allowed_algorithms": ["ES256"],
+///   "require_key_binding": true,
+///   "required_claims": ["/given_name", "/address/country"],
+///   "max_disclosures": 10
+/// }"#
+///   .parse()
+///   .unwrap();
+/// assert!(policy.require_key_binding);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationPolicy {
+  /// Accepted values for the issuer-signed JWT's `alg` header parameter.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub allowed_algorithms: Vec<JwsAlgorithm>,
+  /// Accepted values for the `_sd_alg` claim. "sha-256" is assumed when the claim is missing.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub allowed_hash_algorithms: Vec<String>,
+  /// Whether the issuer must have required a key binding through `cnf` and a KB-JWT must be attached.
+  pub require_key_binding: bool,
+  /// Accepted values for the `iss` claim.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub allowed_issuers: Vec<String>,
+  /// [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) to the claims that must be present in the
+  /// disclosed object.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub required_claims: Vec<String>,
+  /// Maximum number of disclosures a presentation may contain.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_disclosures: Option<usize>,
+}
+
+impl VerificationPolicy {
+  /// Checks `sd_jwt` and its disclosed object - as returned by [`SdJwt::into_disclosed_object`] - against this
+  /// policy.
+  /// ## Error
+  /// Returns [`Error::PolicyViolation`] on the first rule that isn't satisfied.
+  pub fn check(&self, sd_jwt: &SdJwt, disclosed: &JsonObject) -> Result<()> {
+    self.check_sd_jwt(sd_jwt)?;
+    self.check_disclosed_object(disclosed)
+  }
+
+  /// Checks the rules that don't depend on the disclosed claims.
+  pub(crate) fn check_sd_jwt(&self, sd_jwt: &SdJwt) -> Result<()> {
+    self.sd_jwt_violations(sd_jwt).into_iter().next().map_or(Ok(()), Err)
+  }
+
+  /// Checks the rules concerning the disclosed claims.
+  pub(crate) fn check_disclosed_object(&self, disclosed: &JsonObject) -> Result<()> {
+    self
+      .disclosed_object_violations(disclosed)
+      .into_iter()
+      .next()
+      .map_or(Ok(()), |(_, error)| Err(error))
+  }
+
+  /// Returns a violation for every rule that doesn't depend on the disclosed claims and isn't satisfied.
+  pub(crate) fn sd_jwt_violations(&self, sd_jwt: &SdJwt) -> Vec<Error> {
+    let mut violations = vec![];
+
+    if !self.allowed_algorithms.is_empty() {
+      let alg = sd_jwt
+        .header()
+        .alg
+        .as_ref()
+        .map(JwsAlgorithm::as_str)
+        .unwrap_or_default();
+      if !self.allowed_algorithms.iter().any(|allowed| allowed.as_str() == alg) {
+        violations.push(Error::PolicyViolation(format!("algorithm \"{alg}\" is not allowed")));
+      }
+    }
+
+    if !self.allowed_hash_algorithms.is_empty() {
+      let sd_alg = sd_jwt.claims()._sd_alg.as_deref().unwrap_or(SHA_ALG_NAME);
+      if !self.allowed_hash_algorithms.iter().any(|allowed| allowed == sd_alg) {
+        violations.push(Error::PolicyViolation(format!(
+          "hash algorithm \"{sd_alg}\" is not allowed"
+        )));
+      }
+    }
+
+    if self.require_key_binding {
+      if sd_jwt.required_key_bind().is_none() {
+        violations.push(Error::PolicyViolation(
+          "the SD-JWT doesn't require a key binding".to_string(),
+        ));
+      }
+      if sd_jwt.key_binding_jwt().is_none() {
+        violations.push(Error::PolicyViolation("a KB-JWT must be attached".to_string()));
+      }
+    }
+
+    if !self.allowed_issuers.is_empty() {
+      let iss = sd_jwt.claims().iss().unwrap_or_default();
+      if !self.allowed_issuers.iter().any(|allowed| allowed == iss) {
+        violations.push(Error::PolicyViolation(format!("issuer \"{iss}\" is not allowed")));
+      }
+    }
+
+    if let Some(max_disclosures) = self.max_disclosures {
+      let disclosures = sd_jwt.disclosures().len();
+      if disclosures > max_disclosures {
+        violations.push(Error::PolicyViolation(format!(
+          "{disclosures} disclosures exceed the maximum of {max_disclosures}"
+        )));
+      }
+    }
+
+    violations
+  }
+
+  /// Returns a violation, together with the path it concerns, for every required claim that isn't disclosed.
+  pub(crate) fn disclosed_object_violations(&self, disclosed: &JsonObject) -> Vec<(String, Error)> {
+    let disclosed = Value::Object(disclosed.clone());
+    self
+      .required_claims
+      .iter()
+      .filter_map(|path| {
+        let error = match path.parse::<JsonPointer<_, _>>() {
+          Err(_) => Error::InvalidPath(path.to_string()),
+          Ok(pointer) if pointer.get(&disclosed).is_err() => {
+            Error::PolicyViolation(format!("required claim \"{path}\" is not disclosed"))
+          }
+          Ok(_) => return None,
+        };
+        Some((path.clone(), error))
+      })
+      .collect()
+  }
+}
+
+impl FromStr for VerificationPolicy {
+  type Err = Error;
+  fn from_str(s: &str) -> Result<Self> {
+    serde_json::from_str(s).map_err(|e| Error::DeserializationError(format!("invalid verification policy: {e}")))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_json::json;
+
+  use super::VerificationPolicy;
+  use crate::Error;
+  use crate::JwsAlgorithm;
+  use crate::SdJwt;
+
+  const SD_JWT: &str = "eyJhbGciOiAiRVMyNTYiLCAidHlwIjogImV4YW1wbGUrc2Qtand0In0.eyJfc2QiOiBbIkM5aW5wNllvUmFFWFI0Mjd6WUpQN1FyazFXSF84YmR3T0FfWVVyVW5HUVUiLCAiS3VldDF5QWEwSElRdlluT1ZkNTloY1ZpTzlVZzZKMmtTZnFZUkJlb3d2RSIsICJNTWxkT0ZGekIyZDB1bWxtcFRJYUdlcmhXZFVfUHBZZkx2S2hoX2ZfOWFZIiwgIlg2WkFZT0lJMnZQTjQwVjd4RXhad1Z3ejd5Um1MTmNWd3Q1REw4Ukx2NGciLCAiWTM0em1JbzBRTExPdGRNcFhHd2pCZ0x2cjE3eUVoaFlUMEZHb2ZSLWFJRSIsICJmeUdwMFdUd3dQdjJKRFFsbjFsU2lhZW9iWnNNV0ExMGJRNTk4OS05RFRzIiwgIm9tbUZBaWNWVDhMR0hDQjB1eXd4N2ZZdW8zTUhZS08xNWN6LVJaRVlNNVEiLCAiczBCS1lzTFd4UVFlVTh0VmxsdE03TUtzSVJUckVJYTFQa0ptcXhCQmY1VSJdLCAiaXNzIjogImh0dHBzOi8vaXNzdWVyLmV4YW1wbGUuY29tIiwgImlhdCI6IDE2ODMwMDAwMDAsICJleHAiOiAxODgzMDAwMDAwLCAiYWRkcmVzcyI6IHsiX3NkIjogWyI2YVVoelloWjdTSjFrVm1hZ1FBTzN1MkVUTjJDQzFhSGhlWnBLbmFGMF9FIiwgIkF6TGxGb2JrSjJ4aWF1cFJFUHlvSnotOS1OU2xkQjZDZ2pyN2ZVeW9IemciLCAiUHp6Y1Z1MHFiTXVCR1NqdWxmZXd6a2VzRDl6dXRPRXhuNUVXTndrclEtayIsICJiMkRrdzBqY0lGOXJHZzhfUEY4WmN2bmNXN3p3Wmo1cnlCV3ZYZnJwemVrIiwgImNQWUpISVo4VnUtZjlDQ3lWdWIyVWZnRWs4anZ2WGV6d0sxcF9KbmVlWFEiLCAiZ2xUM2hyU1U3ZlNXZ3dGNVVEWm1Xd0JUdzMyZ25VbGRJaGk4aEdWQ2FWNCIsICJydkpkNmlxNlQ1ZWptc0JNb0d3dU5YaDlxQUFGQVRBY2k0MG9pZEVlVnNBIiwgInVOSG9XWWhYc1poVkpDTkUyRHF5LXpxdDd0NjlnSkt5NVFhRnY3R3JNWDQiXX0sICJfc2RfYWxnIjogInNoYS0yNTYifQ.gR6rSL7urX79CNEvTQnP1MH5xthG11ucIV44SqKFZ4Pvlu_u16RfvXQd4k4CAIBZNKn2aTI18TfvFwV97gJFoA~WyJHMDJOU3JRZmpGWFE3SW8wOXN5YWpBIiwgInJlZ2lvbiIsICJcdTZlMmZcdTUzM2EiXQ~WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgImNvdW50cnkiLCAiSlAiXQ~";
+
+  fn check(policy: serde_json::Value) -> Result<(), Error> {
+    let policy: VerificationPolicy = serde_json::from_value(policy).unwrap();
+    let sd_jwt = SdJwt::parse(SD_JWT).unwrap();
+    let disclosed = sd_jwt
+      .clone()
+      .into_disclosed_object(&crate::Sha256Hasher::new())
+      .unwrap();
+    policy.check(&sd_jwt, &disclosed)
+  }
+
+  #[test]
+  fn empty_policy_accepts_everything() {
+    assert!(check(json!({})).is_ok());
+  }
+
+  #[test]
+  fn algorithms_are_checked() {
+    assert!(check(json!({ "allowed_algorithms": ["ES256"], "allowed_hash_algorithms": ["sha-256"] })).is_ok());
+    assert!(matches!(
+      check(json!({ "allowed_algorithms": ["EdDSA"] })),
+      Err(Error::PolicyViolation(_))
+    ));
+    assert!(matches!(
+      check(json!({ "allowed_hash_algorithms": ["sha-512"] })),
+      Err(Error::PolicyViolation(_))
+    ));
+  }
+
+  #[test]
+  fn issuer_and_key_binding_are_checked() {
+    assert!(check(json!({ "allowed_issuers": ["https://issuer.example.com"] })).is_ok());
+    assert!(matches!(
+      check(json!({ "allowed_issuers": ["https://another.example.com"] })),
+      Err(Error::PolicyViolation(_))
+    ));
+    assert!(matches!(
+      check(json!({ "require_key_binding": true })),
+      Err(Error::PolicyViolation(_))
+    ));
+  }
+
+  #[test]
+  fn disclosures_are_checked() {
+    assert!(check(json!({ "required_claims": ["/address/region"], "max_disclosures": 2 })).is_ok());
+    assert!(matches!(
+      check(json!({ "required_claims": ["/address/street_address"] })),
+      Err(Error::PolicyViolation(_))
+    ));
+    assert!(matches!(
+      check(json!({ "max_disclosures": 1 })),
+      Err(Error::PolicyViolation(_))
+    ));
+  }
+
+  #[test]
+  fn round_trip_ser_des() {
+    let policy = VerificationPolicy {
+      allowed_algorithms: vec![JwsAlgorithm::ES256],
+      require_key_binding: true,
+      max_disclosures: Some(3),
+      ..Default::default()
+    };
+    let json = serde_json::to_string(&policy).unwrap();
+    assert_eq!(json.parse::<VerificationPolicy>().unwrap(), policy);
+  }
+}
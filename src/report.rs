@@ -0,0 +1,306 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Clock;
+use crate::Error;
+use crate::JsonObject;
+use crate::KeyBindingJwtValidation;
+use crate::KeyResolver;
+use crate::Result;
+#[cfg(feature = "status-list")]
+use crate::StatusListResolver;
+use crate::SystemClock;
+use crate::VerificationPolicy;
+
+/// The kind of check a [`VerificationCheck`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum CheckKind {
+  /// The hasher matches the `_sd_alg` claim.
+  HashAlgorithm,
+  /// The issuer-signed JWT's signature.
+  IssuerSignature,
+  /// The `exp`, `nbf` and `iat` claims.
+  TimeValidity,
+  /// A disclosure could be matched to a digest and decoded.
+  Disclosure,
+  /// A digest appears more than once.
+  DuplicateDigest,
+  /// A disclosed claim collides with an existing one.
+  ClaimCollision,
+  /// A disclosure doesn't match any digest.
+  UnusedDisclosure,
+  /// The KB-JWT.
+  KeyBinding,
+  /// A rule of a [`crate::VerificationPolicy`].
+  Policy,
+  /// The credential's status in the status list it references.
+  Status,
+}
+
+/// Outcome of a [`VerificationCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+  Passed,
+  Failed,
+  /// The check couldn't be performed, e.g. because of missing inputs or a previous failure.
+  Skipped,
+}
+
+/// A single check performed while verifying an SD-JWT.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationCheck {
+  pub kind: CheckKind,
+  pub status: CheckStatus,
+  /// [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) to the claim involved, if any.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub path: Option<String>,
+  /// Digest of the disclosure involved, if any.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub digest: Option<String>,
+  /// Human-readable explanation of failures and skips.
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub message: Option<String>,
+}
+
+/// Collects the outcome of every check performed while verifying an SD-JWT, instead of stopping at the first failure.
+///
+/// The disclosed claims are kept out of the serialized report, so that it can be logged safely.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+  checks: Vec<VerificationCheck>,
+  #[serde(skip)]
+  disclosed_object: Option<JsonObject>,
+  #[serde(skip)]
+  first_error: Option<Error>,
+}
+
+impl VerificationReport {
+  /// Creates a new empty [`VerificationReport`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns all the checks that have been performed, in order.
+  pub fn checks(&self) -> &[VerificationCheck] {
+    &self.checks
+  }
+
+  /// Returns the checks that failed.
+  pub fn failures(&self) -> impl Iterator<Item = &VerificationCheck> {
+    self.checks.iter().filter(|check| check.status == CheckStatus::Failed)
+  }
+
+  /// Returns `true` if no check failed.
+  pub fn is_valid(&self) -> bool {
+    self.failures().next().is_none()
+  }
+
+  /// Returns the disclosed object, if the disclosures could be decoded.
+  pub fn disclosed_object(&self) -> Option<&JsonObject> {
+    self.disclosed_object.as_ref()
+  }
+
+  /// Converts this report into the result the `Result`-returning verification API would have produced, i.e. the
+  /// disclosed object or the error of the first failed check.
+  pub fn into_result(self) -> Result<JsonObject> {
+    if let Some(error) = self.first_error {
+      return Err(error);
+    }
+    self
+      .disclosed_object
+      .ok_or_else(|| Error::Unspecified("the disclosed object is not available".to_string()))
+  }
+
+  pub(crate) fn pass(&mut self, kind: CheckKind, path: Option<String>, digest: Option<String>) {
+    self.push(kind, CheckStatus::Passed, path, digest, None);
+  }
+
+  pub(crate) fn fail(&mut self, kind: CheckKind, path: Option<String>, digest: Option<String>, error: Error) {
+    self.push(kind, CheckStatus::Failed, path, digest, Some(error.to_string()));
+    self.first_error.get_or_insert(error);
+  }
+
+  pub(crate) fn skip(&mut self, kind: CheckKind, reason: &str) {
+    self.push(kind, CheckStatus::Skipped, None, None, Some(reason.to_string()));
+  }
+
+  /// Records the outcome of a check returning a [`Result`].
+  pub(crate) fn record(&mut self, kind: CheckKind, result: Result<()>) {
+    match result {
+      Ok(()) => self.pass(kind, None, None),
+      Err(error) => self.fail(kind, None, None, error),
+    }
+  }
+
+  pub(crate) fn set_disclosed_object(&mut self, object: JsonObject) {
+    self.disclosed_object = Some(object);
+  }
+
+  fn push(
+    &mut self,
+    kind: CheckKind,
+    status: CheckStatus,
+    path: Option<String>,
+    digest: Option<String>,
+    message: Option<String>,
+  ) {
+    self.checks.push(VerificationCheck {
+      kind,
+      status,
+      path,
+      digest,
+      message,
+    });
+  }
+}
+
+/// Inputs of [`crate::SdJwt::verify_with_options`] and [`crate::SdJwt::verify_with_report`] besides the verifier and
+/// the hasher.
+#[derive(Clone)]
+pub struct VerificationOptions {
+  pub(crate) policy: Option<VerificationPolicy>,
+  pub(crate) key_binding: Option<KeyBindingJwtValidation>,
+  pub(crate) key_resolver: Option<Arc<dyn KeyResolver + Send + Sync>>,
+  pub(crate) clock: Arc<dyn Clock>,
+  pub(crate) leeway: Duration,
+  #[cfg(feature = "status-list")]
+  pub(crate) status_list: Option<StatusListResolver>,
+}
+
+impl Default for VerificationOptions {
+  fn default() -> Self {
+    Self {
+      policy: None,
+      key_binding: None,
+      key_resolver: None,
+      clock: Arc::new(SystemClock),
+      leeway: Duration::ZERO,
+      #[cfg(feature = "status-list")]
+      status_list: None,
+    }
+  }
+}
+
+impl Debug for VerificationOptions {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut debug = f.debug_struct("VerificationOptions");
+    debug
+      .field("policy", &self.policy)
+      .field("key_binding", &self.key_binding)
+      .field("clock", &self.clock)
+      .field("leeway", &self.leeway);
+    #[cfg(feature = "status-list")]
+    debug.field("status_list", &self.status_list);
+    debug.finish_non_exhaustive()
+  }
+}
+
+impl VerificationOptions {
+  /// Creates new [`VerificationOptions`] which only check the issuer's signature, the time claims and the
+  /// disclosures.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Checks the SD-JWT against `policy`.
+  pub fn policy(mut self, policy: VerificationPolicy) -> Self {
+    self.policy = Some(policy);
+    self
+  }
+
+  /// Verifies the attached KB-JWT with `validation`. When not set, the key binding check is skipped.
+  pub fn key_binding(mut self, validation: KeyBindingJwtValidation) -> Self {
+    self.key_binding = Some(validation);
+    self
+  }
+
+  /// Resolves the keys the signatures are verified with through `resolver`: the issuer's key from the issuer-signed
+  /// JWT's header and claims, and the holder's key from the `cnf` claim. The resolved keys are passed to the verifier.
+  /// When not set, the verifier is given no issuer key, and the holder's key only if `cnf` contains it.
+  pub fn key_resolver<R>(mut self, resolver: R) -> Self
+  where
+    R: KeyResolver + Send + Sync + 'static,
+  {
+    self.key_resolver = Some(Arc::new(resolver));
+    self
+  }
+
+  /// Sets the [`Clock`] the `exp`, `nbf` and `iat` claims are checked against, [`SystemClock`] by default.
+  pub fn clock<C>(mut self, clock: C) -> Self
+  where
+    C: Clock + 'static,
+  {
+    self.clock = Arc::new(clock);
+    self
+  }
+
+  /// Sets the tolerated clock skew for the time claims.
+  pub fn leeway(mut self, leeway: Duration) -> Self {
+    self.leeway = leeway;
+    self
+  }
+
+  /// Checks the credential's status in the status list it references, retrieving the status list token with
+  /// `resolver`. When not set, the status isn't checked.
+  #[cfg(feature = "status-list")]
+  pub fn status_list(mut self, resolver: StatusListResolver) -> Self {
+    self.status_list = Some(resolver);
+    self
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use serde_json::json;
+
+  use super::CheckKind;
+  use super::VerificationReport;
+  use crate::Error;
+
+  #[test]
+  fn serialization_omits_disclosed_claims() {
+    let mut report = VerificationReport::new();
+    report.pass(CheckKind::IssuerSignature, None, None);
+    report.fail(
+      CheckKind::DuplicateDigest,
+      Some("/address".to_string()),
+      Some("digest".to_string()),
+      Error::DuplicateDigestError("digest".to_string()),
+    );
+    report.skip(CheckKind::KeyBinding, "no KB-JWT validation was requested");
+    report.set_disclosed_object(json!({ "given_name": "John" }).as_object().unwrap().clone());
+
+    assert_eq!(
+      serde_json::to_value(&report).unwrap(),
+      json!({
+        "checks": [
+          { "kind": "issuer_signature", "status": "passed" },
+          {
+            "kind": "duplicate_digest",
+            "status": "failed",
+            "path": "/address",
+            "digest": "digest",
+            "message": "digest digest appears multiple times"
+          },
+          { "kind": "key_binding", "status": "skipped", "message": "no KB-JWT validation was requested" }
+        ]
+      })
+    );
+    assert!(!report.is_valid());
+    assert_eq!(report.failures().count(), 1);
+    assert_eq!(
+      report.into_result(),
+      Err(Error::DuplicateDigestError("digest".to_string()))
+    );
+  }
+}
@@ -0,0 +1,87 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Debug;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+/// Source of the random data used for the salts of disclosures and for decoy digests.
+pub trait SaltGenerator: Debug + Send + Sync {
+  /// Fills `bytes` with random data.
+  fn fill_bytes(&mut self, bytes: &mut [u8]);
+}
+
+/// A [`SaltGenerator`] backed by the thread-local, cryptographically secure random number generator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadRngSaltGenerator;
+
+impl SaltGenerator for ThreadRngSaltGenerator {
+  fn fill_bytes(&mut self, bytes: &mut [u8]) {
+    rand::thread_rng().fill(bytes);
+  }
+}
+
+/// A [`SaltGenerator`] producing the same sequence of data for the same seed, e.g. to reproduce SD-JWTs in tests.
+///
+/// ## Notes
+/// Salts must be unpredictable for disclosures to stay hidden: never issue SD-JWTs with a known seed.
+#[derive(Debug, Clone)]
+pub struct SeededSaltGenerator(StdRng);
+
+impl SeededSaltGenerator {
+  /// Creates a new [`SeededSaltGenerator`] from `seed`.
+  pub fn new(seed: u64) -> Self {
+    Self(StdRng::seed_from_u64(seed))
+  }
+}
+
+impl SaltGenerator for SeededSaltGenerator {
+  fn fill_bytes(&mut self, bytes: &mut [u8]) {
+    self.0.fill_bytes(bytes);
+  }
+}
+
+/// Adapts a [`SaltGenerator`] to [`RngCore`], to draw random numbers from it.
+pub(crate) struct SaltRng<'g>(pub(crate) &'g mut dyn SaltGenerator);
+
+impl RngCore for SaltRng<'_> {
+  fn next_u32(&mut self) -> u32 {
+    let mut bytes = [0; 4];
+    self.0.fill_bytes(&mut bytes);
+    u32::from_le_bytes(bytes)
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    let mut bytes = [0; 8];
+    self.0.fill_bytes(&mut bytes);
+    u64::from_le_bytes(bytes)
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    self.0.fill_bytes(dest);
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+    self.0.fill_bytes(dest);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn seeded_generators_are_reproducible() {
+    let (mut first, mut second) = ([0; 16], [0; 16]);
+    SeededSaltGenerator::new(42).fill_bytes(&mut first);
+    SeededSaltGenerator::new(42).fill_bytes(&mut second);
+    assert_eq!(first, second);
+
+    SeededSaltGenerator::new(43).fill_bytes(&mut second);
+    assert_ne!(first, second);
+  }
+}
@@ -6,21 +6,47 @@ use std::iter::Peekable;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::str::FromStr;
+use std::time::Duration;
 
+use crate::jwe::decode_jwe_header;
+use crate::jws_json::FlattenedJws;
+use crate::jws_json::GeneralJws;
+use crate::jws_json::SdJwtUnprotectedHeader;
 use crate::jwt::Jwt;
+use crate::CheckKind;
+use crate::ClaimDisclosability;
+use crate::ClaimMetadata;
+use crate::Clock;
 use crate::Disclosure;
 use crate::Error;
 use crate::Hasher;
 use crate::JsonObject;
+use crate::JweDecrypter;
+use crate::JweEncrypter;
+use crate::JwsHeader;
+use crate::JwsJsonSerialization;
+use crate::JwsSignature;
+use crate::JwsVerifier;
+use crate::JwsVerifierSync;
 use crate::KeyBindingJwt;
+use crate::KeyBindingJwtValidation;
+use crate::KeyResolver;
 use crate::RequiredKeyBinding;
+use crate::ResolvedTypeMetadata;
 use crate::Result;
+use crate::SaltGenerator;
+use crate::SaltRng;
 use crate::SdObjectDecoder;
+use crate::SystemClock;
+use crate::VerificationOptions;
+use crate::VerificationPolicy;
+use crate::VerificationReport;
 use crate::ARRAY_DIGEST_KEY;
 use crate::DIGESTS_KEY;
 use crate::SHA_ALG_NAME;
 use indexmap::IndexMap;
 use itertools::Itertools;
+use rand::seq::SliceRandom;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -37,6 +63,99 @@ pub struct SdJwtClaims {
   properties: JsonObject,
 }
 
+impl SdJwtClaims {
+  /// Returns the [iss](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.1) claim, if present.
+  pub fn iss(&self) -> Option<&str> {
+    self.properties.get("iss").and_then(Value::as_str)
+  }
+
+  /// Returns the [sub](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.2) claim, if present.
+  pub fn sub(&self) -> Option<&str> {
+    self.properties.get("sub").and_then(Value::as_str)
+  }
+
+  /// Returns the audiences listed by the [aud](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.3) claim,
+  /// which can either be a single string or an array of strings.
+  pub fn aud(&self) -> Vec<&str> {
+    match self.properties.get("aud") {
+      Some(Value::String(aud)) => vec![aud.as_str()],
+      Some(Value::Array(auds)) => auds.iter().flat_map(Value::as_str).collect(),
+      _ => vec![],
+    }
+  }
+
+  /// Returns the [exp](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.4) claim, if present.
+  ///
+  /// ## Notes
+  /// A value that isn't a `NumericDate` reads as absent, use [`SdJwtClaims::validate_time`] to reject it.
+  pub fn exp(&self) -> Option<i64> {
+    self.numeric_date("exp").ok().flatten()
+  }
+
+  /// Returns the [nbf](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.5) claim, if present.
+  ///
+  /// ## Notes
+  /// A value that isn't a `NumericDate` reads as absent, use [`SdJwtClaims::validate_time`] to reject it.
+  pub fn nbf(&self) -> Option<i64> {
+    self.numeric_date("nbf").ok().flatten()
+  }
+
+  /// Returns the [iat](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.6) claim, if present.
+  ///
+  /// ## Notes
+  /// A value that isn't a `NumericDate` reads as absent, use [`SdJwtClaims::validate_time`] to reject it.
+  pub fn iat(&self) -> Option<i64> {
+    self.numeric_date("iat").ok().flatten()
+  }
+
+  /// Returns the [jti](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.7) claim, if present.
+  pub fn jti(&self) -> Option<&str> {
+    self.properties.get("jti").and_then(Value::as_str)
+  }
+
+  /// Checks the `exp`, `nbf` and `iat` claims against the time provided by `clock`,
+  /// tolerating a clock skew of `leeway`.
+  /// ## Errors
+  /// - [`Error::ExpiredJwt`] is returned if `exp` is in the past.
+  /// - [`Error::JwtNotYetValid`] is returned if either `nbf` or `iat` are in the future.
+  /// - [`Error::DataTypeMismatch`] is returned if any of these claims is not a number.
+  pub fn validate_time(&self, clock: &dyn Clock, leeway: Duration) -> Result<()> {
+    let now = clock.now();
+    let leeway = leeway.as_secs() as i64;
+
+    if let Some(exp) = self.numeric_date("exp")? {
+      if now - leeway >= exp {
+        return Err(Error::ExpiredJwt(exp));
+      }
+    }
+    if let Some(nbf) = self.numeric_date("nbf")? {
+      if now + leeway < nbf {
+        return Err(Error::JwtNotYetValid(nbf));
+      }
+    }
+    if let Some(iat) = self.numeric_date("iat")? {
+      if now + leeway < iat {
+        return Err(Error::JwtNotYetValid(iat));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn numeric_date(&self, claim: &str) -> Result<Option<i64>> {
+    self
+      .properties
+      .get(claim)
+      .map(|value| {
+        value
+          .as_i64()
+          .or_else(|| value.as_f64().map(|timestamp| timestamp as i64))
+          .ok_or_else(|| Error::DataTypeMismatch(format!("`{claim}` must be a NumericDate")))
+      })
+      .transpose()
+  }
+}
+
 impl Deref for SdJwtClaims {
   type Target = JsonObject;
   fn deref(&self) -> &Self::Target {
@@ -51,7 +170,8 @@ impl DerefMut for SdJwtClaims {
 }
 
 /// Representation of an SD-JWT of the format
-/// `<Issuer-signed JWT>~<Disclosure 1>~<Disclosure 2>~...~<Disclosure N>~<optional KB-JWT>`.
+/// `<Issuer-signed JWT>~<Disclosure 1>~<Disclosure 2>~...~<Disclosure N>~<optional KB-JWT>`,
+/// or of its equivalent in the JWS JSON Serialization.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SdJwt {
   /// The JWT part.
@@ -60,6 +180,8 @@ pub struct SdJwt {
   disclosures: Vec<Disclosure>,
   /// The optional key binding JWT.
   key_binding_jwt: Option<KeyBindingJwt>,
+  /// The issuer signatures following the JWT's one, in the general JWS JSON Serialization.
+  additional_signatures: Vec<JwsSignature>,
 }
 
 impl SdJwt {
@@ -73,10 +195,16 @@ impl SdJwt {
       jwt,
       disclosures,
       key_binding_jwt,
+      additional_signatures: vec![],
     }
   }
 
-  pub fn header(&self) -> &JsonObject {
+  pub(crate) fn with_additional_signatures(mut self, signatures: Vec<JwsSignature>) -> Self {
+    self.additional_signatures = signatures;
+    self
+  }
+
+  pub fn header(&self) -> &JwsHeader {
     &self.jwt.header
   }
 
@@ -104,26 +232,204 @@ impl SdJwt {
     self.key_binding_jwt.as_ref()
   }
 
+  /// Returns the issuer signatures following the first one, which is part of the issuer-signed JWT.
+  /// Only SD-JWTs in the general JWS JSON Serialization can have additional signatures.
+  ///
+  /// ## Notes
+  /// The verification methods of [`SdJwt`] only check the first signature, use [`SdJwt::verify_signature`] to check
+  /// the others.
+  pub fn additional_signatures(&self) -> &[JwsSignature] {
+    &self.additional_signatures
+  }
+
+  /// Verifies the issuer signature at `index` with `verifier`: index 0 is the issuer-signed JWT's signature, checked
+  /// by [`SdJwt::verify`] as well, and the following indices are the ones of [`SdJwt::additional_signatures`].
+  ///
+  /// ## Notes
+  /// Only the signature is checked, not the claims.
+  /// ## Errors
+  /// - [`Error::JwsVerificationFailure`] is returned if there's no signature at `index` or if it's invalid.
+  /// - [`Error::DeserializationError`] is returned if the signature's protected header is invalid.
+  pub async fn verify_signature<V>(&self, index: usize, verifier: &V) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    let Some(index) = index.checked_sub(1) else {
+      return self.jwt.verify(verifier, None).await;
+    };
+    let signature = self
+      .additional_signatures
+      .get(index)
+      .ok_or_else(|| Error::JwsVerificationFailure(format!("the SD-JWT has no signature at index {}", index + 1)))?;
+    let (payload, _) = JwsSignature::from_compact(&self.jwt.jws)?;
+    let jwt = Jwt {
+      header: signature.protected_header()?,
+      claims: (),
+      jws: signature.to_compact(&payload),
+    };
+
+    jwt.verify(verifier, None).await
+  }
+
   /// Serializes the components into the final SD-JWT.
   ///
   /// ## Error
   /// Returns [`Error::DeserializationError`] if parsing fails.
   pub fn presentation(&self) -> String {
-    let disclosures = self.disclosures.iter().map(ToString::to_string).join("~");
     let key_bindings = self
       .key_binding_jwt
       .as_ref()
       .map(ToString::to_string)
       .unwrap_or_default();
+    format!("{}{}", self.presentation_without_key_binding(), key_bindings)
+  }
+
+  /// Serializes the issuer-signed JWT and the disclosures, omitting any KB-JWT.
+  /// This is the input `sd_hash` is computed over.
+  pub(crate) fn presentation_without_key_binding(&self) -> String {
+    let disclosures = self.disclosures.iter().map(ToString::to_string).join("~");
     if disclosures.is_empty() {
-      format!("{}~{}", self.jwt, key_bindings)
+      format!("{}~", self.jwt)
     } else {
-      format!("{}~{}~{}", self.jwt, disclosures, key_bindings)
+      format!("{}~{}~", self.jwt, disclosures)
     }
   }
 
+  /// Encrypts the compact presentation of this SD-JWT - see [`SdJwt::presentation`] - into a JWE in compact
+  /// serialization, e.g. to the key of the verifier it is presented to. `header` is the JWE's protected header and
+  /// must specify the algorithms used through its `alg` and `enc` properties.
+  /// ## Error
+  /// Returns [`Error::JweEncryptionFailure`] if `encrypter` fails.
+  pub async fn encrypted_presentation<E>(&self, encrypter: &E, header: &JsonObject) -> Result<String>
+  where
+    E: JweEncrypter,
+  {
+    encrypter
+      .encrypt(header, self.presentation().as_bytes())
+      .await
+      .map_err(|e| Error::JweEncryptionFailure(e.to_string()))
+  }
+
+  /// Serializes this SD-JWT in the flattened or general JWS JSON Serialization, placing the disclosures and the
+  /// KB-JWT in the (first) unprotected header.
+  ///
+  /// ## Notes
+  /// A KB-JWT's `sd_hash` is always computed over the compact serialization of the SD-JWT - using its first
+  /// signature - which is why the same KB-JWT is valid in every serialization.
+  /// ## Error
+  /// Returns [`Error::Unspecified`] if [`JwsJsonSerialization::Flattened`] is requested for an SD-JWT with
+  /// additional signatures.
+  pub fn presentation_json(&self, serialization: JwsJsonSerialization) -> Result<String> {
+    let (payload, mut signature) = JwsSignature::from_compact(&self.jwt.jws)?;
+    let header = SdJwtUnprotectedHeader {
+      disclosures: self.disclosures.iter().map(ToString::to_string).collect(),
+      kb_jwt: self.key_binding_jwt.as_ref().map(ToString::to_string),
+      properties: JsonObject::default(),
+    };
+
+    let json = match serialization {
+      JwsJsonSerialization::Flattened => {
+        if !self.additional_signatures.is_empty() {
+          return Err(Error::Unspecified(
+            "the flattened JWS JSON serialization cannot hold more than one signature".to_string(),
+          ));
+        }
+        serde_json::to_string(&FlattenedJws {
+          header,
+          payload,
+          protected: signature.protected,
+          signature: signature.signature,
+        })
+      }
+      JwsJsonSerialization::General => {
+        let Value::Object(header) = serde_json::to_value(header).expect("header is serializable") else {
+          unreachable!("header is an object");
+        };
+        signature.header = Some(header);
+        serde_json::to_string(&GeneralJws {
+          payload,
+          signatures: std::iter::once(signature)
+            .chain(self.additional_signatures.iter().cloned())
+            .collect(),
+        })
+      }
+    };
+
+    Ok(json.expect("JWS JSON is serializable"))
+  }
+
+  /// Parses an SD-JWT in either the flattened or general JWS JSON Serialization into its components as [`SdJwt`].
+  ///
+  /// ## Notes
+  /// Unprotected header parameters of the first signature other than `disclosures` and `kb_jwt` are discarded.
+  /// ## Error
+  /// Returns [`Error::DeserializationError`] if parsing fails or if a signature other than the first one carries
+  /// disclosures or a KB-JWT.
+  pub fn parse_json(json: &str) -> Result<Self> {
+    let deserialization_error = |e: serde_json::Error| Error::DeserializationError(format!("invalid JWS JSON: {e}"));
+    let object: JsonObject = serde_json::from_str(json).map_err(deserialization_error)?;
+
+    let (payload, mut signatures, header) = if object.contains_key("signatures") {
+      let GeneralJws {
+        payload,
+        mut signatures,
+      } = serde_json::from_value(Value::Object(object)).map_err(deserialization_error)?;
+      let first = signatures
+        .first_mut()
+        .ok_or_else(|| Error::DeserializationError("invalid JWS JSON: no signatures".to_string()))?;
+      let header: SdJwtUnprotectedHeader = first
+        .header
+        .take()
+        .map(|header| serde_json::from_value(Value::Object(header)))
+        .transpose()
+        .map_err(deserialization_error)?
+        .unwrap_or_default();
+      let misplaced_members = signatures[1..]
+        .iter()
+        .filter_map(|signature| signature.header.as_ref())
+        .any(|header| header.contains_key("disclosures") || header.contains_key("kb_jwt"));
+      if misplaced_members {
+        return Err(Error::DeserializationError(
+          "invalid JWS JSON: only the first signature may carry disclosures or a KB-JWT".to_string(),
+        ));
+      }
+
+      (payload, signatures, header)
+    } else {
+      let FlattenedJws {
+        header,
+        payload,
+        protected,
+        signature,
+      } = serde_json::from_value(Value::Object(object)).map_err(deserialization_error)?;
+      let signature = JwsSignature {
+        protected,
+        header: None,
+        signature,
+      };
+
+      (payload, vec![signature], header)
+    };
+
+    let jwt = signatures.remove(0).to_compact(&payload).parse()?;
+    let disclosures = header
+      .disclosures
+      .iter()
+      .map(|disclosure| Disclosure::parse(disclosure))
+      .try_collect()?;
+    let key_binding_jwt = header.kb_jwt.as_deref().map(str::parse).transpose()?;
+
+    Ok(Self::new(jwt, disclosures, key_binding_jwt).with_additional_signatures(signatures))
+  }
+
   /// Parses an SD-JWT into its components as [`SdJwt`].
+  ///
+  /// Both the compact serialization and the JWS JSON Serialization - see [`SdJwt::parse_json`] - are supported.
   pub fn parse(sd_jwt: &str) -> Result<Self> {
+    if sd_jwt.trim_start().starts_with('{') {
+      return Self::parse_json(sd_jwt);
+    }
+
     let sd_segments: Vec<&str> = sd_jwt.split('~').collect();
     let num_of_segments = sd_segments.len();
     if num_of_segments < 2 {
@@ -145,11 +451,27 @@ impl SdJwt {
       .map(|segment| segment.parse())
       .transpose()?;
 
-    Ok(Self {
-      jwt,
-      disclosures,
-      key_binding_jwt,
-    })
+    Ok(Self::new(jwt, disclosures, key_binding_jwt))
+  }
+
+  /// Decrypts the JWE in compact serialization `jwe` with `decrypter`, and parses its plaintext as [`SdJwt`].
+  /// ## Errors
+  /// - [`Error::DeserializationError`] is returned if `jwe` isn't a JWE in compact serialization or its plaintext isn't
+  ///   a valid SD-JWT.
+  /// - [`Error::JweDecryptionFailure`] is returned if `decrypter` fails.
+  pub async fn parse_encrypted<D>(jwe: &str, decrypter: &D) -> Result<Self>
+  where
+    D: JweDecrypter,
+  {
+    let header = decode_jwe_header(jwe)?;
+    let plaintext = decrypter
+      .decrypt(&header, jwe)
+      .await
+      .map_err(|e| Error::JweDecryptionFailure(e.to_string()))?;
+    let sd_jwt = String::from_utf8(plaintext)
+      .map_err(|_| Error::DeserializationError("the decrypted SD-JWT is not valid UTF-8".to_string()))?;
+
+    Self::parse(&sd_jwt)
   }
 
   /// Prepares this [`SdJwt`] for a presentation, returning an [`SdJwtPresentationBuilder`].
@@ -160,6 +482,320 @@ impl SdJwt {
     SdJwtPresentationBuilder::new(self, hasher)
   }
 
+  /// Verifies the issuer's signature and the time claims of this SD-JWT - against the
+  /// [`SystemClock`](crate::SystemClock), without leeway - and returns the JSON object obtained by replacing all
+  /// disclosures into their corresponding JWT concealable claims.
+  /// ## Errors
+  /// - [`Error::InvalidHasher`] is returned if the provided `hasher`'s algorithm doesn't match the algorithm specified
+  ///   by SD-JWT's `_sd_alg` claim. "sha-256" is used if the claim is missing.
+  /// - [`Error::JwsVerificationFailure`] is returned if the issuer-signed JWT's signature is invalid.
+  /// - Any error returned by [`SdJwtClaims::validate_time`].
+  /// - Any error returned by [`SdJwt::into_disclosed_object`].
+  pub async fn verify<V>(&self, verifier: &V, hasher: &dyn Hasher) -> Result<JsonObject>
+  where
+    V: JwsVerifier,
+  {
+    self
+      .verify_with_options(verifier, hasher, &VerificationOptions::default())
+      .await
+  }
+
+  /// Verifies this SD-JWT like [`SdJwt::verify`] does, with a blocking `verifier`.
+  /// ## Errors
+  /// See [`SdJwt::verify`].
+  pub fn verify_sync<V>(&self, verifier: &V, hasher: &dyn Hasher) -> Result<JsonObject>
+  where
+    V: JwsVerifierSync,
+  {
+    check_hasher(self.claims(), hasher)?;
+    self.jwt.verify_sync(verifier, None)?;
+    self.claims().validate_time(&SystemClock, Duration::ZERO)?;
+
+    self.clone().into_disclosed_object(hasher)
+  }
+
+  /// Verifies this SD-JWT like [`SdJwt::verify`] does, additionally checking it against `policy`.
+  ///
+  /// ## Notes
+  /// - Rules that don't depend on the disclosed claims, e.g. allowed algorithms, are checked before the signature.
+  /// - The KB-JWT's presence is checked, but it's not verified. Use [`SdJwt::verify_key_binding`] for that.
+  /// ## Errors
+  /// - [`Error::PolicyViolation`] is returned if any of the policy's rules isn't satisfied.
+  /// - Any error returned by [`SdJwt::verify`].
+  pub async fn verify_with_policy<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    policy: &VerificationPolicy,
+  ) -> Result<JsonObject>
+  where
+    V: JwsVerifier,
+  {
+    let options = VerificationOptions::new().policy(policy.clone());
+    self.verify_with_options(verifier, hasher, &options).await
+  }
+
+  /// Verifies this SD-JWT like [`SdJwt::verify_with_report`] does, but stops at the first failure and returns the
+  /// disclosed object.
+  ///
+  /// ## Notes
+  /// - The time claims are checked against the clock and with the leeway set in `options`.
+  /// - The keys are resolved by the `KeyResolver` set with `VerificationOptions::key_resolver`, if any.
+  /// - The KB-JWT is only verified when `VerificationOptions::key_binding` is set.
+  /// - The credential's status is only checked when a `StatusListResolver` is set with
+  ///   `VerificationOptions::status_list`.
+  /// ## Errors
+  /// - [`Error::PolicyViolation`] is returned if any of the policy's rules isn't satisfied.
+  /// - Any error returned by [`SdJwt::verify`] or [`SdJwt::verify_key_binding`].
+  /// - Any error returned by `SdJwt::verify_status`, e.g. [`Error::CredentialRevoked`].
+  pub async fn verify_with_options<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    options: &VerificationOptions,
+  ) -> Result<JsonObject>
+  where
+    V: JwsVerifier,
+  {
+    if let Some(policy) = &options.policy {
+      policy.check_sd_jwt(self)?;
+    }
+    check_hasher(self.claims(), hasher)?;
+    self
+      .verify_issuer_signature(verifier, options.key_resolver.as_deref())
+      .await?;
+    self.claims().validate_time(options.clock.as_ref(), options.leeway)?;
+    #[cfg(feature = "status-list")]
+    if let Some(resolver) = &options.status_list {
+      self.verify_status(resolver, verifier).await?;
+    }
+
+    let disclosed = self.clone().into_disclosed_object(hasher)?;
+    if let Some(policy) = &options.policy {
+      policy.check_disclosed_object(&disclosed)?;
+    }
+    if let Some(validation) = &options.key_binding {
+      self
+        .verify_key_binding_with_resolver(verifier, hasher, validation, options.key_resolver.as_deref())
+        .await?;
+    }
+
+    Ok(disclosed)
+  }
+
+  /// Verifies the issuer's signature with `verifier`, giving it the issuer's key resolved by `key_resolver`, if any.
+  async fn verify_issuer_signature<V>(
+    &self,
+    verifier: &V,
+    key_resolver: Option<&(dyn KeyResolver + Send + Sync)>,
+  ) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    let issuer_key = match key_resolver {
+      Some(resolver) => {
+        let claims = match serde_json::to_value(self.claims()).expect("claims are serializable") {
+          Value::Object(claims) => claims,
+          _ => unreachable!("claims are an object"),
+        };
+        Some(resolver.resolve_issuer_key(&self.jwt.header, &claims).await?)
+      }
+      None => None,
+    };
+
+    self.jwt.verify(verifier, issuer_key.as_ref()).await
+  }
+
+  /// Verifies the KB-JWT attached to this SD-JWT against the key required by the issuer through the `cnf` claim.
+  ///
+  /// The KB-JWT's signature is checked with `verifier`, which is given the holder's key as a JWK.
+  ///
+  /// ## Notes
+  /// - This method doesn't verify the issuer's signature, use [`SdJwt::verify`] for that.
+  /// - If no `cnf` claim is present and no KB-JWT is attached, this method succeeds.
+  /// - When `cnf` only references the key - i.e. [`RequiredKeyBinding::Kid`] or [`RequiredKeyBinding::Jwu`] - use
+  ///   [`SdJwt::verify_with_options`] with a `KeyResolver` set through `VerificationOptions::key_resolver`.
+  /// ## Errors
+  /// - [`Error::MissingKeyBindingJwt`] is returned if `cnf` is present but no KB-JWT is attached.
+  /// - [`Error::InvalidKeyBindingJwt`] is returned if a KB-JWT is attached but no `cnf` claim is present.
+  /// - Any error returned by [`RequiredKeyBinding::to_jwk`], e.g. if `cnf` only references the key.
+  /// - Any error returned by [`KeyBindingJwt::verify`].
+  pub async fn verify_key_binding<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    self
+      .verify_key_binding_with_resolver(verifier, hasher, validation, None)
+      .await
+  }
+
+  /// Verifies the KB-JWT like [`SdJwt::verify_key_binding`] does, with the holder's key resolved by `key_resolver`, if
+  /// any.
+  async fn verify_key_binding_with_resolver<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    validation: &KeyBindingJwtValidation,
+    key_resolver: Option<&(dyn KeyResolver + Send + Sync)>,
+  ) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    let Some((kb_jwt, cnf)) = self.key_binding_jwt_to_verify()? else {
+      return Ok(());
+    };
+    let holder_key = match key_resolver {
+      Some(resolver) => resolver.resolve_holder_key(cnf).await?,
+      None => cnf.to_jwk()?,
+    };
+
+    kb_jwt
+      .verify(self, hasher, verifier, Some(&holder_key), validation)
+      .await
+  }
+
+  /// Verifies the KB-JWT attached to this SD-JWT like [`SdJwt::verify_key_binding`] does, with a blocking `verifier`.
+  /// ## Errors
+  /// See [`SdJwt::verify_key_binding`].
+  pub fn verify_key_binding_sync<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<()>
+  where
+    V: JwsVerifierSync,
+  {
+    let Some((kb_jwt, cnf)) = self.key_binding_jwt_to_verify()? else {
+      return Ok(());
+    };
+    let holder_key = cnf.to_jwk()?;
+
+    kb_jwt.verify_sync(self, hasher, verifier, Some(&holder_key), validation)
+  }
+
+  /// Returns the KB-JWT to verify together with the `cnf` claim naming the holder's key, or `None` if no key binding
+  /// is required and no KB-JWT is attached.
+  /// ## Errors
+  /// - [`Error::MissingKeyBindingJwt`] is returned if `cnf` is present but no KB-JWT is attached.
+  /// - [`Error::InvalidKeyBindingJwt`] is returned if a KB-JWT is attached but there's no `cnf` to verify it against.
+  fn key_binding_jwt_to_verify(&self) -> Result<Option<(&KeyBindingJwt, &RequiredKeyBinding)>> {
+    match (self.key_binding_jwt(), self.required_key_bind()) {
+      (None, None) => Ok(None),
+      (None, Some(_)) => Err(Error::MissingKeyBindingJwt),
+      (Some(_), None) => Err(Error::InvalidKeyBindingJwt(
+        "a KB-JWT is attached, but the SD-JWT has no `cnf` claim naming the holder's key".to_string(),
+      )),
+      (Some(kb_jwt), Some(cnf)) => Ok(Some((kb_jwt, cnf))),
+    }
+  }
+
+  /// Verifies this SD-JWT like [`SdJwt::verify_with_policy`] and [`SdJwt::verify_key_binding`] do, but instead of
+  /// stopping at the first failure, performs every check it can and records its outcome in the returned
+  /// [`VerificationReport`].
+  ///
+  /// ## Notes
+  /// - The time claims are always checked, see [`SdJwtClaims::validate_time`].
+  /// - When the hasher doesn't match `_sd_alg`, the disclosures aren't decoded.
+  /// - The credential's status is only checked when a `StatusListResolver` is set with
+  ///   `VerificationOptions::status_list`.
+  /// - Use [`VerificationReport::into_result`] to obtain the disclosed object.
+  pub async fn verify_with_report<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    options: &VerificationOptions,
+  ) -> VerificationReport
+  where
+    V: JwsVerifier,
+  {
+    let mut report = VerificationReport::new();
+
+    let hasher_check = check_hasher(self.claims(), hasher);
+    let valid_hasher = hasher_check.is_ok();
+    report.record(CheckKind::HashAlgorithm, hasher_check);
+    report.record(
+      CheckKind::IssuerSignature,
+      self
+        .verify_issuer_signature(verifier, options.key_resolver.as_deref())
+        .await,
+    );
+    report.record(
+      CheckKind::TimeValidity,
+      self.claims().validate_time(options.clock.as_ref(), options.leeway),
+    );
+    #[cfg(feature = "status-list")]
+    if let Some(resolver) = &options.status_list {
+      match self.status_list_reference() {
+        Ok(None) => report.skip(CheckKind::Status, "no status list is referenced"),
+        _ => report.record(CheckKind::Status, self.verify_status(resolver, verifier).await),
+      }
+    }
+
+    let mut policy_violations = options
+      .policy
+      .as_ref()
+      .map(|policy| {
+        policy
+          .sd_jwt_violations(self)
+          .into_iter()
+          .map(|error| (None, error))
+          .collect_vec()
+      })
+      .unwrap_or_default();
+
+    if valid_hasher {
+      let object = serde_json::to_value(self.claims()).expect("claims are serializable");
+      let disclosures = self
+        .disclosures
+        .iter()
+        .map(|disclosure| (hasher.encoded_digest(disclosure.as_str()), disclosure.clone()))
+        .collect();
+      let disclosed = SdObjectDecoder.decode_with_report(
+        object.as_object().expect("claims are an object"),
+        &disclosures,
+        &mut report,
+      );
+
+      if let Some(policy) = &options.policy {
+        policy_violations.extend(
+          policy
+            .disclosed_object_violations(&disclosed)
+            .into_iter()
+            .map(|(path, error)| (Some(path), error)),
+        );
+      }
+      report.set_disclosed_object(disclosed);
+    } else {
+      report.skip(CheckKind::Disclosure, "the hasher doesn't match `_sd_alg`");
+    }
+
+    if options.policy.is_some() && policy_violations.is_empty() {
+      report.pass(CheckKind::Policy, None, None);
+    }
+    for (path, violation) in policy_violations {
+      report.fail(CheckKind::Policy, path, None, violation);
+    }
+
+    match (&options.key_binding, self.key_binding_jwt(), self.required_key_bind()) {
+      (Some(validation), _, _) => report.record(
+        CheckKind::KeyBinding,
+        self
+          .verify_key_binding_with_resolver(verifier, hasher, validation, options.key_resolver.as_deref())
+          .await,
+      ),
+      (None, None, None) => report.skip(CheckKind::KeyBinding, "no key binding is required"),
+      (None, _, _) => report.skip(CheckKind::KeyBinding, "no KB-JWT validation was requested"),
+    }
+
+    report
+  }
+
   /// Returns the JSON object obtained by replacing all disclosures into their
   /// corresponding JWT concealable claims.
   pub fn into_disclosed_object(self, hasher: &dyn Hasher) -> Result<JsonObject> {
@@ -195,6 +831,7 @@ pub struct SdJwtPresentationBuilder {
   disclosures: IndexMap<String, Disclosure>,
   removed_disclosures: Vec<Disclosure>,
   object: Value,
+  claim_rules: Vec<ClaimMetadata>,
 }
 
 impl Deref for SdJwtPresentationBuilder {
@@ -206,13 +843,7 @@ impl Deref for SdJwtPresentationBuilder {
 
 impl SdJwtPresentationBuilder {
   pub fn new(mut sd_jwt: SdJwt, hasher: &dyn Hasher) -> Result<Self> {
-    let required_hasher = sd_jwt.claims()._sd_alg.as_deref().unwrap_or(SHA_ALG_NAME);
-    if required_hasher != hasher.alg_name() {
-      return Err(Error::InvalidHasher(format!(
-        "hasher \"{}\" was provided, but \"{required_hasher} is required\"",
-        hasher.alg_name()
-      )));
-    }
+    check_hasher(sd_jwt.claims(), hasher)?;
     let disclosures = std::mem::take(&mut sd_jwt.disclosures)
       .into_iter()
       .map(|disclosure| (hasher.encoded_digest(disclosure.as_str()), disclosure))
@@ -235,6 +866,7 @@ impl SdJwtPresentationBuilder {
       disclosures,
       removed_disclosures: vec![],
       object,
+      claim_rules: vec![],
     })
   }
 
@@ -243,9 +875,27 @@ impl SdJwtPresentationBuilder {
   /// ## Notes
   /// - When concealing a claim more than one disclosure may be removed: the disclosure for the claim itself and the
   ///   disclosures for any concealable sub-claim.
+  /// - Claims the holder can't hide - claims that were not made selectively disclosable or that are marked `never` by
+  ///   the type metadata set with [`SdJwtPresentationBuilder::type_metadata`] - are reported with
+  ///   [`Error::DisclosabilityViolation`].
   pub fn conceal(mut self, path: &str) -> Result<Self> {
+    if self
+      .claim_rules
+      .iter()
+      .any(|rule| rule.sd == ClaimDisclosability::Never && rule.selects(path))
+    {
+      return Err(Error::DisclosabilityViolation(format!(
+        "claim \"{path}\" must not be selectively disclosable"
+      )));
+    }
     let path_segments = path.trim_start_matches('/').split('/').peekable();
-    let digests_to_remove = conceal(&self.object, path_segments, &self.disclosures)?
+    let digests_to_remove = conceal(&self.object, path_segments, &self.disclosures)
+      .map_err(|e| match e {
+        Error::DisclosabilityViolation(_) => {
+          Error::DisclosabilityViolation(format!("claim \"{path}\" is not selectively disclosable"))
+        }
+        e => e,
+      })?
       .into_iter()
       // needed, since some strings are borrowed for the lifetime of the borrow of `self.disclosures`.
       .map(ToOwned::to_owned)
@@ -260,6 +910,22 @@ impl SdJwtPresentationBuilder {
     Ok(self)
   }
 
+  /// Checks the claims concealed with [`SdJwtPresentationBuilder::conceal`] against the selective disclosability
+  /// rules (`sd`) of the claims described by `metadata`.
+  pub fn type_metadata(mut self, metadata: &ResolvedTypeMetadata) -> Self {
+    self.claim_rules = metadata.claims().into_iter().cloned().collect();
+    self
+  }
+
+  /// Shuffles the disclosures left in the presentation using `salt_generator` as source of randomness, so that
+  /// their order doesn't reveal the order in which the issuer created them.
+  pub fn shuffle_disclosures(mut self, salt_generator: &mut dyn SaltGenerator) -> Self {
+    let mut disclosures = std::mem::take(&mut self.disclosures).into_iter().collect_vec();
+    disclosures.shuffle(&mut SaltRng(salt_generator));
+    self.disclosures = disclosures.into_iter().collect();
+    self
+  }
+
   /// Adds a [`KeyBindingJwt`] to this [`SdJwt`]'s presentation.
   pub fn attach_key_binding_jwt(mut self, kb_jwt: KeyBindingJwt) -> Self {
     self.sd_jwt.key_binding_jwt = Some(kb_jwt);
@@ -306,6 +972,18 @@ impl SdJwtPresentationBuilder {
   }
 }
 
+/// Checks that `hasher` implements the hash function required by `claims._sd_alg`.
+pub(crate) fn check_hasher(claims: &SdJwtClaims, hasher: &dyn Hasher) -> Result<()> {
+  let required_hasher = claims._sd_alg.as_deref().unwrap_or(SHA_ALG_NAME);
+  if required_hasher != hasher.alg_name() {
+    return Err(Error::InvalidHasher(format!(
+      "hasher \"{}\" was provided, but \"{required_hasher} is required\"",
+      hasher.alg_name()
+    )));
+  }
+  Ok(())
+}
+
 fn conceal<'p, 'o, 'd, I>(
   object: &'o Value,
   mut path: Peekable<I>,
@@ -336,8 +1014,13 @@ where
     // We reached the parent of the value we want to conceal.
     // Make sure its concealable by finding its disclosure.
     Value::Object(object) => {
-      let digest = find_disclosure(object, element_key, disclosures)
-        .ok_or_else(|| Error::InvalidPath("the referenced element doesn't exist or is not concealable".to_string()))?;
+      let digest = find_disclosure(object, element_key, disclosures).ok_or_else(|| {
+        if object.contains_key(element_key) {
+          Error::DisclosabilityViolation(String::default())
+        } else {
+          Error::InvalidPath("the referenced element doesn't exist or is not concealable".to_string())
+        }
+      })?;
       let disclosure = disclosures.get(digest).unwrap();
       let mut sub_disclosures: Vec<&str> = get_all_sub_disclosures(&disclosure.claim_value, disclosures).collect();
       sub_disclosures.push(digest);
@@ -368,7 +1051,7 @@ where
         .unwrap()
         .as_object()
         .and_then(|entry| find_disclosure(entry, "", disclosures))
-        .ok_or_else(|| Error::InvalidPath("the referenced element doesn't exist or is not concealable".to_string()))?;
+        .ok_or_else(|| Error::DisclosabilityViolation(String::default()))?;
       let disclosure = disclosures.get(digest).unwrap();
       let mut sub_disclosures: Vec<&str> = get_all_sub_disclosures(&disclosure.claim_value, disclosures).collect();
       sub_disclosures.push(digest);
@@ -451,6 +1134,10 @@ where
 
 #[cfg(test)]
 mod test {
+  use std::time::Duration;
+
+  use crate::Error;
+  use crate::FixedClock;
   use crate::SdJwt;
   const SD_JWT: &str = "eyJhbGciOiAiRVMyNTYiLCAidHlwIjogImV4YW1wbGUrc2Qtand0In0.eyJfc2QiOiBbIkM5aW5wNllvUmFFWFI0Mjd6WUpQN1FyazFXSF84YmR3T0FfWVVyVW5HUVUiLCAiS3VldDF5QWEwSElRdlluT1ZkNTloY1ZpTzlVZzZKMmtTZnFZUkJlb3d2RSIsICJNTWxkT0ZGekIyZDB1bWxtcFRJYUdlcmhXZFVfUHBZZkx2S2hoX2ZfOWFZIiwgIlg2WkFZT0lJMnZQTjQwVjd4RXhad1Z3ejd5Um1MTmNWd3Q1REw4Ukx2NGciLCAiWTM0em1JbzBRTExPdGRNcFhHd2pCZ0x2cjE3eUVoaFlUMEZHb2ZSLWFJRSIsICJmeUdwMFdUd3dQdjJKRFFsbjFsU2lhZW9iWnNNV0ExMGJRNTk4OS05RFRzIiwgIm9tbUZBaWNWVDhMR0hDQjB1eXd4N2ZZdW8zTUhZS08xNWN6LVJaRVlNNVEiLCAiczBCS1lzTFd4UVFlVTh0VmxsdE03TUtzSVJUckVJYTFQa0ptcXhCQmY1VSJdLCAiaXNzIjogImh0dHBzOi8vaXNzdWVyLmV4YW1wbGUuY29tIiwgImlhdCI6IDE2ODMwMDAwMDAsICJleHAiOiAxODgzMDAwMDAwLCAiYWRkcmVzcyI6IHsiX3NkIjogWyI2YVVoelloWjdTSjFrVm1hZ1FBTzN1MkVUTjJDQzFhSGhlWnBLbmFGMF9FIiwgIkF6TGxGb2JrSjJ4aWF1cFJFUHlvSnotOS1OU2xkQjZDZ2pyN2ZVeW9IemciLCAiUHp6Y1Z1MHFiTXVCR1NqdWxmZXd6a2VzRDl6dXRPRXhuNUVXTndrclEtayIsICJiMkRrdzBqY0lGOXJHZzhfUEY4WmN2bmNXN3p3Wmo1cnlCV3ZYZnJwemVrIiwgImNQWUpISVo4VnUtZjlDQ3lWdWIyVWZnRWs4anZ2WGV6d0sxcF9KbmVlWFEiLCAiZ2xUM2hyU1U3ZlNXZ3dGNVVEWm1Xd0JUdzMyZ25VbGRJaGk4aEdWQ2FWNCIsICJydkpkNmlxNlQ1ZWptc0JNb0d3dU5YaDlxQUFGQVRBY2k0MG9pZEVlVnNBIiwgInVOSG9XWWhYc1poVkpDTkUyRHF5LXpxdDd0NjlnSkt5NVFhRnY3R3JNWDQiXX0sICJfc2RfYWxnIjogInNoYS0yNTYifQ.gR6rSL7urX79CNEvTQnP1MH5xthG11ucIV44SqKFZ4Pvlu_u16RfvXQd4k4CAIBZNKn2aTI18TfvFwV97gJFoA~WyJHMDJOU3JRZmpGWFE3SW8wOXN5YWpBIiwgInJlZ2lvbiIsICJcdTZlMmZcdTUzM2EiXQ~WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgImNvdW50cnkiLCAiSlAiXQ~";
 
@@ -466,4 +1153,34 @@ mod test {
     let sd_jwt = SdJwt::parse(SD_JWT).unwrap();
     assert_eq!(&sd_jwt.to_string(), SD_JWT);
   }
+
+  #[test]
+  fn registered_claims() {
+    let sd_jwt = SdJwt::parse(SD_JWT).unwrap();
+    let claims = sd_jwt.claims();
+    assert_eq!(claims.iss(), Some("https://issuer.example.com"));
+    assert_eq!(claims.iat(), Some(1683000000));
+    assert_eq!(claims.exp(), Some(1883000000));
+    assert_eq!(claims.nbf(), None);
+    assert!(claims.aud().is_empty());
+  }
+
+  #[test]
+  fn time_validation() {
+    let sd_jwt = SdJwt::parse(SD_JWT).unwrap();
+    let claims = sd_jwt.claims();
+    let leeway = Duration::from_secs(60);
+
+    assert!(claims.validate_time(&FixedClock::new(1783000000), leeway).is_ok());
+    assert_eq!(
+      claims.validate_time(&FixedClock::new(1883000060), leeway),
+      Err(Error::ExpiredJwt(1883000000))
+    );
+    assert!(claims.validate_time(&FixedClock::new(1883000059), leeway).is_ok());
+    assert_eq!(
+      claims.validate_time(&FixedClock::new(1682999900), leeway),
+      Err(Error::JwtNotYetValid(1683000000))
+    );
+    assert!(claims.validate_time(&FixedClock::new(1682999940), leeway).is_ok());
+  }
 }
@@ -0,0 +1,454 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Display;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ConcealmentPlan;
+use crate::DecoyPolicy;
+use crate::Error;
+use crate::Hasher;
+use crate::JsonObject;
+use crate::JwsAlgorithm;
+use crate::JwsHeader;
+use crate::JwsSigner;
+use crate::JwsSignerSync;
+use crate::JwsVerifier;
+use crate::KeyBindingJwtValidation;
+use crate::RequiredKeyBinding;
+use crate::ResolvedTypeMetadata;
+use crate::Result;
+use crate::SaltGenerator;
+use crate::SdJwt;
+use crate::SdJwtBuilder;
+#[cfg(feature = "sha")]
+use crate::Sha256Hasher;
+#[cfg(feature = "status-list")]
+use crate::StatusListStorage;
+use crate::TimestampObfuscation;
+use crate::DEFAULT_NON_CONCEALABLE_PATHS;
+
+/// Media type of an [SD-JWT VC](https://datatracker.ietf.org/doc/draft-ietf-oauth-sd-jwt-vc/), used as `typ` header.
+pub const SD_JWT_VC_TYP: &str = "dc+sd-jwt";
+/// Media type formerly used by SD-JWT VCs, still accepted when parsing.
+pub const LEGACY_SD_JWT_VC_TYP: &str = "vc+sd-jwt";
+
+/// JSON pointers to the top-level claims that must not be selectively disclosed in an SD-JWT VC.
+const NON_CONCEALABLE_PATHS: [&str; 7] = ["/iss", "/nbf", "/exp", "/cnf", "/vct", "/vct#integrity", "/status"];
+
+/// The `status` claim of an SD-JWT VC, referencing the status mechanisms the credential's status can be obtained
+/// with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Status {
+  /// Reference to an entry of a [Token Status List](https://datatracker.ietf.org/doc/draft-ietf-oauth-status-list/).
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub status_list: Option<StatusListReference>,
+  /// Any other status mechanism.
+  #[serde(flatten)]
+  pub other: JsonObject,
+}
+
+impl Status {
+  /// Creates a [`Status`] referencing the entry at index `idx` of the status list published at `uri`.
+  pub fn status_list(idx: usize, uri: impl Into<String>) -> Self {
+    Self {
+      status_list: Some(StatusListReference { idx, uri: uri.into() }),
+      other: JsonObject::default(),
+    }
+  }
+}
+
+/// Reference to the entry of a credential in a status list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StatusListReference {
+  /// Index of the credential's entry in the status list.
+  pub idx: usize,
+  /// URI of the status list token.
+  pub uri: String,
+}
+
+/// The claims of an SD-JWT VC specific to this profile, as found in its issuer-signed JWT.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SdJwtVcClaims {
+  /// The credential's issuer.
+  pub iss: String,
+  /// The credential's type.
+  pub vct: String,
+  /// Integrity metadata of the type metadata document `vct` resolves to.
+  #[serde(rename = "vct#integrity", skip_serializing_if = "Option::is_none")]
+  pub vct_integrity: Option<String>,
+  /// The credential's status.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub status: Option<Status>,
+}
+
+/// An SD-JWT VC: an [`SdJwt`] satisfying the requirements of the
+/// [SD-JWT VC](https://datatracker.ietf.org/doc/draft-ietf-oauth-sd-jwt-vc/) profile.
+///
+/// The following rules are enforced:
+/// - the `typ` header is "dc+sd-jwt" - or the legacy "vc+sd-jwt".
+/// - `iss` and `vct` are present and not selectively disclosed, and `vct#integrity` and `status` - if present - are not
+///   selectively disclosed either.
+/// - a `cnf` claim is present whenever a KB-JWT is attached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdJwtVc {
+  sd_jwt: SdJwt,
+  vc_claims: SdJwtVcClaims,
+}
+
+impl SdJwtVc {
+  /// Parses an SD-JWT VC.
+  /// ## Errors
+  /// - [`Error::InvalidSdJwtVc`] is returned if the SD-JWT doesn't satisfy the SD-JWT VC rules.
+  /// - Any error returned by [`SdJwt::parse`].
+  pub fn parse(sd_jwt_vc: &str) -> Result<Self> {
+    SdJwt::parse(sd_jwt_vc)?.try_into()
+  }
+
+  /// Returns the typed claims of this SD-JWT VC.
+  pub fn vc_claims(&self) -> &SdJwtVcClaims {
+    &self.vc_claims
+  }
+
+  pub fn vct(&self) -> &str {
+    &self.vc_claims.vct
+  }
+
+  pub fn status(&self) -> Option<&Status> {
+    self.vc_claims.status.as_ref()
+  }
+
+  /// Returns the underlying [`SdJwt`].
+  pub fn into_sd_jwt(self) -> SdJwt {
+    self.sd_jwt
+  }
+
+  /// Verifies the KB-JWT attached to this SD-JWT VC like [`SdJwt::verify_key_binding`] does, additionally requiring
+  /// the issuer to have bound the credential to a key.
+  /// ## Errors
+  /// - [`Error::InvalidSdJwtVc`] is returned if no `cnf` claim is present.
+  /// - Any error returned by [`SdJwt::verify_key_binding`].
+  pub async fn verify_key_binding<V>(
+    &self,
+    verifier: &V,
+    hasher: &dyn Hasher,
+    validation: &KeyBindingJwtValidation,
+  ) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    if self.sd_jwt.required_key_bind().is_none() {
+      return Err(Error::InvalidSdJwtVc(
+        "key binding is required, but the credential has no `cnf` claim".to_string(),
+      ));
+    }
+    self.sd_jwt.verify_key_binding(verifier, hasher, validation).await
+  }
+}
+
+impl TryFrom<SdJwt> for SdJwtVc {
+  type Error = Error;
+  fn try_from(sd_jwt: SdJwt) -> Result<Self> {
+    let typ = sd_jwt.header().typ.as_deref();
+    if !matches!(typ, Some(SD_JWT_VC_TYP | LEGACY_SD_JWT_VC_TYP)) {
+      return Err(Error::InvalidSdJwtVc(format!(
+        "\"typ\" must be \"{SD_JWT_VC_TYP}\", found {typ:?}"
+      )));
+    }
+
+    // Mandatory claims that are missing from the issuer-signed JWT are either absent or concealed.
+    for claim in ["iss", "vct"] {
+      if !sd_jwt.claims().get(claim).is_some_and(Value::is_string) {
+        return Err(Error::InvalidSdJwtVc(format!(
+          "`{claim}` must be a string that is not selectively disclosed"
+        )));
+      }
+    }
+    let vc_claims = serde_json::from_value(Value::Object(sd_jwt.claims().deref().clone()))
+      .map_err(|e| Error::InvalidSdJwtVc(e.to_string()))?;
+
+    if sd_jwt.key_binding_jwt().is_some() && sd_jwt.required_key_bind().is_none() {
+      return Err(Error::InvalidSdJwtVc(
+        "a KB-JWT is attached, but the credential has no `cnf` claim".to_string(),
+      ));
+    }
+
+    Ok(Self { sd_jwt, vc_claims })
+  }
+}
+
+impl From<SdJwtVc> for SdJwt {
+  fn from(sd_jwt_vc: SdJwtVc) -> Self {
+    sd_jwt_vc.sd_jwt
+  }
+}
+
+impl Deref for SdJwtVc {
+  type Target = SdJwt;
+  fn deref(&self) -> &Self::Target {
+    &self.sd_jwt
+  }
+}
+
+impl Display for SdJwtVc {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    Display::fmt(&self.sd_jwt, f)
+  }
+}
+
+impl FromStr for SdJwtVc {
+  type Err = Error;
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+/// Builder structure to create an issuable [`SdJwtVc`], on top of an [`SdJwtBuilder`].
+#[derive(Debug)]
+pub struct SdJwtVcBuilder<H> {
+  builder: SdJwtBuilder<H>,
+  header: JwsHeader,
+}
+
+#[cfg(feature = "sha")]
+impl SdJwtVcBuilder<Sha256Hasher> {
+  /// Creates a new [`SdJwtVcBuilder`] with `sha-256` hash function.
+  ///
+  /// ## Error
+  /// Returns [`Error::DataTypeMismatch`] if `object` is not a valid JSON object.
+  pub fn new<T: Serialize>(object: T) -> Result<Self> {
+    Self::new_with_hasher(object, Sha256Hasher::new())
+  }
+}
+
+impl<H: Hasher> SdJwtVcBuilder<H> {
+  /// Creates a new [`SdJwtVcBuilder`] with custom hash function to create digests.
+  pub fn new_with_hasher<T: Serialize>(object: T, hasher: H) -> Result<Self> {
+    Ok(Self {
+      builder: SdJwtBuilder::new_with_hasher(object, hasher)?.non_concealable_paths(
+        DEFAULT_NON_CONCEALABLE_PATHS
+          .into_iter()
+          .chain(NON_CONCEALABLE_PATHS)
+          .unique(),
+      ),
+      header: JwsHeader::default(),
+    })
+  }
+
+  /// Sets the `iss` claim.
+  pub fn iss(self, iss: impl Into<String>) -> Result<Self> {
+    self.insert_claim("iss", iss.into())
+  }
+
+  /// Sets the `vct` claim.
+  pub fn vct(self, vct: impl Into<String>) -> Result<Self> {
+    self.insert_claim("vct", vct.into())
+  }
+
+  /// Sets the `vct#integrity` claim.
+  pub fn vct_integrity(self, integrity: impl Into<String>) -> Result<Self> {
+    self.insert_claim("vct#integrity", integrity.into())
+  }
+
+  /// Sets the `status` claim.
+  pub fn status(self, status: Status) -> Result<Self> {
+    self.insert_claim("status", status)
+  }
+
+  /// Allocates an entry of the status list published at `uri` and references it in the `status` claim, see
+  /// [`SdJwtBuilder::allocate_status`].
+  #[cfg(feature = "status-list")]
+  pub async fn allocate_status<S>(mut self, storage: &S, uri: &str) -> Result<Self>
+  where
+    S: StatusListStorage,
+  {
+    self.builder = self.builder.allocate_status(storage, uri).await?;
+    Ok(self)
+  }
+
+  /// Adds a new claim to the underlying object, see [`SdJwtBuilder::insert_claim`].
+  pub fn insert_claim<V: Serialize>(mut self, key: &str, value: V) -> Result<Self> {
+    self.builder = self.builder.insert_claim(key, value)?;
+    Ok(self)
+  }
+
+  /// Substitutes a value with the digest of its disclosure, see [`SdJwtBuilder::make_concealable`].
+  /// ## Error
+  /// Returns [`Error::NonConcealableClaim`] if `path` points to, or into, a top-level claim that must not be
+  /// selectively disclosed: `iss`, `nbf`, `exp`, `cnf`, `vct`, `vct#integrity` or `status`.
+  pub fn make_concealable(mut self, path: &str) -> Result<Self> {
+    self.builder = self.builder.make_concealable(path)?;
+    Ok(self)
+  }
+
+  /// Substitutes a value with the digest of its disclosure using the given `salt`, see
+  /// [`SdJwtBuilder::make_concealable_with_salt`].
+  /// ## Error
+  /// See [`SdJwtVcBuilder::make_concealable`].
+  pub fn make_concealable_with_salt(mut self, path: &str, salt: impl Into<String>) -> Result<Self> {
+    self.builder = self.builder.make_concealable_with_salt(path, salt)?;
+    Ok(self)
+  }
+
+  /// Makes concealable every value for which `predicate` returns `true`, see
+  /// [`SdJwtBuilder::make_concealable_where`].
+  pub fn make_concealable_where<F>(mut self, predicate: F) -> Self
+  where
+    F: Fn(&str, &Value) -> bool,
+  {
+    self.builder = self.builder.make_concealable_where(predicate);
+    self
+  }
+
+  /// Makes the value at `path` concealable, together with every value it contains, see
+  /// [`SdJwtBuilder::make_concealable_recursive`].
+  /// ## Error
+  /// See [`SdJwtVcBuilder::make_concealable`].
+  pub fn make_concealable_recursive(mut self, path: &str) -> Result<Self> {
+    self.builder = self.builder.make_concealable_recursive(path)?;
+    Ok(self)
+  }
+
+  /// Makes concealable the claims listed by `plan` and adds its decoys, see [`SdJwtBuilder::apply_plan`].
+  /// ## Error
+  /// See [`SdJwtVcBuilder::make_concealable`].
+  pub fn apply_plan(mut self, plan: &ConcealmentPlan) -> Result<Self> {
+    self.builder = self.builder.apply_plan(plan)?;
+    Ok(self)
+  }
+
+  /// Sets further JSON pointers to values that must not be made concealable, see
+  /// [`SdJwtBuilder::non_concealable_paths`].
+  /// ## Notes
+  /// The claims SD-JWT VC requires to be always disclosed stay non-concealable.
+  pub fn non_concealable_paths<I, P>(mut self, paths: I) -> Self
+  where
+    I: IntoIterator<Item = P>,
+    P: Into<String>,
+  {
+    let paths = DEFAULT_NON_CONCEALABLE_PATHS
+      .into_iter()
+      .chain(NON_CONCEALABLE_PATHS)
+      .map(ToString::to_string)
+      .chain(paths.into_iter().map(Into::into))
+      .unique()
+      .collect_vec();
+    self.builder = self.builder.non_concealable_paths(paths);
+    self
+  }
+
+  /// Sets the source of the random data used for salts and decoys, see [`SdJwtBuilder::salt_generator`].
+  pub fn salt_generator<G: SaltGenerator + 'static>(mut self, salt_generator: G) -> Self {
+    self.builder = self.builder.salt_generator(salt_generator);
+    self
+  }
+
+  /// Adds decoy digests, see [`SdJwtBuilder::add_decoys`].
+  pub fn add_decoys(mut self, path: &str, number_of_decoys: usize) -> Result<Self> {
+    self.builder = self.builder.add_decoys(path, number_of_decoys)?;
+    Ok(self)
+  }
+
+  /// Sets the policy used to add decoy digests when finishing, see [`SdJwtBuilder::decoy_policy`].
+  pub fn decoy_policy(mut self, policy: DecoyPolicy) -> Self {
+    self.builder = self.builder.decoy_policy(policy);
+    self
+  }
+
+  /// Shuffles the disclosures of the SD-JWT VC when finishing, see [`SdJwtBuilder::shuffle_disclosures`].
+  pub fn shuffle_disclosures(mut self) -> Self {
+    self.builder = self.builder.shuffle_disclosures();
+    self
+  }
+
+  /// Sets the JWT header.
+  /// ## Notes
+  /// `typ` defaults to "dc+sd-jwt", and `alg` is always replaced with the value passed to [`SdJwtVcBuilder::finish`].
+  pub fn header(mut self, header: JwsHeader) -> Self {
+    self.header = header;
+    self
+  }
+
+  /// Require a proof of possession of a given key from the holder, see [`SdJwtBuilder::require_key_binding`].
+  pub fn require_key_binding(mut self, key_bind: RequiredKeyBinding) -> Self {
+    self.builder = self.builder.require_key_binding(key_bind);
+    self
+  }
+
+  /// Enforces the selective disclosability rules of the claims described by `metadata`, see
+  /// [`SdJwtBuilder::type_metadata`].
+  pub fn type_metadata(mut self, metadata: &ResolvedTypeMetadata) -> Self {
+    self.builder = self.builder.type_metadata(metadata);
+    self
+  }
+
+  /// Blurs the `iat`, `nbf` and `exp` claims when finishing, see [`SdJwtBuilder::obfuscate_timestamps`].
+  pub fn obfuscate_timestamps(mut self, obfuscation: TimestampObfuscation) -> Self {
+    self.builder = self.builder.obfuscate_timestamps(obfuscation);
+    self
+  }
+
+  /// Creates an SD-JWT VC with the provided data.
+  /// ## Errors
+  /// - [`Error::InvalidSdJwtVc`] is returned if the header's `typ` isn't "dc+sd-jwt", or if `iss` or `vct` are missing.
+  /// - Any error returned by [`SdJwtBuilder::finish`].
+  pub async fn finish<S>(self, signer: &S, alg: JwsAlgorithm) -> Result<SdJwtVc>
+  where
+    S: JwsSigner,
+  {
+    self.into_builder()?.finish(signer, alg).await?.try_into()
+  }
+
+  /// Creates an SD-JWT VC with the provided data, signed by a blocking `signer`.
+  /// ## Errors
+  /// See [`SdJwtVcBuilder::finish`].
+  pub fn finish_sync<S>(self, signer: &S, alg: JwsAlgorithm) -> Result<SdJwtVc>
+  where
+    S: JwsSignerSync,
+  {
+    self.into_builder()?.finish_sync(signer, alg)?.try_into()
+  }
+
+  /// Creates one unlinkable SD-JWT VC for each key of `holder_keys`, see [`SdJwtBuilder::finish_batch`].
+  /// ## Errors
+  /// See [`SdJwtVcBuilder::finish`] and [`SdJwtBuilder::finish_batch`].
+  pub async fn finish_batch<S>(
+    self,
+    signer: &S,
+    alg: JwsAlgorithm,
+    holder_keys: impl IntoIterator<Item = RequiredKeyBinding>,
+  ) -> Result<Vec<SdJwtVc>>
+  where
+    S: JwsSigner,
+  {
+    self
+      .into_builder()?
+      .finish_batch(signer, alg, holder_keys)
+      .await?
+      .into_iter()
+      .map(SdJwtVc::try_from)
+      .collect()
+  }
+
+  /// Checks the SD-JWT VC profile rules and returns the underlying [`SdJwtBuilder`], with its header set.
+  fn into_builder(self) -> Result<SdJwtBuilder<H>> {
+    let SdJwtVcBuilder { builder, mut header } = self;
+    let typ = header.typ.get_or_insert_with(|| SD_JWT_VC_TYP.to_string());
+    if typ != SD_JWT_VC_TYP {
+      return Err(Error::InvalidSdJwtVc(format!("\"typ\" must be \"{SD_JWT_VC_TYP}\"")));
+    }
+    for claim in ["iss", "vct"] {
+      if !builder.claims().get(claim).is_some_and(Value::is_string) {
+        return Err(Error::InvalidSdJwtVc(format!("`{claim}` must be set to a string")));
+      }
+    }
+
+    Ok(builder.header(header))
+  }
+}
@@ -17,3 +17,45 @@ pub trait JwsSigner {
   /// Creates a JWS. The algorithm used for signed must be read from `header.alg` property.
   async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error>;
 }
+
+/// Blocking counterpart of [`JwsSigner`], for signers that don't need an async runtime, e.g. signing with a local key.
+///
+/// Every [`JwsSignerSync`] is a [`JwsSigner`] as well, so that it can be used with both the blocking `finish_sync`
+/// and the async `finish` methods of the builders.
+pub trait JwsSignerSync {
+  type Error: Display;
+  /// Creates a JWS. The algorithm used for signed must be read from `header.alg` property.
+  fn sign_sync(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T> JwsSigner for T
+where
+  T: JwsSignerSync + Sync,
+{
+  type Error = T::Error;
+  async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    self.sign_sync(header, payload)
+  }
+}
+
+/// Object-safe counterpart of [`JwsSigner`], implemented by every [`JwsSigner`], so that signers of different types
+/// can be used together, e.g. by [`SdJwtBuilder::finish_multi_signed`](crate::SdJwtBuilder::finish_multi_signed).
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait DynJwsSigner: Sync {
+  /// Creates a JWS like [`JwsSigner::sign`] does, with the error turned into its message.
+  async fn sign_dyn(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, String>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T> DynJwsSigner for T
+where
+  T: JwsSigner + Sync,
+{
+  async fn sign_dyn(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, String> {
+    self.sign(header, payload).await.map_err(|e| e.to_string())
+  }
+}
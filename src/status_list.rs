@@ -0,0 +1,702 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::io::Read;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use multibase::Base;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::builder::sign;
+use crate::jwt::Jwt;
+use crate::Clock;
+use crate::Error;
+use crate::JsonObject;
+use crate::JwsAlgorithm;
+use crate::JwsHeader;
+use crate::JwsSigner;
+use crate::JwsVerifier;
+use crate::Result;
+use crate::SdJwt;
+use crate::StatusListReference;
+use crate::SystemClock;
+
+/// Media type of a status list token, used as `typ` header.
+pub const STATUS_LIST_JWT_TYP: &str = "statuslist+jwt";
+
+/// Maximum size, in bytes, of a decompressed status list: 16 MiB, i.e. over 134 million 1-bit entries.
+pub const MAX_STATUS_LIST_SIZE: usize = 16 * 1024 * 1024;
+
+/// The status of a credential, as recorded in a [`StatusList`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusType {
+  /// The credential is valid.
+  Valid,
+  /// The credential is revoked.
+  Invalid,
+  /// The credential is temporarily invalid.
+  Suspended,
+  /// An application specific status.
+  Other(u8),
+}
+
+impl From<u8> for StatusType {
+  fn from(value: u8) -> Self {
+    match value {
+      0 => Self::Valid,
+      1 => Self::Invalid,
+      2 => Self::Suspended,
+      value => Self::Other(value),
+    }
+  }
+}
+
+impl From<StatusType> for u8 {
+  fn from(status: StatusType) -> Self {
+    match status {
+      StatusType::Valid => 0,
+      StatusType::Invalid => 1,
+      StatusType::Suspended => 2,
+      StatusType::Other(value) => value,
+    }
+  }
+}
+
+/// A [Token Status List](https://datatracker.ietf.org/doc/draft-ietf-oauth-status-list/): the statuses of many
+/// credentials, each one stored in 1, 2, 4 or 8 bits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "EncodedStatusList", into = "EncodedStatusList")]
+pub struct StatusList {
+  bits: u8,
+  len: usize,
+  bytes: Vec<u8>,
+}
+
+/// The JSON representation of a [`StatusList`].
+#[derive(Serialize, Deserialize)]
+struct EncodedStatusList {
+  bits: u8,
+  lst: String,
+}
+
+impl StatusList {
+  /// Creates a new [`StatusList`] of `len` entries of `bits` bits, all set to [`StatusType::Valid`].
+  /// ## Error
+  /// Returns [`Error::InvalidStatusList`] if `bits` isn't 1, 2, 4 or 8.
+  pub fn new(bits: u8, len: usize) -> Result<Self> {
+    check_bits(bits)?;
+    Ok(Self {
+      bits,
+      len,
+      bytes: vec![0; (len * bits as usize).div_ceil(8)],
+    })
+  }
+
+  /// Decodes a [`StatusList`] from its zlib-compressed, base64url-encoded byte array `lst`.
+  /// ## Error
+  /// Returns [`Error::InvalidStatusList`] if `bits` isn't 1, 2, 4 or 8, if `lst` cannot be decoded, or if it
+  /// decompresses to more than [`MAX_STATUS_LIST_SIZE`] bytes.
+  pub fn from_lst(bits: u8, lst: &str) -> Result<Self> {
+    check_bits(bits)?;
+    let compressed = Base::Base64Url
+      .decode(lst)
+      .map_err(|_| Error::InvalidStatusList("`lst` is not base64url encoded".to_string()))?;
+    let mut bytes = vec![];
+    ZlibDecoder::new(compressed.as_slice())
+      .take(MAX_STATUS_LIST_SIZE as u64 + 1)
+      .read_to_end(&mut bytes)
+      .map_err(|e| Error::InvalidStatusList(format!("`lst` is not zlib compressed: {e}")))?;
+    if bytes.len() > MAX_STATUS_LIST_SIZE {
+      return Err(Error::InvalidStatusList(format!(
+        "`lst` decompresses to more than {MAX_STATUS_LIST_SIZE} bytes"
+      )));
+    }
+
+    Ok(Self {
+      bits,
+      len: bytes.len() * 8 / bits as usize,
+      bytes,
+    })
+  }
+
+  /// Returns the zlib-compressed, base64url-encoded byte array of this [`StatusList`].
+  pub fn to_lst(&self) -> String {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::best());
+    encoder.write_all(&self.bytes).expect("writing to a vector cannot fail");
+    let compressed = encoder.finish().expect("writing to a vector cannot fail");
+
+    Base::Base64Url.encode(compressed)
+  }
+
+  /// Returns the number of bits each entry is stored in.
+  pub fn bits(&self) -> u8 {
+    self.bits
+  }
+
+  /// Returns the number of entries of this [`StatusList`].
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns whether this [`StatusList`] has no entries.
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the status of the entry at index `idx`, if any.
+  pub fn get(&self, idx: usize) -> Option<StatusType> {
+    if idx >= self.len {
+      return None;
+    }
+    let (byte, shift) = self.position(idx);
+
+    Some(StatusType::from((self.bytes[byte] >> shift) & self.mask()))
+  }
+
+  /// Sets the status of the entry at index `idx`.
+  /// ## Error
+  /// Returns [`Error::InvalidStatusList`] if `idx` is out of bounds or if `status` cannot be stored in
+  /// [`StatusList::bits`] bits.
+  pub fn set(&mut self, idx: usize, status: StatusType) -> Result<()> {
+    if idx >= self.len {
+      return Err(Error::InvalidStatusList(format!(
+        "index {idx} is out of bounds for a list of {} entries",
+        self.len
+      )));
+    }
+    let value = u8::from(status);
+    if value > self.mask() {
+      return Err(Error::InvalidStatusList(format!(
+        "status {value} cannot be stored in {} bits",
+        self.bits
+      )));
+    }
+    let (byte, shift) = self.position(idx);
+    self.bytes[byte] = (self.bytes[byte] & !(self.mask() << shift)) | (value << shift);
+
+    Ok(())
+  }
+
+  fn mask(&self) -> u8 {
+    (((1u16) << self.bits) - 1) as u8
+  }
+
+  /// Returns the byte storing entry `idx`, and the offset of the entry in it - starting from the least significant
+  /// bit.
+  fn position(&self, idx: usize) -> (usize, usize) {
+    let bit = idx * self.bits as usize;
+    (bit / 8, bit % 8)
+  }
+}
+
+impl TryFrom<EncodedStatusList> for StatusList {
+  type Error = Error;
+  fn try_from(encoded: EncodedStatusList) -> std::result::Result<Self, Self::Error> {
+    Self::from_lst(encoded.bits, &encoded.lst)
+  }
+}
+
+impl From<StatusList> for EncodedStatusList {
+  fn from(status_list: StatusList) -> Self {
+    Self {
+      bits: status_list.bits,
+      lst: status_list.to_lst(),
+    }
+  }
+}
+
+fn check_bits(bits: u8) -> Result<()> {
+  if ![1, 2, 4, 8].contains(&bits) {
+    return Err(Error::InvalidStatusList(format!(
+      "entries must be 1, 2, 4 or 8 bits long, found {bits}"
+    )));
+  }
+  Ok(())
+}
+
+/// A signed status list token, i.e. a JWT publishing a [`StatusList`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusListToken(Jwt<StatusListTokenClaims>);
+
+impl Display for StatusListToken {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", &self.0)
+  }
+}
+
+impl FromStr for StatusListToken {
+  type Err = Error;
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    let jwt = Jwt::<StatusListTokenClaims>::from_str(s)?;
+    let valid_jwt_type = jwt.header.typ.as_deref() == Some(STATUS_LIST_JWT_TYP);
+    if !valid_jwt_type {
+      return Err(Error::DeserializationError(format!(
+        "invalid status list token: typ must be \"{STATUS_LIST_JWT_TYP}\""
+      )));
+    }
+
+    Ok(Self(jwt))
+  }
+}
+
+impl StatusListToken {
+  /// Parses a status list token.
+  pub fn parse(token: &str) -> Result<Self> {
+    token.parse()
+  }
+
+  /// Returns the header of this token.
+  pub fn header(&self) -> &JwsHeader {
+    &self.0.header
+  }
+
+  /// Returns the claims of this token.
+  pub fn claims(&self) -> &StatusListTokenClaims {
+    &self.0.claims
+  }
+
+  /// Returns the status list published by this token.
+  pub fn status_list(&self) -> &StatusList {
+    &self.0.claims.status_list
+  }
+}
+
+/// Claims set of a [`StatusListToken`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusListTokenClaims {
+  /// The URI the status list token is published at.
+  pub sub: String,
+  pub iat: i64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub exp: Option<i64>,
+  /// How long, in seconds, the token may be cached before being fetched again.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ttl: Option<u64>,
+  pub status_list: StatusList,
+  #[serde(flatten)]
+  pub properties: JsonObject,
+}
+
+/// Builder-style struct to ease the creation of a [`StatusListToken`].
+#[derive(Debug, Clone)]
+pub struct StatusListTokenBuilder {
+  header: JwsHeader,
+  payload: JsonObject,
+  status_list: StatusList,
+}
+
+impl StatusListTokenBuilder {
+  /// Creates a new [`StatusListTokenBuilder`] for a token publishing `status_list` at `uri`.
+  pub fn new(uri: impl Into<String>, status_list: StatusList) -> Self {
+    let mut payload = JsonObject::default();
+    payload.insert("sub".to_string(), Value::String(uri.into()));
+    Self {
+      header: JwsHeader::default(),
+      payload,
+      status_list,
+    }
+  }
+
+  /// Sets the JWT's header.
+  /// ## Notes
+  /// `typ` defaults to "statuslist+jwt", and `alg` is always replaced with the value passed to
+  /// [`StatusListTokenBuilder::finish`].
+  pub fn header(mut self, header: JwsHeader) -> Self {
+    self.header = header;
+    self
+  }
+
+  /// Sets the [iat](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.6) property, which defaults to the
+  /// current time.
+  pub fn iat(mut self, iat: i64) -> Self {
+    self.payload.insert("iat".to_string(), iat.into());
+    self
+  }
+
+  /// Sets the [exp](https://www.rfc-editor.org/rfc/rfc7519.html#section-4.1.4) property.
+  pub fn exp(mut self, exp: i64) -> Self {
+    self.payload.insert("exp".to_string(), exp.into());
+    self
+  }
+
+  /// Sets the `ttl` property: how long, in seconds, the token may be cached.
+  pub fn ttl(mut self, ttl: u64) -> Self {
+    self.payload.insert("ttl".to_string(), ttl.into());
+    self
+  }
+
+  /// Inserts a given property with key `name` and value `value` in the payload.
+  pub fn insert_property(mut self, name: &str, value: Value) -> Self {
+    self.payload.insert(name.to_string(), value);
+    self
+  }
+
+  /// Builds a [`StatusListToken`] signed by `signer`.
+  pub async fn finish<S>(self, signer: &S, alg: JwsAlgorithm) -> Result<StatusListToken>
+  where
+    S: JwsSigner,
+  {
+    let StatusListTokenBuilder {
+      mut header,
+      mut payload,
+      status_list,
+    } = self;
+    payload.entry("iat").or_insert_with(|| SystemClock.now().into());
+    payload.insert(
+      "status_list".to_string(),
+      serde_json::to_value(status_list).map_err(|e| Error::DeserializationError(e.to_string()))?,
+    );
+    header.alg = Some(alg);
+    header.typ.get_or_insert_with(|| STATUS_LIST_JWT_TYP.to_owned());
+
+    let claims = serde_json::from_value::<StatusListTokenClaims>(payload.clone().into())
+      .map_err(|e| Error::DeserializationError(format!("invalid status list token claims: {e}")))?;
+    let jws = sign(signer, &header, &payload).await?;
+
+    Ok(StatusListToken(Jwt { header, claims, jws }))
+  }
+}
+
+/// Persistence of the status lists managed by an issuer.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait StatusListStorage {
+  type Error: Display;
+  /// Allocates an unused entry of the status list published at `uri`, returning its index.
+  async fn allocate_index(&self, uri: &str) -> std::result::Result<usize, Self::Error>;
+  /// Sets the status of the entry at index `idx` of the status list published at `uri`.
+  async fn set_status(&self, uri: &str, idx: usize, status: StatusType) -> std::result::Result<(), Self::Error>;
+  /// Returns the status list published at `uri`.
+  async fn status_list(&self, uri: &str) -> std::result::Result<StatusList, Self::Error>;
+}
+
+/// A [`StatusListStorage`] keeping its status lists in memory, allocating their entries in order.
+#[derive(Debug, Default)]
+pub struct InMemoryStatusListStorage {
+  lists: Mutex<HashMap<String, (StatusList, usize)>>,
+}
+
+impl InMemoryStatusListStorage {
+  /// Creates a new empty [`InMemoryStatusListStorage`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds `status_list`, published at `uri`, replacing any status list previously published there.
+  pub fn insert(&self, uri: impl Into<String>, status_list: StatusList) {
+    self
+      .lists
+      .lock()
+      .expect("status lists lock is not poisoned")
+      .insert(uri.into(), (status_list, 0));
+  }
+
+  fn with_list<T>(&self, uri: &str, f: impl FnOnce(&mut (StatusList, usize)) -> Result<T>) -> Result<T> {
+    let mut lists = self.lists.lock().expect("status lists lock is not poisoned");
+    let list = lists
+      .get_mut(uri)
+      .ok_or_else(|| Error::StatusListFailure(format!("unknown status list \"{uri}\"")))?;
+    f(list)
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StatusListStorage for InMemoryStatusListStorage {
+  type Error = Error;
+  async fn allocate_index(&self, uri: &str) -> Result<usize> {
+    self.with_list(uri, |(status_list, next_idx)| {
+      if *next_idx >= status_list.len() {
+        return Err(Error::StatusListFailure(format!("status list \"{uri}\" is full")));
+      }
+      *next_idx += 1;
+      Ok(*next_idx - 1)
+    })
+  }
+
+  async fn set_status(&self, uri: &str, idx: usize, status: StatusType) -> Result<()> {
+    self.with_list(uri, |(status_list, _)| status_list.set(idx, status))
+  }
+
+  async fn status_list(&self, uri: &str) -> Result<StatusList> {
+    self.with_list(uri, |(status_list, _)| Ok(status_list.clone()))
+  }
+}
+
+/// Retrieves the status list token published at a given URI.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait StatusListTokenFetcher {
+  type Error: Display;
+  /// Fetches the status list token published at `uri`, in its compact serialization.
+  async fn fetch(&self, uri: &str) -> std::result::Result<String, Self::Error>;
+}
+
+/// In-memory [`StatusListTokenFetcher`] mapping URIs to status list tokens, useful as a local stand-in for remote
+/// status lists.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl StatusListTokenFetcher for HashMap<String, String> {
+  type Error = Error;
+  async fn fetch(&self, uri: &str) -> Result<String> {
+    self
+      .get(uri)
+      .cloned()
+      .ok_or_else(|| Error::StatusListFailure(format!("no status list token is available at \"{uri}\"")))
+  }
+}
+
+/// Object-safe counterpart of [`StatusListTokenFetcher`].
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+trait DynStatusListTokenFetcher: Send + Sync {
+  async fn fetch_token(&self, uri: &str) -> Result<String>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F> DynStatusListTokenFetcher for F
+where
+  F: StatusListTokenFetcher + Send + Sync,
+{
+  async fn fetch_token(&self, uri: &str) -> Result<String> {
+    self
+      .fetch(uri)
+      .await
+      .map_err(|e| Error::StatusListFailure(format!("failed to fetch \"{uri}\": {e}")))
+  }
+}
+
+impl StatusListToken {
+  /// Verifies this token's signature with `verifier`, and checks that it is the token published at `uri` and that
+  /// it hasn't expired.
+  /// ## Errors
+  /// - [`Error::JwsVerificationFailure`] is returned if the signature is invalid.
+  /// - [`Error::StatusListFailure`] is returned if `sub` isn't `uri`.
+  /// - [`Error::ExpiredJwt`] is returned if `exp` is in the past.
+  pub async fn verify<V>(&self, verifier: &V, uri: &str, clock: &dyn Clock) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    self.0.verify(verifier, None).await?;
+    let claims = self.claims();
+    if claims.sub != uri {
+      return Err(Error::StatusListFailure(format!(
+        "the status list token published at \"{uri}\" has `sub` \"{}\"",
+        claims.sub
+      )));
+    }
+    if let Some(exp) = claims.exp {
+      if clock.now() >= exp {
+        return Err(Error::ExpiredJwt(exp));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Obtains the status of credentials from their status lists, retrieving status list tokens with a
+/// [`StatusListTokenFetcher`].
+///
+/// Tokens are cached for as long as their `ttl` and `exp` claims allow, tokens with neither aren't cached.
+/// Clones share the same cache.
+///
+/// ## Notes
+/// The cache only spares fetching tokens: a cached token is verified again with the verifier of each call, so a token
+/// accepted by one verifier isn't trusted by another.
+#[derive(Clone)]
+pub struct StatusListResolver {
+  fetcher: Arc<dyn DynStatusListTokenFetcher>,
+  cache: Arc<RwLock<HashMap<String, (StatusListToken, i64)>>>,
+  clock: Arc<dyn Clock>,
+}
+
+impl Debug for StatusListResolver {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("StatusListResolver")
+      .field("cache", &self.cache)
+      .field("clock", &self.clock)
+      .finish_non_exhaustive()
+  }
+}
+
+impl StatusListResolver {
+  /// Creates a new [`StatusListResolver`] retrieving status list tokens with `fetcher`.
+  pub fn new<F>(fetcher: F) -> Self
+  where
+    F: StatusListTokenFetcher + Send + Sync + 'static,
+  {
+    Self {
+      fetcher: Arc::new(fetcher),
+      cache: Arc::default(),
+      clock: Arc::new(SystemClock),
+    }
+  }
+
+  /// Sets the [`Clock`] the tokens' `exp` and the cache entries are checked against, [`SystemClock`] by default.
+  pub fn clock<C>(mut self, clock: C) -> Self
+  where
+    C: Clock + 'static,
+  {
+    self.clock = Arc::new(clock);
+    self
+  }
+
+  /// Returns the status list token published at `uri`, from the cache when possible, once verified with `verifier`.
+  /// ## Errors
+  /// - [`Error::StatusListFailure`] is returned if the token cannot be fetched.
+  /// - Any error returned by [`StatusListToken::parse`] or [`StatusListToken::verify`].
+  pub async fn status_list_token<V>(&self, uri: &str, verifier: &V) -> Result<StatusListToken>
+  where
+    V: JwsVerifier,
+  {
+    let now = self.clock.now();
+    let cached = self
+      .cache
+      .read()
+      .expect("status list cache lock is not poisoned")
+      .get(uri)
+      .filter(|(_, expires_at)| now < *expires_at)
+      .map(|(token, _)| token.clone());
+    if let Some(token) = cached {
+      token.verify(verifier, uri, self.clock.as_ref()).await?;
+      return Ok(token);
+    }
+
+    let token = StatusListToken::parse(&self.fetcher.fetch_token(uri).await?)?;
+    token.verify(verifier, uri, self.clock.as_ref()).await?;
+
+    let claims = token.claims();
+    let expires_at = [claims.exp, claims.ttl.map(|ttl| now.saturating_add(ttl as i64))]
+      .into_iter()
+      .flatten()
+      .min();
+    let mut cache = self.cache.write().expect("status list cache lock is not poisoned");
+    match expires_at {
+      Some(expires_at) => cache.insert(uri.to_owned(), (token.clone(), expires_at)),
+      None => cache.remove(uri),
+    };
+
+    Ok(token)
+  }
+
+  /// Returns the status recorded in the entry referenced by `reference`.
+  /// ## Errors
+  /// - [`Error::StatusListFailure`] is returned if the referenced entry doesn't exist.
+  /// - Any error returned by [`StatusListResolver::status_list_token`].
+  pub async fn status<V>(&self, reference: &StatusListReference, verifier: &V) -> Result<StatusType>
+  where
+    V: JwsVerifier,
+  {
+    let token = self.status_list_token(&reference.uri, verifier).await?;
+    token.status_list().get(reference.idx).ok_or_else(|| {
+      Error::StatusListFailure(format!(
+        "index {} is out of bounds for the status list \"{}\"",
+        reference.idx, reference.uri
+      ))
+    })
+  }
+}
+
+impl SdJwt {
+  /// Checks the status of this SD-JWT in the status list referenced by its `status.status_list` claim.
+  ///
+  /// The status list token is retrieved by `resolver` and its signature verified with `verifier`.
+  /// ## Notes
+  /// If no status list is referenced, this method succeeds.
+  /// ## Errors
+  /// - [`Error::CredentialRevoked`] is returned if the credential's status is [`StatusType::Invalid`].
+  /// - [`Error::CredentialSuspended`] is returned if the credential's status is [`StatusType::Suspended`].
+  /// - [`Error::DeserializationError`] is returned if `status.status_list` is invalid.
+  /// - Any error returned by [`StatusListResolver::status`].
+  pub async fn verify_status<V>(&self, resolver: &StatusListResolver, verifier: &V) -> Result<()>
+  where
+    V: JwsVerifier,
+  {
+    let Some(reference) = self.status_list_reference()? else {
+      return Ok(());
+    };
+    match resolver.status(&reference, verifier).await? {
+      StatusType::Invalid => Err(Error::CredentialRevoked),
+      StatusType::Suspended => Err(Error::CredentialSuspended),
+      _ => Ok(()),
+    }
+  }
+
+  /// Returns the entry of a status list referenced by the `status.status_list` claim, if any.
+  pub(crate) fn status_list_reference(&self) -> Result<Option<StatusListReference>> {
+    self
+      .claims()
+      .get("status")
+      .and_then(|status| status.get("status_list"))
+      .map(|reference| {
+        serde_json::from_value(reference.clone())
+          .map_err(|e| Error::DeserializationError(format!("invalid `status.status_list` claim: {e}")))
+      })
+      .transpose()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn one_bit_status_list_is_decoded() {
+    // Example from https://datatracker.ietf.org/doc/draft-ietf-oauth-status-list/.
+    let status_list = StatusList::from_lst(1, "eNrbuRgAAhcBXQ").unwrap();
+    let statuses = [1, 0, 0, 1, 1, 1, 0, 1, 1, 1, 0, 0, 0, 1, 0, 1];
+
+    assert_eq!(status_list.len(), 16);
+    for (idx, status) in statuses.into_iter().enumerate() {
+      assert_eq!(status_list.get(idx), Some(StatusType::from(status)));
+    }
+    assert_eq!(status_list.get(16), None);
+  }
+
+  #[test]
+  fn two_bit_status_list_round_trips() {
+    // Example from https://datatracker.ietf.org/doc/draft-ietf-oauth-status-list/.
+    let statuses = [1, 2, 0, 3, 0, 1, 0, 1, 1, 2, 3, 3];
+    let mut status_list = StatusList::new(2, statuses.len()).unwrap();
+    for (idx, status) in statuses.into_iter().enumerate() {
+      status_list.set(idx, StatusType::from(status)).unwrap();
+    }
+
+    assert_eq!(status_list.bytes, [0xc9, 0x44, 0xf9]);
+    assert_eq!(StatusList::from_lst(2, "eNo76fITAAPfAgc").unwrap(), status_list);
+    let decoded = StatusList::from_lst(2, &status_list.to_lst()).unwrap();
+    assert_eq!(decoded, status_list);
+  }
+
+  #[test]
+  fn invalid_entries_are_rejected() {
+    assert!(StatusList::new(3, 8).is_err());
+    let mut status_list = StatusList::new(1, 8).unwrap();
+    assert!(status_list.set(8, StatusType::Invalid).is_err());
+    assert!(status_list.set(0, StatusType::Suspended).is_err());
+    assert!(StatusList::from_lst(1, "not zlib").is_err());
+  }
+
+  #[test]
+  fn oversized_status_lists_are_rejected() {
+    let status_list = StatusList::new(8, MAX_STATUS_LIST_SIZE + 1).unwrap();
+    let error = StatusList::from_lst(8, &status_list.to_lst()).unwrap_err();
+
+    assert!(matches!(error, Error::InvalidStatusList(_)));
+  }
+}
@@ -0,0 +1,462 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::Path;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use multibase::Base;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+use crate::Hasher;
+#[cfg(feature = "json-schema")]
+use crate::JsonObject;
+use crate::Result;
+use crate::SdJwtVc;
+
+/// Maximum number of type metadata documents in an `extends` chain.
+const MAX_CHAIN_LENGTH: usize = 16;
+
+/// Retrieves the documents referenced by SD-JWT VC type metadata: the type metadata documents identified by `vct` and
+/// `extends`, and the JSON schemas referenced by `schema_uri`.
+///
+/// Documents are returned as raw bytes, as their integrity is checked against the exact bytes that were retrieved.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait TypeMetadataResolver {
+  type Error: Display;
+  /// Fetches the document identified by `uri`.
+  async fn resolve(&self, uri: &str) -> std::result::Result<Vec<u8>, Self::Error>;
+}
+
+/// A local [`TypeMetadataResolver`] mapping URIs to documents held in memory or stored in files.
+#[derive(Debug, Default, Clone)]
+pub struct TypeMetadataRegistry {
+  documents: HashMap<String, RegistryEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum RegistryEntry {
+  Document(Vec<u8>),
+  File(PathBuf),
+}
+
+impl TypeMetadataRegistry {
+  /// Creates a new empty [`TypeMetadataRegistry`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers `document` under `uri`.
+  pub fn insert(&mut self, uri: impl Into<String>, document: impl Into<Vec<u8>>) {
+    self
+      .documents
+      .insert(uri.into(), RegistryEntry::Document(document.into()));
+  }
+
+  /// Registers the document stored at `path` under `uri`. The file is read whenever the document is resolved.
+  pub fn insert_file(&mut self, uri: impl Into<String>, path: impl AsRef<Path>) {
+    self
+      .documents
+      .insert(uri.into(), RegistryEntry::File(path.as_ref().to_owned()));
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl TypeMetadataResolver for TypeMetadataRegistry {
+  type Error = Error;
+  async fn resolve(&self, uri: &str) -> Result<Vec<u8>> {
+    match self.documents.get(uri) {
+      Some(RegistryEntry::Document(document)) => Ok(document.clone()),
+      Some(RegistryEntry::File(path)) => std::fs::read(path)
+        .map_err(|e| Error::TypeMetadataResolutionFailure(format!("cannot read {}: {e}", path.display()))),
+      None => Err(Error::TypeMetadataResolutionFailure(format!(
+        "no document is registered for \"{uri}\""
+      ))),
+    }
+  }
+}
+
+/// An SD-JWT VC [Type Metadata](https://datatracker.ietf.org/doc/html/draft-ietf-oauth-sd-jwt-vc-08#name-sd-jwt-vc-type-metadata)
+/// document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TypeMetadata {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub vct: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  /// The type this type extends.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub extends: Option<String>,
+  #[serde(rename = "extends#integrity", skip_serializing_if = "Option::is_none")]
+  pub extends_integrity: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub display: Vec<DisplayMetadata>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub claims: Vec<ClaimMetadata>,
+  /// JSON Schema the disclosed credential must be valid against, embedded in the document.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub schema: Option<Value>,
+  /// JSON Schema the disclosed credential must be valid against, referenced by URI.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub schema_uri: Option<String>,
+  #[serde(rename = "schema_uri#integrity", skip_serializing_if = "Option::is_none")]
+  pub schema_uri_integrity: Option<String>,
+}
+
+/// Display information of a type, for a given language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DisplayMetadata {
+  pub lang: String,
+  pub name: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rendering: Option<Value>,
+}
+
+/// Metadata of the claims selected by `path`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClaimMetadata {
+  pub path: Vec<ClaimPathSegment>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub display: Vec<ClaimDisplay>,
+  #[serde(default)]
+  pub sd: ClaimDisclosability,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub svg_id: Option<String>,
+}
+
+impl ClaimMetadata {
+  /// Returns whether `pointer`, a [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901), references a claim
+  /// selected by this metadata's `path`.
+  pub fn selects(&self, pointer: &str) -> bool {
+    let segments = pointer_segments(pointer);
+    segments.len() == self.path.len()
+      && self
+        .path
+        .iter()
+        .zip(&segments)
+        .all(|(path_segment, segment)| path_segment.matches(segment))
+  }
+
+  /// Returns the JSON pointers to every claim of `object` selected by this metadata's `path`.
+  pub(crate) fn select(&self, object: &Value) -> Vec<String> {
+    let mut selected = vec![];
+    select_claims(object, &self.path, String::new(), &mut selected);
+    selected
+  }
+}
+
+/// A segment of a claim path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ClaimPathSegment {
+  /// The object property with the given name.
+  Name(String),
+  /// The array element at the given index.
+  Index(usize),
+  /// All elements of an array, serialized as `null`.
+  AllElements,
+}
+
+impl ClaimPathSegment {
+  fn matches(&self, segment: &str) -> bool {
+    match self {
+      Self::Name(name) => name == segment,
+      Self::Index(index) => segment.parse::<usize>().is_ok_and(|segment| segment == *index),
+      Self::AllElements => segment.parse::<usize>().is_ok(),
+    }
+  }
+}
+
+/// Display information of a claim, for a given language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClaimDisplay {
+  pub lang: String,
+  pub label: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+}
+
+/// Whether a claim can be selectively disclosed.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimDisclosability {
+  /// The claim must be selectively disclosable.
+  Always,
+  /// The issuer decides whether the claim is selectively disclosable.
+  #[default]
+  Allowed,
+  /// The claim must not be selectively disclosable.
+  Never,
+}
+
+/// The type metadata of an SD-JWT VC type, together with the metadata of all the types it extends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTypeMetadata {
+  /// The `extends` chain, starting with the resolved type.
+  chain: Vec<TypeMetadata>,
+  /// The JSON schemas of the types in `chain`, either embedded or resolved from `schema_uri`.
+  schemas: Vec<Value>,
+}
+
+impl ResolvedTypeMetadata {
+  /// Resolves the type metadata of the type `vct` with `resolver`, following its `extends` chain.
+  ///
+  /// The integrity of every retrieved document is checked against its
+  /// [subresource integrity](https://www.w3.org/TR/SRI/) metadata - `integrity` for `vct`'s document, `extends#integrity`
+  /// and `schema_uri#integrity` for the documents it references - when present. Only integrity metadata using the
+  /// hash function of `hasher` is considered.
+  /// ## Errors
+  /// - [`Error::TypeMetadataResolutionFailure`] is returned if a document cannot be retrieved or is invalid, if a
+  ///   document's `vct` doesn't match the type it was retrieved for, or if the `extends` chain contains a cycle.
+  /// - [`Error::IntegrityCheckFailure`] is returned if a document doesn't match its integrity metadata.
+  pub async fn resolve<R>(resolver: &R, vct: &str, integrity: Option<&str>, hasher: &dyn Hasher) -> Result<Self>
+  where
+    R: TypeMetadataResolver,
+  {
+    let mut chain: Vec<TypeMetadata> = vec![];
+    let mut schemas = vec![];
+    let mut visited = HashSet::new();
+    let mut next = Some((vct.to_owned(), integrity.map(ToOwned::to_owned)));
+
+    while let Some((vct, integrity)) = next.take() {
+      if !visited.insert(vct.clone()) || chain.len() >= MAX_CHAIN_LENGTH {
+        return Err(Error::TypeMetadataResolutionFailure(format!(
+          "the `extends` chain of \"{vct}\" is cyclic or too long"
+        )));
+      }
+      let document = resolve_document(resolver, &vct, integrity.as_deref(), hasher).await?;
+      let metadata: TypeMetadata = serde_json::from_slice(&document)
+        .map_err(|e| Error::TypeMetadataResolutionFailure(format!("invalid type metadata of \"{vct}\": {e}")))?;
+      if metadata.vct.as_ref().is_some_and(|document_vct| *document_vct != vct) {
+        return Err(Error::TypeMetadataResolutionFailure(format!(
+          "the type metadata retrieved for \"{vct}\" describes another type"
+        )));
+      }
+
+      match (&metadata.schema, &metadata.schema_uri) {
+        (Some(_), Some(_)) => {
+          return Err(Error::TypeMetadataResolutionFailure(format!(
+            "the type metadata of \"{vct}\" has both `schema` and `schema_uri`"
+          )))
+        }
+        (Some(schema), None) => schemas.push(schema.clone()),
+        (None, Some(schema_uri)) => {
+          let schema = resolve_document(resolver, schema_uri, metadata.schema_uri_integrity.as_deref(), hasher).await?;
+          schemas.push(
+            serde_json::from_slice(&schema)
+              .map_err(|e| Error::TypeMetadataResolutionFailure(format!("invalid schema \"{schema_uri}\": {e}")))?,
+          );
+        }
+        (None, None) => (),
+      }
+
+      next = metadata
+        .extends
+        .clone()
+        .map(|extends| (extends, metadata.extends_integrity.clone()));
+      chain.push(metadata);
+    }
+
+    Ok(Self { chain, schemas })
+  }
+
+  /// Returns the type metadata of the resolved type, followed by the metadata of the types it extends.
+  pub fn chain(&self) -> &[TypeMetadata] {
+    &self.chain
+  }
+
+  /// Returns the JSON schemas a disclosed credential of this type must be valid against.
+  pub fn schemas(&self) -> &[Value] {
+    &self.schemas
+  }
+
+  /// Returns the metadata of every claim described in the `extends` chain, where the metadata of a claim described by
+  /// a type overrides the one of the types it extends.
+  pub fn claims(&self) -> Vec<&ClaimMetadata> {
+    let mut claims: Vec<&ClaimMetadata> = vec![];
+    for claim in self.chain.iter().flat_map(|metadata| &metadata.claims) {
+      if !claims.iter().any(|known| known.path == claim.path) {
+        claims.push(claim);
+      }
+    }
+
+    claims
+  }
+
+  /// Validates `object`, the fully disclosed claims of a credential - see [`crate::SdJwt::into_disclosed_object`] -
+  /// against every JSON schema of the `extends` chain.
+  /// ## Error
+  /// Returns [`Error::SchemaValidationFailure`] if a schema is invalid or `object` isn't valid against it.
+  #[cfg(feature = "json-schema")]
+  pub fn validate(&self, object: &JsonObject) -> Result<()> {
+    let instance = Value::Object(object.clone());
+    for schema in &self.schemas {
+      let schema = jsonschema::JSONSchema::compile(schema)
+        .map_err(|e| Error::SchemaValidationFailure(format!("invalid schema: {e}")))?;
+      let result = schema.validate(&instance).map_err(|mut errors| {
+        let error = errors.next().expect("validation failed with at least one error");
+        Error::SchemaValidationFailure(format!("{error} at \"{}\"", error.instance_path))
+      });
+      result?;
+    }
+
+    Ok(())
+  }
+}
+
+impl SdJwtVc {
+  /// Resolves the type metadata of this SD-JWT VC's `vct`, checking the `vct#integrity` claim if present.
+  /// See [`ResolvedTypeMetadata::resolve`].
+  pub async fn resolve_type_metadata<R>(&self, resolver: &R, hasher: &dyn Hasher) -> Result<ResolvedTypeMetadata>
+  where
+    R: TypeMetadataResolver,
+  {
+    let claims = self.vc_claims();
+    ResolvedTypeMetadata::resolve(resolver, &claims.vct, claims.vct_integrity.as_deref(), hasher).await
+  }
+}
+
+/// Splits a JSON pointer into its unescaped reference tokens.
+pub(crate) fn pointer_segments(pointer: &str) -> Vec<String> {
+  pointer
+    .split('/')
+    .skip(1)
+    .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+    .collect()
+}
+
+fn select_claims(value: &Value, path: &[ClaimPathSegment], pointer: String, selected: &mut Vec<String>) {
+  let Some((segment, path)) = path.split_first() else {
+    selected.push(pointer);
+    return;
+  };
+  match (segment, value) {
+    (ClaimPathSegment::Name(name), Value::Object(object)) => {
+      if let Some(value) = object.get(name) {
+        let name = name.replace('~', "~0").replace('/', "~1");
+        select_claims(value, path, format!("{pointer}/{name}"), selected);
+      }
+    }
+    (ClaimPathSegment::Index(index), Value::Array(array)) => {
+      if let Some(value) = array.get(*index) {
+        select_claims(value, path, format!("{pointer}/{index}"), selected);
+      }
+    }
+    (ClaimPathSegment::AllElements, Value::Array(array)) => {
+      for (index, value) in array.iter().enumerate() {
+        select_claims(value, path, format!("{pointer}/{index}"), selected);
+      }
+    }
+    _ => (),
+  }
+}
+
+async fn resolve_document<R>(resolver: &R, uri: &str, integrity: Option<&str>, hasher: &dyn Hasher) -> Result<Vec<u8>>
+where
+  R: TypeMetadataResolver,
+{
+  let document = resolver
+    .resolve(uri)
+    .await
+    .map_err(|e| Error::TypeMetadataResolutionFailure(format!("cannot resolve \"{uri}\": {e}")))?;
+  if let Some(integrity) = integrity {
+    check_integrity(&document, integrity, hasher)
+      .map_err(|e| Error::IntegrityCheckFailure(format!("\"{uri}\": {e}")))?;
+  }
+
+  Ok(document)
+}
+
+/// Checks `document` against the subresource integrity metadata `integrity`, e.g. "sha256-<base64 digest>".
+///
+/// Only the hashes computed with `hasher`'s hash function are compared, at least one of which must match.
+fn check_integrity(document: &[u8], integrity: &str, hasher: &dyn Hasher) -> std::result::Result<(), String> {
+  // SRI names hash functions without dashes, e.g. "sha256" for "sha-256".
+  let alg = hasher.alg_name().replace('-', "");
+  let expected_digests: Vec<&str> = integrity
+    .split_whitespace()
+    .filter_map(|entry| entry.split_once('-'))
+    .filter(|(entry_alg, _)| *entry_alg == alg)
+    // Options following the digest are ignored.
+    .map(|(_, digest)| digest.split('?').next().unwrap_or_default())
+    .collect();
+  if expected_digests.is_empty() {
+    return Err(format!("no integrity metadata uses {}", hasher.alg_name()));
+  }
+
+  let digest = Base::Base64Pad.encode(hasher.digest(document));
+  if expected_digests.contains(&digest.as_str()) {
+    Ok(())
+  } else {
+    Err("the digest doesn't match the integrity metadata".to_string())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::Sha256Hasher;
+
+  #[test]
+  fn integrity_is_checked_with_the_hasher_algorithm() {
+    // Example from https://www.w3.org/TR/SRI/#the-integrity-attribute.
+    let document = b"alert('Hello, world.');";
+    let hasher = Sha256Hasher::new();
+    let digest = "sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng=";
+
+    assert!(check_integrity(document, digest, &hasher).is_ok());
+    assert!(check_integrity(document, &format!("sha384-abc {digest}?ct=text/javascript"), &hasher).is_ok());
+    assert!(check_integrity(b"alert('Hello!');", digest, &hasher).is_err());
+    assert!(check_integrity(document, "sha384-abc", &hasher).is_err());
+  }
+
+  #[test]
+  fn claim_paths_are_deserialized() {
+    let claim: ClaimMetadata = serde_json::from_value(serde_json::json!({
+      "path": ["nationalities", null, 0],
+      "sd": "always"
+    }))
+    .unwrap();
+
+    assert_eq!(
+      claim.path,
+      vec![
+        ClaimPathSegment::Name("nationalities".to_string()),
+        ClaimPathSegment::AllElements,
+        ClaimPathSegment::Index(0)
+      ]
+    );
+    assert_eq!(claim.sd, ClaimDisclosability::Always);
+  }
+
+  #[test]
+  fn claim_paths_select_json_pointers() {
+    let claim: ClaimMetadata = serde_json::from_value(serde_json::json!({
+      "path": ["addresses", null, "street/name"]
+    }))
+    .unwrap();
+    let object = serde_json::json!({
+      "addresses": [{ "street/name": "Main St" }, { "city": "Berlin" }, { "street/name": "High St" }]
+    });
+
+    assert_eq!(
+      claim.select(&object),
+      vec!["/addresses/0/street~1name", "/addresses/2/street~1name"]
+    );
+    assert!(claim.selects("/addresses/1/street~1name"));
+    assert!(!claim.selects("/addresses/first/street~1name"));
+    assert!(!claim.selects("/addresses/1"));
+  }
+}
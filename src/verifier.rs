@@ -0,0 +1,80 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::Display;
+
+use async_trait::async_trait;
+
+use crate::JsonObject;
+use crate::JwsHeader;
+
+/// JSON Web Signature (JWS) Verifier.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JwsVerifier {
+  type Error: Display;
+  /// Verifies `signature` over `signing_input`, i.e. `<base64url header>.<base64url payload>`.
+  /// The algorithm used for signing must be read from `header.alg`.
+  ///
+  /// `key` is the public key, as a JWK, the signature must be checked against, when the caller
+  /// was able to determine one. If `None` is passed, the verifier is expected to use its own key material.
+  async fn verify(
+    &self,
+    header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error>;
+
+  /// Returns whether this verifier processes the header parameter `name`, so that JWSs listing it in `crit` can be
+  /// accepted. JWSs with critical extensions are rejected by default.
+  fn understands_critical_extension(&self, _name: &str) -> bool {
+    false
+  }
+}
+
+/// Blocking counterpart of [`JwsVerifier`], for verifiers that don't need an async runtime, e.g. verifying with a
+/// local key.
+///
+/// Every [`JwsVerifierSync`] is a [`JwsVerifier`] as well, so that it can be used with both the blocking `_sync` and
+/// the async verification methods, e.g. [`SdJwt::verify_sync`](crate::SdJwt::verify_sync) and
+/// [`SdJwt::verify`](crate::SdJwt::verify).
+pub trait JwsVerifierSync {
+  type Error: Display;
+  /// Verifies `signature` over `signing_input`, see [`JwsVerifier::verify`].
+  fn verify_sync(
+    &self,
+    header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error>;
+
+  /// Returns whether this verifier processes the header parameter `name`, see
+  /// [`JwsVerifier::understands_critical_extension`].
+  fn understands_critical_extension(&self, _name: &str) -> bool {
+    false
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T> JwsVerifier for T
+where
+  T: JwsVerifierSync + Sync,
+{
+  type Error = T::Error;
+  async fn verify(
+    &self,
+    header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    self.verify_sync(header, signing_input, signature, key)
+  }
+
+  fn understands_critical_extension(&self, name: &str) -> bool {
+    JwsVerifierSync::understands_critical_extension(self, name)
+  }
+}
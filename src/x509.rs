@@ -0,0 +1,307 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use multibase::Base;
+use serde_json::Value;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::oid_registry::OID_EC_P256;
+use x509_parser::oid_registry::OID_KEY_TYPE_EC_PUBLIC_KEY;
+use x509_parser::oid_registry::OID_NIST_EC_P384;
+use x509_parser::oid_registry::OID_PKCS1_RSAENCRYPTION;
+use x509_parser::oid_registry::OID_SIG_ED25519;
+use x509_parser::pem::Pem;
+use x509_parser::public_key::PublicKey;
+use x509_parser::time::ASN1Time;
+use x509_parser::x509::SubjectPublicKeyInfo;
+
+use crate::Clock;
+use crate::Error;
+use crate::JsonObject;
+use crate::JwsHeader;
+use crate::KeyResolver;
+use crate::RequiredKeyBinding;
+use crate::Result;
+use crate::SystemClock;
+
+/// A [`KeyResolver`] that validates the X.509 certificate chain found in the `x5c` header parameter of issuer-signed
+/// JWTs, and resolves the issuer's key to the public key of the chain's leaf certificate.
+///
+/// A chain is accepted if:
+/// - every certificate is signed by the next one in the chain, and the last one is either a trust anchor or signed by a
+///   trust anchor.
+/// - every certificate, including the trust anchor, is valid at the current time.
+/// - every issuing certificate is a CA allowed to sign certificates, and the leaf is allowed to create signatures.
+/// - the JWT's `iss` claim matches a URI entry of the leaf's Subject Alternative Name, or its host matches a DNS entry.
+///
+/// Holder keys can only be resolved when embedded as [`RequiredKeyBinding::Jwk`].
+#[derive(Debug, Clone)]
+pub struct X509KeyResolver {
+  trust_anchors: Vec<Vec<u8>>,
+  clock: Arc<dyn Clock>,
+  leeway: Duration,
+}
+
+impl X509KeyResolver {
+  /// Creates a new [`X509KeyResolver`] trusting the given DER-encoded certificates.
+  ///
+  /// ## Error
+  /// Returns [`Error::InvalidX509Chain`] if any of `trust_anchors` is not a valid certificate.
+  pub fn new<I, C>(trust_anchors: I) -> Result<Self>
+  where
+    I: IntoIterator<Item = C>,
+    C: Into<Vec<u8>>,
+  {
+    let trust_anchors: Vec<Vec<u8>> = trust_anchors.into_iter().map(Into::into).collect();
+    for der in &trust_anchors {
+      parse_certificate(der)?;
+    }
+
+    Ok(Self {
+      trust_anchors,
+      clock: Arc::new(SystemClock),
+      leeway: Duration::ZERO,
+    })
+  }
+
+  /// Creates a new [`X509KeyResolver`] trusting all the certificates contained in `pem`.
+  pub fn from_pem(pem: &str) -> Result<Self> {
+    let trust_anchors: Vec<Vec<u8>> = Pem::iter_from_buffer(pem.as_bytes())
+      .map(|pem| {
+        pem
+          .map(|pem| pem.contents)
+          .map_err(|e| Error::InvalidX509Chain(format!("invalid PEM: {e}")))
+      })
+      .collect::<Result<_>>()?;
+    Self::new(trust_anchors)
+  }
+
+  /// Sets the [`Clock`] certificates' validity periods are checked against. Defaults to [`SystemClock`].
+  pub fn clock<C>(mut self, clock: C) -> Self
+  where
+    C: Clock + 'static,
+  {
+    self.clock = Arc::new(clock);
+    self
+  }
+
+  /// Sets the tolerated clock skew when checking certificates' validity periods.
+  pub fn leeway(mut self, leeway: Duration) -> Self {
+    self.leeway = leeway;
+    self
+  }
+
+  /// Validates the certificate chain `x5c` - as found in a JWS header - for an issuer identified by `iss`, returning
+  /// the public key of the leaf certificate as a JWK.
+  ///
+  /// ## Error
+  /// Returns [`Error::InvalidX509Chain`] if the chain is not valid.
+  pub fn validate_chain(&self, x5c: &[String], iss: &str) -> Result<JsonObject> {
+    if x5c.is_empty() {
+      return Err(Error::InvalidX509Chain("\"x5c\" is empty".to_string()));
+    }
+    let ders = x5c
+      .iter()
+      .map(|cert| {
+        Base::Base64Pad
+          .decode(cert)
+          .map_err(|_| Error::InvalidX509Chain("\"x5c\" entries must be base64-encoded".to_string()))
+      })
+      .collect::<Result<Vec<_>>>()?;
+    let chain = ders
+      .iter()
+      .map(|der| parse_certificate(der))
+      .collect::<Result<Vec<_>>>()?;
+    let anchors = self
+      .trust_anchors
+      .iter()
+      .map(|der| parse_certificate(der))
+      .collect::<Result<Vec<_>>>()?;
+
+    for pair in chain.windows(2) {
+      check_issued_by(&pair[0], &pair[1])?;
+    }
+    // Safety: `x5c` is not empty.
+    let (last, last_der) = (chain.last().unwrap(), ders.last().unwrap());
+    let anchor = match self.trust_anchors.iter().position(|anchor| anchor == last_der) {
+      Some(idx) => &anchors[idx],
+      None => {
+        let anchor = anchors
+          .iter()
+          .find(|anchor| anchor.subject().as_raw() == last.issuer().as_raw())
+          .ok_or_else(|| Error::InvalidX509Chain("the chain doesn't lead to a trust anchor".to_string()))?;
+        check_issued_by(last, anchor)?;
+        anchor
+      }
+    };
+
+    let now = self.clock.now();
+    let leeway = self.leeway.as_secs() as i64;
+    for cert in chain.iter().chain(std::iter::once(anchor)) {
+      let not_before = cert.validity().not_before.timestamp();
+      let not_after = cert.validity().not_after.timestamp();
+      if now + leeway < not_before || now - leeway > not_after {
+        return Err(Error::InvalidX509Chain(format!(
+          "certificate \"{}\" is not valid at {}",
+          cert.subject(),
+          ASN1Time::from_timestamp(now)
+            .map(|time| time.to_string())
+            .unwrap_or_default()
+        )));
+      }
+    }
+
+    let leaf = &chain[0];
+    let can_sign = leaf
+      .key_usage()
+      .map_err(|e| Error::InvalidX509Chain(e.to_string()))?
+      .map(|key_usage| key_usage.value.digital_signature())
+      .unwrap_or(true);
+    if !can_sign {
+      return Err(Error::InvalidX509Chain(
+        "the leaf certificate is not allowed to create digital signatures".to_string(),
+      ));
+    }
+    check_subject_alternative_name(leaf, iss)?;
+
+    spki_to_jwk(leaf.public_key())
+  }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl KeyResolver for X509KeyResolver {
+  async fn resolve_issuer_key(&self, header: &JwsHeader, claims: &JsonObject) -> Result<JsonObject> {
+    let x5c = header
+      .x5c
+      .as_deref()
+      .ok_or_else(|| Error::KeyResolutionFailure("the JWT header has no \"x5c\"".to_string()))?;
+    let iss = claims
+      .get("iss")
+      .and_then(Value::as_str)
+      .ok_or_else(|| Error::InvalidX509Chain("an \"iss\" claim is required to validate \"x5c\"".to_string()))?;
+
+    self.validate_chain(x5c, iss)
+  }
+
+  async fn resolve_holder_key(&self, cnf: &RequiredKeyBinding) -> Result<JsonObject> {
+    match cnf {
+      RequiredKeyBinding::Jwk(jwk) => Ok(jwk.clone()),
+      _ => Err(Error::KeyResolutionFailure(
+        "only embedded JWKs are supported as holder keys".to_string(),
+      )),
+    }
+  }
+}
+
+fn parse_certificate(der: &[u8]) -> Result<X509Certificate<'_>> {
+  x509_parser::parse_x509_certificate(der)
+    .map(|(_, cert)| cert)
+    .map_err(|e| Error::InvalidX509Chain(format!("invalid certificate: {e}")))
+}
+
+/// Checks that `cert` was issued, and signed, by the CA certificate `issuer`.
+fn check_issued_by(cert: &X509Certificate<'_>, issuer: &X509Certificate<'_>) -> Result<()> {
+  if cert.issuer().as_raw() != issuer.subject().as_raw() {
+    return Err(Error::InvalidX509Chain(format!(
+      "certificate \"{}\" was not issued by \"{}\"",
+      cert.subject(),
+      issuer.subject()
+    )));
+  }
+  let is_ca = issuer
+    .basic_constraints()
+    .map_err(|e| Error::InvalidX509Chain(e.to_string()))?
+    .is_some_and(|constraints| constraints.value.ca);
+  let can_sign_certificates = issuer
+    .key_usage()
+    .map_err(|e| Error::InvalidX509Chain(e.to_string()))?
+    .map(|key_usage| key_usage.value.key_cert_sign())
+    .unwrap_or(true);
+  if !is_ca || !can_sign_certificates {
+    return Err(Error::InvalidX509Chain(format!(
+      "certificate \"{}\" is not allowed to issue certificates",
+      issuer.subject()
+    )));
+  }
+  cert
+    .verify_signature(Some(issuer.public_key()))
+    .map_err(|e| Error::InvalidX509Chain(format!("invalid signature of \"{}\": {e}", cert.subject())))
+}
+
+fn check_subject_alternative_name(leaf: &X509Certificate<'_>, iss: &str) -> Result<()> {
+  let host = iss
+    .split_once("://")
+    .map(|(_, rest)| rest)
+    .unwrap_or(iss)
+    .split(['/', '?', '#'])
+    .next()
+    .unwrap_or_default();
+  let host = host.rsplit_once('@').map(|(_, host)| host).unwrap_or(host);
+  let host = host.split(':').next().unwrap_or_default();
+
+  let matches = leaf
+    .subject_alternative_name()
+    .map_err(|e| Error::InvalidX509Chain(e.to_string()))?
+    .iter()
+    .flat_map(|san| san.value.general_names.iter())
+    .any(|name| match name {
+      GeneralName::URI(uri) => *uri == iss,
+      GeneralName::DNSName(dns) => dns.eq_ignore_ascii_case(host),
+      _ => false,
+    });
+  if !matches {
+    return Err(Error::InvalidX509Chain(format!(
+      "issuer \"{iss}\" doesn't match any Subject Alternative Name of the leaf certificate"
+    )));
+  }
+  Ok(())
+}
+
+fn spki_to_jwk(spki: &SubjectPublicKeyInfo<'_>) -> Result<JsonObject> {
+  let unsupported = || Error::InvalidX509Chain("unsupported public key type".to_string());
+  let b64 = |bytes: &[u8]| Value::String(Base::Base64Url.encode(bytes));
+
+  let jwk = if spki.algorithm.algorithm == OID_SIG_ED25519 {
+    serde_json::json!({ "kty": "OKP", "crv": "Ed25519", "x": b64(&spki.subject_public_key.data) })
+  } else if spki.algorithm.algorithm == OID_KEY_TYPE_EC_PUBLIC_KEY {
+    let curve = spki
+      .algorithm
+      .parameters
+      .as_ref()
+      .and_then(|params| params.as_oid().ok())
+      .ok_or_else(unsupported)?;
+    let (crv, len) = if curve == OID_EC_P256 {
+      ("P-256", 32)
+    } else if curve == OID_NIST_EC_P384 {
+      ("P-384", 48)
+    } else {
+      return Err(unsupported());
+    };
+    let Ok(PublicKey::EC(point)) = spki.parsed() else {
+      return Err(unsupported());
+    };
+    // Only uncompressed points are supported.
+    match point.data() {
+      [0x04, coordinates @ ..] if coordinates.len() == 2 * len => {
+        let (x, y) = coordinates.split_at(len);
+        serde_json::json!({ "kty": "EC", "crv": crv, "x": b64(x), "y": b64(y) })
+      }
+      _ => return Err(unsupported()),
+    }
+  } else if spki.algorithm.algorithm == OID_PKCS1_RSAENCRYPTION {
+    let Ok(PublicKey::RSA(rsa)) = spki.parsed() else {
+      return Err(unsupported());
+    };
+    let trim = |bytes: &'_ [u8]| -> Vec<u8> { bytes.iter().copied().skip_while(|byte| *byte == 0).collect() };
+    serde_json::json!({ "kty": "RSA", "n": b64(&trim(rsa.modulus)), "e": b64(&trim(rsa.exponent)) })
+  } else {
+    return Err(unsupported());
+  };
+
+  Ok(jwk.as_object().cloned().expect("JWK is a JSON object"))
+}
@@ -2,21 +2,50 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use async_trait::async_trait;
+use itertools::Itertools;
 use josekit::jws::alg::hmac::HmacJwsSigner;
-use josekit::jws::JwsHeader;
+use josekit::jws::alg::hmac::HmacJwsVerifier;
 use josekit::jws::HS256;
 use josekit::jwt;
 use josekit::jwt::JwtPayload;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::FixedClock;
 use sd_jwt_payload::Hasher;
 use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwkSet;
+use sd_jwt_payload::JwkSetKeyResolver;
+use sd_jwt_payload::JwsAlgorithm;
+use sd_jwt_payload::JwsHeader;
+use sd_jwt_payload::JwsJsonSerialization;
 use sd_jwt_payload::JwsSigner;
+use sd_jwt_payload::JwsSignerSync;
+use sd_jwt_payload::JwsVerifier;
+use sd_jwt_payload::JwsVerifierSync;
 use sd_jwt_payload::KeyBindingJwt;
+use sd_jwt_payload::KeyBindingJwtValidation;
+use sd_jwt_payload::KeyResolvingVerifier;
+use sd_jwt_payload::RequiredKeyBinding;
 use sd_jwt_payload::Sha256Hasher;
 use serde_json::json;
 use serde_json::Value;
 
+use sd_jwt_payload::CheckKind;
+use sd_jwt_payload::CheckStatus;
+use sd_jwt_payload::ClaimDisclosability;
+use sd_jwt_payload::ConcealmentPlan;
+use sd_jwt_payload::ResolvedTypeMetadata;
 use sd_jwt_payload::SdJwt;
 use sd_jwt_payload::SdJwtBuilder;
+use sd_jwt_payload::SdJwtVc;
+use sd_jwt_payload::SdJwtVcBuilder;
+use sd_jwt_payload::SeededSaltGenerator;
+use sd_jwt_payload::Status;
+use sd_jwt_payload::StatusListReference;
+use sd_jwt_payload::TimestampObfuscation;
+use sd_jwt_payload::TypeMetadataRegistry;
+use sd_jwt_payload::VerificationOptions;
+use sd_jwt_payload::VerificationPolicy;
+use sd_jwt_payload::SD_JWT_VC_TYP;
 
 const HMAC_SECRET: &[u8; 32] = b"0123456789ABCDEF0123456789ABCDEF";
 
@@ -26,13 +55,61 @@ struct HmacSignerAdapter(HmacJwsSigner);
 impl JwsSigner for HmacSignerAdapter {
   type Error = josekit::JoseError;
   async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
-    let header = JwsHeader::from_map(header.clone())?;
+    let header = josekit::jws::JwsHeader::from_map(header.clone())?;
     let payload = JwtPayload::from_map(payload.clone())?;
 
     jwt::encode_with_signer(&payload, &header, &self.0).map(String::into_bytes)
   }
 }
 
+/// A blocking signer, usable through both the blocking and the async API.
+struct HmacSyncSigner(HmacJwsSigner);
+
+impl JwsSignerSync for HmacSyncSigner {
+  type Error = josekit::JoseError;
+  fn sign_sync(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    let header = josekit::jws::JwsHeader::from_map(header.clone())?;
+    let payload = JwtPayload::from_map(payload.clone())?;
+
+    jwt::encode_with_signer(&payload, &header, &self.0).map(String::into_bytes)
+  }
+}
+
+struct HmacVerifierAdapter(HmacJwsVerifier);
+
+#[async_trait]
+impl JwsVerifier for HmacVerifierAdapter {
+  type Error = josekit::JoseError;
+  async fn verify(
+    &self,
+    _header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    _key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    josekit::jws::JwsVerifier::verify(&self.0, signing_input, signature)
+  }
+}
+
+struct HmacSyncVerifier(HmacJwsVerifier);
+
+impl JwsVerifierSync for HmacSyncVerifier {
+  type Error = josekit::JoseError;
+  fn verify_sync(
+    &self,
+    _header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    _key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    josekit::jws::JwsVerifier::verify(&self.0, signing_input, signature)
+  }
+}
+
+fn hmac_verifier() -> HmacVerifierAdapter {
+  HmacVerifierAdapter(HS256.verifier_from_bytes(HMAC_SECRET).unwrap())
+}
+
 async fn make_sd_jwt(object: Value, disclosable_values: impl IntoIterator<Item = &str>) -> SdJwt {
   let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
   disclosable_values
@@ -40,7 +117,7 @@ async fn make_sd_jwt(object: Value, disclosable_values: impl IntoIterator<Item =
     .fold(SdJwtBuilder::new(object).unwrap(), |builder, path| {
       builder.make_concealable(path).unwrap()
     })
-    .finish(&signer, "HS256")
+    .finish(&signer, JwsAlgorithm::HS256)
     .await
     .unwrap()
 }
@@ -51,7 +128,7 @@ async fn make_kb_jwt(sd_jwt: &SdJwt, hasher: &dyn Hasher) -> KeyBindingJwt {
     .nonce("abcdefghi")
     .aud("https://example.com")
     .iat(1458304832)
-    .finish(sd_jwt, hasher, "HS256", &signer)
+    .finish(sd_jwt, hasher, JwsAlgorithm::HS256, &signer)
     .await
     .unwrap()
 }
@@ -93,9 +170,10 @@ async fn concealing_parent_also_removes_all_sub_disclosures() -> anyhow::Result<
 #[tokio::test]
 async fn concealing_property_of_concealable_value_works() -> anyhow::Result<()> {
   let hasher = Sha256Hasher::new();
+  // A parent can be made concealable before its properties.
   let sd_jwt = make_sd_jwt(
     json!({"parent": {"property1": "value1", "property2": [1, 2, 3]}}),
-    ["/parent/property1", "/parent/property2/0", "/parent"],
+    ["/parent", "/parent/property1", "/parent/property2/0"],
   )
   .await;
 
@@ -107,6 +185,148 @@ async fn concealing_property_of_concealable_value_works() -> anyhow::Result<()>
   Ok(())
 }
 
+#[tokio::test]
+async fn recursive_concealment_discloses_every_value_individually() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let object = json!({
+    "sub": "user_42",
+    "address": {
+      "street_address": "Schulstr. 12",
+      "locality": "Schulpforta",
+      "region": "Sachsen-Anhalt",
+      "country": "DE"
+    }
+  });
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let sd_jwt = SdJwtBuilder::new(object.clone())?
+    .make_concealable_recursive("/address")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  assert_eq!(sd_jwt.disclosures().len(), 5);
+  assert!(sd_jwt.claims().get("address").is_none());
+  assert_eq!(Value::Object(sd_jwt.clone().into_disclosed_object(&hasher)?), object);
+
+  let (presentation, removed) = sd_jwt
+    .into_presentation(&hasher)?
+    .conceal("/address/street_address")?
+    .conceal("/address/locality")?
+    .finish()?;
+  assert_eq!(removed.len(), 2);
+  assert_eq!(
+    Value::Object(presentation.into_disclosed_object(&hasher)?),
+    json!({ "sub": "user_42", "address": { "region": "Sachsen-Anhalt", "country": "DE" } })
+  );
+
+  let error = SdJwtBuilder::new(object)?
+    .make_concealable_recursive("/address/zip")
+    .unwrap_err();
+  assert_eq!(error, Error::InvalidPath("/address/zip".to_string()));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn explicit_salts_reproduce_the_specification_examples() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let sd_jwt = SdJwtBuilder::new(json!({ "address": { "region": "港区", "country": "JP" } }))?
+    .make_concealable_with_salt("/address/region", "G02NSrQfjFXQ7Io09syajA")?
+    .make_concealable_with_salt("/address/country", "lklxF5jMYlGTPUovMNIvCA")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  // Disclosures and digests of the example used by `simple_sd_jwt`.
+  let disclosures = sd_jwt.disclosures().iter().map(ToString::to_string).collect_vec();
+  assert_eq!(
+    disclosures,
+    [
+      "WyJHMDJOU3JRZmpGWFE3SW8wOXN5YWpBIiwgInJlZ2lvbiIsICJcdTZlMmZcdTUzM2EiXQ",
+      "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIiwgImNvdW50cnkiLCAiSlAiXQ",
+    ]
+  );
+  assert_eq!(
+    sd_jwt.claims().get("address"),
+    Some(&json!({ "_sd": [
+      "PzzcVu0qbMuBGSjulfewzkesD9zutOExn5EWNwkrQ-k",
+      "uNHoWYhXsZhVJCNE2Dqy-zqt7t69gJKy5QaFv7GrMX4"
+    ] }))
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn seeded_salt_generators_make_issuance_reproducible() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let issue = |seed: u64| {
+    SdJwtBuilder::new(json!({ "given_name": "John", "nationalities": ["US", "DE"] }))
+      .unwrap()
+      .salt_generator(SeededSaltGenerator::new(seed))
+      .make_concealable("/given_name")
+      .unwrap()
+      .make_concealable("/nationalities/*")
+      .unwrap()
+      .add_decoys("", 2)
+      .unwrap()
+      .add_decoys("/nationalities", 1)
+      .unwrap()
+      .finish(&signer, JwsAlgorithm::HS256)
+  };
+
+  let sd_jwt = issue(42).await?;
+  assert_eq!(sd_jwt.to_string(), issue(42).await?.to_string());
+  assert_ne!(sd_jwt.to_string(), issue(43).await?.to_string());
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn batch_issuance_produces_unlinkable_copies() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let holder_keys = (0..3)
+    .map(|i| RequiredKeyBinding::Kid(format!("did:example:holder#key-{i}")))
+    .collect_vec();
+  let object =
+    json!({ "iat": 1_700_001_234, "exp": 1_700_087_634, "given_name": "John", "nationalities": ["US", "DE"] });
+  let copies = SdJwtBuilder::new(object)?
+    .make_concealable("/given_name")?
+    .make_concealable("/nationalities/*")?
+    .add_decoys("", 2)?
+    .obfuscate_timestamps(TimestampObfuscation::Round(3600))
+    .finish_batch(&signer, JwsAlgorithm::HS256, holder_keys.clone())
+    .await?;
+
+  assert_eq!(copies.len(), 3);
+  let options = VerificationOptions::new().clock(FixedClock::new(1_700_001_234));
+  for (copy, key) in copies.iter().zip(&holder_keys) {
+    assert_eq!(copy.required_key_bind(), Some(key));
+    assert_eq!(copy.claims().get("iat"), Some(&json!(1_699_999_200)));
+    assert_eq!(copy.claims().get("exp"), Some(&json!(1_700_085_600)));
+    assert_eq!(copy.disclosures().len(), 3);
+    let disclosed = copy.verify_with_options(&hmac_verifier(), &hasher, &options).await?;
+    assert_eq!(disclosed["given_name"], "John");
+  }
+  // Neither digests nor salts are shared among copies.
+  let digests = copies.iter().flat_map(|copy| copy.claims()._sd.clone()).collect_vec();
+  assert_eq!(digests.len(), 9);
+  assert!(digests.iter().all_unique());
+  assert!(copies
+    .iter()
+    .flat_map(|copy| copy.disclosures().iter().map(|disclosure| disclosure.salt.clone()))
+    .all_unique());
+
+  // Copies can't share explicit salts.
+  let error = SdJwtBuilder::new(json!({ "given_name": "John" }))?
+    .make_concealable_with_salt("/given_name", "2GLC42sKQveCfGfryNRN9w")?
+    .finish_batch(&signer, JwsAlgorithm::HS256, holder_keys)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::DataTypeMismatch(_)));
+
+  Ok(())
+}
+
 #[tokio::test]
 async fn sd_jwt_is_verifiable() -> anyhow::Result<()> {
   let sd_jwt = make_sd_jwt(json!({"key": "value"}), []).await;
@@ -147,3 +367,1080 @@ async fn sd_jwt_without_disclosures_works() -> anyhow::Result<()> {
 
   Ok(())
 }
+
+#[tokio::test]
+async fn sd_jwt_verification_returns_disclosed_claims() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt(
+    json!({"parent": {"property1": "value1", "property2": [1, 2, 3]}}),
+    ["/parent/property1", "/parent/property2/0"],
+  )
+  .await;
+
+  let disclosed = sd_jwt.verify(&hmac_verifier(), &hasher).await?;
+  assert_eq!(
+    Value::Object(disclosed),
+    json!({"parent": {"property1": "value1", "property2": [1, 2, 3]}})
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn shuffled_disclosures_hide_the_issuance_order() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let object = json!({ "claims": (0..16).map(|i| format!("value{i}")).collect_vec() });
+  let issue = |shuffle: bool| {
+    let builder = SdJwtBuilder::new(object.clone())
+      .unwrap()
+      .salt_generator(SeededSaltGenerator::new(42))
+      .make_concealable("/claims/*")
+      .unwrap();
+    let builder = if shuffle {
+      builder.shuffle_disclosures()
+    } else {
+      builder
+    };
+    builder.finish(&signer, JwsAlgorithm::HS256)
+  };
+  let claim_values = |sd_jwt: &SdJwt| {
+    sd_jwt
+      .disclosures()
+      .iter()
+      .map(|disclosure| disclosure.claim_value.clone())
+      .collect_vec()
+  };
+
+  let ordered = issue(false).await?;
+  let shuffled = issue(true).await?;
+  assert_ne!(claim_values(&shuffled), claim_values(&ordered));
+  assert_eq!(
+    claim_values(&shuffled)
+      .into_iter()
+      .sorted_by_key(ToString::to_string)
+      .collect_vec(),
+    claim_values(&ordered)
+      .into_iter()
+      .sorted_by_key(ToString::to_string)
+      .collect_vec()
+  );
+  assert_eq!(Value::Object(shuffled.verify(&hmac_verifier(), &hasher).await?), object);
+
+  // Presentations can be shuffled as well.
+  let (presentation, _) = ordered
+    .into_presentation(&hasher)?
+    .conceal("/claims/0")?
+    .shuffle_disclosures(&mut SeededSaltGenerator::new(7))
+    .finish()?;
+  let values = claim_values(&presentation);
+  assert_eq!(values.len(), 15);
+  assert!(!values
+    .iter()
+    .is_sorted_by_key(|value| value.as_str().unwrap()[5..].parse::<u32>().unwrap()));
+  let disclosed = presentation.verify(&hmac_verifier(), &hasher).await?;
+  assert_eq!(disclosed["claims"].as_array().unwrap().len(), 15);
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_verification_fails_for_tampered_claims() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt(json!({"key": "value"}), ["/key"]).await;
+  let presentation = sd_jwt.presentation();
+  let (jwt, disclosures) = presentation.split_once('~').unwrap();
+  let (header, _, signature) = jwt.split('.').collect_tuple().unwrap();
+  let forged_payload = multibase::Base::Base64Url.encode(r#"{"key":"forged"}"#);
+  let forged = format!("{header}.{forged_payload}.{signature}~{disclosures}").parse::<SdJwt>()?;
+
+  let error = forged.verify(&hmac_verifier(), &hasher).await.unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+
+  Ok(())
+}
+
+fn unix_now() -> i64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap()
+    .as_secs() as i64
+}
+
+async fn make_bound_presentation(iat: i64) -> anyhow::Result<SdJwt> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let holder_jwk = json!({"kty": "oct", "kid": "holder-key"});
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice", "age": 42}))?
+    .make_concealable("/age")?
+    .require_key_binding(RequiredKeyBinding::Jwk(holder_jwk.as_object().unwrap().clone()))
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let kb_jwt = KeyBindingJwt::builder()
+    .nonce("abcdefghi")
+    .aud("https://example.com")
+    .iat(iat)
+    .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &signer)
+    .await?;
+
+  Ok(
+    sd_jwt
+      .into_presentation(&hasher)?
+      .attach_key_binding_jwt(kb_jwt)
+      .finish()?
+      .0,
+  )
+}
+
+#[tokio::test]
+async fn key_binding_jwt_is_verifiable() -> anyhow::Result<()> {
+  let presentation = make_bound_presentation(unix_now())
+    .await?
+    .to_string()
+    .parse::<SdJwt>()?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  presentation
+    .verify_key_binding(&hmac_verifier(), &Sha256Hasher::new(), &validation)
+    .await?;
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_verification_fails_for_unexpected_nonce_or_aud() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let presentation = make_bound_presentation(unix_now()).await?;
+
+  let validation = KeyBindingJwtValidation::new("https://example.com", "another-nonce");
+  let error = presentation
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+
+  let validation = KeyBindingJwtValidation::new("https://another.example.com", "abcdefghi");
+  let error = presentation
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_verification_fails_for_stale_iat() -> anyhow::Result<()> {
+  let presentation = make_bound_presentation(unix_now() - 600).await?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  let error = presentation
+    .verify_key_binding(&hmac_verifier(), &Sha256Hasher::new(), &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+
+  let validation = validation.max_age(std::time::Duration::from_secs(900));
+  presentation
+    .verify_key_binding(&hmac_verifier(), &Sha256Hasher::new(), &validation)
+    .await?;
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_verification_fails_for_a_different_sd_jwt() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let presentation = make_bound_presentation(unix_now()).await?;
+  // Drop a disclosure, leaving the KB-JWT in place.
+  let (tampered, _) = presentation.into_presentation(&hasher)?.conceal("/age")?.finish()?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  let error = tampered
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+
+  Ok(())
+}
+
+#[test]
+fn sync_signers_do_not_require_a_runtime() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSyncSigner(HS256.signer_from_bytes(HMAC_SECRET)?);
+  let sd_jwt = SdJwtBuilder::new(json!({ "given_name": "John" }))?
+    .make_concealable("/given_name")?
+    .require_key_binding(RequiredKeyBinding::Kid("did:example:holder#key-1".to_string()))
+    .finish_sync(&signer, JwsAlgorithm::HS256)?;
+  let kb_jwt = KeyBindingJwt::builder()
+    .nonce("abcdefghi")
+    .aud("https://example.com")
+    .iat(1458304832)
+    .finish_sync(&sd_jwt, &hasher, JwsAlgorithm::HS256, &signer)?;
+
+  assert_eq!(sd_jwt.disclosures().len(), 1);
+  assert_eq!(kb_jwt.claims().sd_hash, hasher.encoded_digest(&sd_jwt.to_string()));
+
+  Ok(())
+}
+
+#[test]
+fn sync_verifiers_do_not_require_a_runtime() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSyncSigner(HS256.signer_from_bytes(HMAC_SECRET)?);
+  let verifier = HmacSyncVerifier(HS256.verifier_from_bytes(HMAC_SECRET)?);
+  let sd_jwt = SdJwtBuilder::new(json!({ "given_name": "John" }))?
+    .make_concealable("/given_name")?
+    .require_key_binding(RequiredKeyBinding::Jwk(JsonObject::new()))
+    .finish_sync(&signer, JwsAlgorithm::HS256)?;
+  let kb_jwt = KeyBindingJwt::builder()
+    .nonce("abcdefghi")
+    .aud("https://example.com")
+    .iat(unix_now())
+    .finish_sync(&sd_jwt, &hasher, JwsAlgorithm::HS256, &signer)?;
+  let sd_jwt = sd_jwt
+    .into_presentation(&hasher)?
+    .attach_key_binding_jwt(kb_jwt)
+    .finish()?
+    .0;
+
+  let disclosed = sd_jwt.verify_sync(&verifier, &hasher)?;
+  assert_eq!(disclosed["given_name"], "John");
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+  sd_jwt.verify_key_binding_sync(&verifier, &hasher, &validation)?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "other nonce");
+  assert!(sd_jwt.verify_key_binding_sync(&verifier, &hasher, &validation).is_err());
+
+  let other_verifier = HmacSyncVerifier(HS256.verifier_from_bytes(b"FEDCBA9876543210FEDCBA9876543210")?);
+  let error = sd_jwt.verify_sync(&other_verifier, &hasher).unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sync_verifiers_can_be_used_with_the_async_api() -> anyhow::Result<()> {
+  let sd_jwt = make_sd_jwt(json!({ "given_name": "John" }), ["/given_name"]).await;
+  let verifier = HmacSyncVerifier(HS256.verifier_from_bytes(HMAC_SECRET)?);
+
+  let disclosed = sd_jwt.verify(&verifier, &Sha256Hasher::new()).await?;
+  assert_eq!(disclosed["given_name"], "John");
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sync_signers_can_be_used_with_the_async_api() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSyncSigner(HS256.signer_from_bytes(HMAC_SECRET)?);
+  let sd_jwt = SdJwtBuilder::new(json!({ "given_name": "John" }))?
+    .make_concealable("/given_name")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  let disclosed = sd_jwt.verify(&hmac_verifier(), &hasher).await?;
+  assert_eq!(disclosed["given_name"], "John");
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_is_required_when_cnf_is_present() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .require_key_binding(RequiredKeyBinding::Kid("holder-key".to_string()))
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  let error = sd_jwt
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert_eq!(error, Error::MissingKeyBindingJwt);
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_is_verified_with_the_cnf_key() -> anyhow::Result<()> {
+  const HOLDER_SECRET: &[u8; 32] = b"FEDCBA9876543210FEDCBA9876543210";
+  let hasher = Sha256Hasher::new();
+  let issuer_signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET)?);
+  let holder_signer = HmacSignerAdapter(HS256.signer_from_bytes(HOLDER_SECRET)?);
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .require_key_binding(RequiredKeyBinding::Jwk(oct_jwk("holder-key", HOLDER_SECRET)))
+    .finish(&issuer_signer, JwsAlgorithm::HS256)
+    .await?;
+  let present = |kb_jwt: KeyBindingJwt| -> anyhow::Result<SdJwt> {
+    Ok(
+      sd_jwt
+        .clone()
+        .into_presentation(&hasher)?
+        .attach_key_binding_jwt(kb_jwt)
+        .finish()?
+        .0,
+    )
+  };
+  let kb_jwt_builder = || {
+    KeyBindingJwt::builder()
+      .nonce("abcdefghi")
+      .aud("https://example.com")
+      .iat(unix_now())
+  };
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  // `OctJwkVerifier` only verifies with the key it's given, i.e. the one named by `cnf`.
+  let presentation = present(
+    kb_jwt_builder()
+      .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &holder_signer)
+      .await?,
+  )?;
+  presentation
+    .verify_key_binding(&OctJwkVerifier, &hasher, &validation)
+    .await?;
+  presentation.verify_key_binding_sync(&OctJwkVerifier, &hasher, &validation)?;
+
+  // A KB-JWT signed with any other key, e.g. the issuer's, is rejected.
+  let presentation = present(
+    kb_jwt_builder()
+      .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &issuer_signer)
+      .await?,
+  )?;
+  let error = presentation
+    .verify_key_binding(&OctJwkVerifier, &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+  let error = presentation
+    .verify_key_binding_sync(&OctJwkVerifier, &hasher, &validation)
+    .unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+
+  // Other confirmation methods aren't mistaken for a JWK.
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .require_key_binding(RequiredKeyBinding::Custom(
+      json!({ "x5t#S256": "bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2" }),
+    ))
+    .finish(&issuer_signer, JwsAlgorithm::HS256)
+    .await?;
+  let kb_jwt = kb_jwt_builder()
+    .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &holder_signer)
+    .await?;
+  let presentation = sd_jwt
+    .into_presentation(&hasher)?
+    .attach_key_binding_jwt(kb_jwt)
+    .finish()?
+    .0;
+  let error = presentation
+    .verify_key_binding(&OctJwkVerifier, &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::KeyResolutionFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_is_rejected_without_cnf() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt(json!({"name": "Alice"}), []).await;
+  let kb_jwt = make_kb_jwt(&sd_jwt, &hasher).await;
+  let presentation = sd_jwt
+    .into_presentation(&hasher)?
+    .attach_key_binding_jwt(kb_jwt)
+    .finish()?
+    .0;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  let error = presentation
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+  let error = presentation
+    .verify_key_binding_sync(
+      &HmacSyncVerifier(HS256.verifier_from_bytes(HMAC_SECRET)?),
+      &hasher,
+      &validation,
+    )
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingJwt(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_jwt_verification_uses_the_provided_clock() -> anyhow::Result<()> {
+  let iat = unix_now();
+  let presentation = make_bound_presentation(iat).await?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi").clock(FixedClock::new(iat - 10));
+
+  let error = presentation
+    .verify_key_binding(&hmac_verifier(), &Sha256Hasher::new(), &validation)
+    .await
+    .unwrap_err();
+  assert_eq!(error, Error::JwtNotYetValid(iat));
+
+  let validation = validation.leeway(std::time::Duration::from_secs(10));
+  presentation
+    .verify_key_binding(&hmac_verifier(), &Sha256Hasher::new(), &validation)
+    .await?;
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_verification_checks_the_time_claims() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let now = unix_now();
+  let expired = make_sd_jwt(json!({ "name": "Alice", "exp": now - 60 }), ["/name"]).await;
+  let error = expired.verify(&hmac_verifier(), &hasher).await.unwrap_err();
+  assert_eq!(error, Error::ExpiredJwt(now - 60));
+
+  let not_yet_valid = make_sd_jwt(json!({ "name": "Alice", "nbf": now + 60 }), ["/name"]).await;
+  let error = not_yet_valid.verify(&hmac_verifier(), &hasher).await.unwrap_err();
+  assert_eq!(error, Error::JwtNotYetValid(now + 60));
+
+  let options = VerificationOptions::new().clock(FixedClock::new(now + 60));
+  let disclosed = not_yet_valid
+    .verify_with_options(&hmac_verifier(), &hasher, &options)
+    .await?;
+  assert_eq!(disclosed.get("name"), Some(&json!("Alice")));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_verification_honors_policy() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt(json!({"name": "Alice", "age": 42}), ["/name", "/age"]).await;
+  let (sd_jwt, _) = sd_jwt.into_presentation(&hasher)?.conceal("/age")?.finish()?;
+
+  let policy: VerificationPolicy = serde_json::from_value(json!({
+    "allowed_algorithms": ["HS256"],
+    "required_claims": ["/name"],
+  }))?;
+  sd_jwt.verify_with_policy(&hmac_verifier(), &hasher, &policy).await?;
+
+  let policy = VerificationPolicy {
+    required_claims: vec!["/age".to_string()],
+    ..policy
+  };
+  let error = sd_jwt
+    .verify_with_policy(&hmac_verifier(), &hasher, &policy)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::PolicyViolation(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn verification_report_collects_every_failure() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt(json!({"name": "Alice", "age": 42}), ["/name", "/age"]).await;
+  let unused = multibase::Base::Base64Url.encode(r#"["salt", "nickname", "Ally"]"#);
+  let presentation = format!("{}{unused}~", sd_jwt.presentation());
+  let (jwt, disclosures) = presentation.split_once('~').unwrap();
+  let (header, _, signature) = jwt.split('.').collect_tuple().unwrap();
+  let claims = serde_json::to_string(sd_jwt.claims())?;
+  let forged_claims = claims.replace('{', r#"{"exp":1,"#);
+  let forged_payload = multibase::Base::Base64Url.encode(forged_claims);
+  let forged = format!("{header}.{forged_payload}.{signature}~{disclosures}").parse::<SdJwt>()?;
+
+  let options = VerificationOptions::new().policy(VerificationPolicy {
+    required_claims: vec!["/address".to_string()],
+    ..Default::default()
+  });
+  let report = forged.verify_with_report(&hmac_verifier(), &hasher, &options).await;
+
+  let failures = report.failures().map(|check| check.kind).collect_vec();
+  assert_eq!(
+    failures,
+    [
+      CheckKind::IssuerSignature,
+      CheckKind::TimeValidity,
+      CheckKind::UnusedDisclosure,
+      CheckKind::Policy
+    ]
+  );
+  let disclosed = report
+    .checks()
+    .iter()
+    .filter(|check| check.kind == CheckKind::Disclosure && check.status == CheckStatus::Passed)
+    .filter_map(|check| check.path.as_deref())
+    .sorted()
+    .collect_vec();
+  assert_eq!(disclosed, ["/age", "/name"]);
+  assert_eq!(
+    report.disclosed_object().cloned().map(Value::Object),
+    Some(json!({"exp": 1, "name": "Alice", "age": 42}))
+  );
+  let key_binding = report.checks().last().unwrap();
+  assert_eq!(
+    (key_binding.kind, key_binding.status),
+    (CheckKind::KeyBinding, CheckStatus::Skipped)
+  );
+  assert!(matches!(report.into_result(), Err(Error::JwsVerificationFailure(_))));
+
+  let report = sd_jwt
+    .verify_with_report(&hmac_verifier(), &hasher, &VerificationOptions::new())
+    .await;
+  assert!(report.is_valid());
+  assert_eq!(
+    Value::Object(report.into_result()?),
+    json!({"name": "Alice", "age": 42})
+  );
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn presentations_round_trip_in_jws_json_serialization() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_bound_presentation(unix_now()).await?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  for serialization in [JwsJsonSerialization::Flattened, JwsJsonSerialization::General] {
+    let json = sd_jwt.presentation_json(serialization)?;
+    let parsed: SdJwt = json.parse()?;
+    assert_eq!(parsed, sd_jwt);
+    assert_eq!(parsed.presentation_json(serialization)?, json);
+
+    let header = &serde_json::from_str::<Value>(&json)?;
+    let header = match serialization {
+      JwsJsonSerialization::Flattened => &header["header"],
+      JwsJsonSerialization::General => &header["signatures"][0]["header"],
+    };
+    assert_eq!(header["disclosures"], json!([sd_jwt.disclosures()[0].to_string()]));
+    assert_eq!(header["kb_jwt"], json!(sd_jwt.key_binding_jwt().unwrap().to_string()));
+
+    parsed.verify(&hmac_verifier(), &hasher).await?;
+    parsed
+      .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+      .await?;
+  }
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_can_carry_multiple_signatures() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let first_signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let second_secret = b"FEDCBA9876543210FEDCBA9876543210";
+  // Signers of different types can be mixed.
+  let second_signer = HmacSyncSigner(HS256.signer_from_bytes(second_secret).unwrap());
+  let parameters = |kid: &str| JwsHeader {
+    alg: Some(JwsAlgorithm::HS256),
+    kid: Some(kid.to_string()),
+    ..Default::default()
+  };
+
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .make_concealable("/name")?
+    .finish_multi_signed(&[
+      (&first_signer, parameters("first")),
+      (&second_signer, parameters("second")),
+    ])
+    .await?;
+  assert_eq!(sd_jwt.header().kid.as_deref(), Some("first"));
+  assert!(sd_jwt.presentation_json(JwsJsonSerialization::Flattened).is_err());
+
+  let json = sd_jwt.presentation_json(JwsJsonSerialization::General)?;
+  let parsed = SdJwt::parse_json(&json)?;
+  assert_eq!(parsed, sd_jwt);
+  assert_eq!(
+    parsed.verify(&hmac_verifier(), &hasher).await?.get("name"),
+    Some(&json!("Alice"))
+  );
+
+  let [second] = parsed.additional_signatures() else {
+    panic!("expected exactly one additional signature");
+  };
+  assert_eq!(second.protected_header()?.kid.as_deref(), Some("second"));
+  let second_verifier = HmacVerifierAdapter(HS256.verifier_from_bytes(second_secret)?);
+  parsed.verify_signature(0, &hmac_verifier()).await?;
+  parsed.verify_signature(1, &second_verifier).await?;
+  assert!(parsed.verify_signature(1, &hmac_verifier()).await.is_err());
+  assert!(parsed.verify_signature(2, &second_verifier).await.is_err());
+
+  // The parameters shared by every signature can't be overridden by a signer.
+  let mut overriding_typ = parameters("second");
+  overriding_typ.typ = Some("JWT".to_string());
+  let error = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .finish_multi_signed(&[(&first_signer, parameters("first")), (&second_signer, overriding_typ)])
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::DataTypeMismatch(_)));
+  let error = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .header(JwsHeader {
+      kid: Some("shared".to_string()),
+      ..Default::default()
+    })
+    .finish_multi_signed(&[(&first_signer, parameters("first"))])
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::DataTypeMismatch(_)));
+
+  Ok(())
+}
+
+/// Verifies HMAC signatures with the secret of the `oct` JWK it's given.
+struct OctJwkVerifier;
+
+impl JwsVerifierSync for OctJwkVerifier {
+  type Error = anyhow::Error;
+  fn verify_sync(
+    &self,
+    _header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    let secret = key
+      .and_then(|jwk| jwk.get("k"))
+      .and_then(Value::as_str)
+      .ok_or_else(|| anyhow::anyhow!("missing key"))?;
+    let verifier = HS256.verifier_from_bytes(multibase::Base::Base64Url.decode(secret)?)?;
+    Ok(josekit::jws::JwsVerifier::verify(&verifier, signing_input, signature)?)
+  }
+}
+
+/// Understands the `exp` header parameter, so that it can be listed in `crit`.
+struct ExpAwareVerifier(HmacVerifierAdapter);
+
+#[async_trait]
+impl JwsVerifier for ExpAwareVerifier {
+  type Error = josekit::JoseError;
+  async fn verify(
+    &self,
+    header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    self.0.verify(header, signing_input, signature, key).await
+  }
+
+  fn understands_critical_extension(&self, name: &str) -> bool {
+    name == "exp"
+  }
+}
+
+#[tokio::test]
+async fn unknown_critical_extensions_are_rejected() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET)?);
+  let hasher = Sha256Hasher::new();
+  let mut header = JwsHeader::new();
+  header.crit = Some(vec!["exp".to_string()]);
+  header.extensions.insert("exp".to_string(), json!(1883000000));
+  let sd_jwt = SdJwtBuilder::new(json!({ "given_name": "Alice" }))?
+    .make_concealable("/given_name")?
+    .header(header)
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  let sd_jwt = SdJwt::parse(&sd_jwt.presentation())?;
+  assert_eq!(sd_jwt.header().alg, Some(JwsAlgorithm::HS256));
+  assert_eq!(sd_jwt.header().typ.as_deref(), Some("sd-jwt"));
+  let error = sd_jwt.verify(&hmac_verifier(), &hasher).await.unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+  let disclosed = sd_jwt.verify(&ExpAwareVerifier(hmac_verifier()), &hasher).await?;
+  assert_eq!(disclosed.get("given_name"), Some(&json!("Alice")));
+
+  Ok(())
+}
+
+fn oct_jwk(kid: &str, secret: &[u8]) -> JsonObject {
+  json!({"kty": "oct", "kid": kid, "k": multibase::Base::Base64Url.encode(secret)})
+    .as_object()
+    .unwrap()
+    .clone()
+}
+
+#[tokio::test]
+async fn keys_are_resolved_during_verification() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let holder_secret = b"FEDCBA9876543210FEDCBA9876543210";
+  let holder_signer = HmacSignerAdapter(HS256.signer_from_bytes(holder_secret).unwrap());
+  let sd_jwt = SdJwtBuilder::new(json!({"name": "Alice"}))?
+    .make_concealable("/name")?
+    .header(JwsHeader {
+      kid: Some("issuer-key".to_string()),
+      ..Default::default()
+    })
+    .require_key_binding(RequiredKeyBinding::Kid("holder-key".to_string()))
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let kb_jwt = KeyBindingJwt::builder()
+    .nonce("abcdefghi")
+    .aud("https://example.com")
+    .iat(unix_now())
+    .finish(&sd_jwt, &hasher, JwsAlgorithm::HS256, &holder_signer)
+    .await?;
+  let sd_jwt = sd_jwt
+    .into_presentation(&hasher)?
+    .attach_key_binding_jwt(kb_jwt)
+    .finish()?
+    .0;
+
+  let resolver = JwkSetKeyResolver::new(JwkSet::from_iter([oct_jwk("issuer-key", HMAC_SECRET)]));
+  let verifier = KeyResolvingVerifier::new(resolver, OctJwkVerifier);
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+
+  sd_jwt.verify(&verifier, &hasher).await?;
+  assert!(sd_jwt.verify(&OctJwkVerifier, &hasher).await.is_err());
+  // The holder's key is only referenced, it can't be verified against without a resolver.
+  let error = sd_jwt
+    .verify_key_binding(&verifier, &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::KeyResolutionFailure(_)));
+  let options = VerificationOptions::new()
+    .key_resolver(JwkSetKeyResolver::new(JwkSet::from_iter([oct_jwk(
+      "issuer-key",
+      HMAC_SECRET,
+    )])))
+    .key_binding(validation.clone());
+  assert!(sd_jwt
+    .verify_with_options(&OctJwkVerifier, &hasher, &options)
+    .await
+    .is_err());
+
+  let resolver = JwkSetKeyResolver::new(JwkSet::from_iter([
+    oct_jwk("issuer-key", HMAC_SECRET),
+    oct_jwk("holder-key", holder_secret),
+  ]));
+  let options = VerificationOptions::new()
+    .key_resolver(resolver)
+    .key_binding(validation);
+  let disclosed = sd_jwt.verify_with_options(&OctJwkVerifier, &hasher, &options).await?;
+  assert_eq!(disclosed.get("name"), Some(&json!("Alice")));
+  assert!(sd_jwt
+    .verify_with_report(&OctJwkVerifier, &hasher, &options)
+    .await
+    .is_valid());
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_vc_enforces_profile_rules() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let builder = || {
+    SdJwtVcBuilder::new(json!({ "given_name": "Alice", "age": 42 }))
+      .unwrap()
+      .iss("https://issuer.example.com")
+      .unwrap()
+      .vct("https://credentials.example.com/identity_credential")
+      .unwrap()
+  };
+
+  let sd_jwt_vc = builder()
+    .status(Status::status_list(7, "https://issuer.example.com/statuslists/1"))?
+    .make_concealable("/given_name")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  assert_eq!(sd_jwt_vc.header().typ.as_deref(), Some(SD_JWT_VC_TYP));
+  assert_eq!(sd_jwt_vc.vct(), "https://credentials.example.com/identity_credential");
+  assert_eq!(
+    sd_jwt_vc.status().and_then(|status| status.status_list.as_ref()),
+    Some(&StatusListReference {
+      idx: 7,
+      uri: "https://issuer.example.com/statuslists/1".to_string()
+    })
+  );
+  let parsed = SdJwtVc::parse(&sd_jwt_vc.presentation())?;
+  assert_eq!(parsed, sd_jwt_vc);
+  let disclosed = parsed.verify(&hmac_verifier(), &Sha256Hasher::new()).await?;
+  assert_eq!(disclosed.get("given_name"), Some(&json!("Alice")));
+
+  let error = builder().make_concealable("/vct").unwrap_err();
+  assert!(matches!(error, Error::NonConcealableClaim(_)));
+  let error = SdJwtVcBuilder::new(json!({ "vct": "identity_credential" }))?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidSdJwtVc(_)));
+
+  // Plain SD-JWTs, or SD-JWTs with a concealed `iss`, are rejected.
+  let sd_jwt = make_sd_jwt(
+    json!({ "iss": "https://issuer.example.com", "vct": "identity_credential" }),
+    [],
+  )
+  .await;
+  let error = SdJwtVc::try_from(sd_jwt).unwrap_err();
+  assert!(matches!(error, Error::InvalidSdJwtVc(_)));
+  let sd_jwt = SdJwtBuilder::new(json!({ "iss": "https://issuer.example.com", "vct": "identity_credential" }))?
+    .make_concealable("/iss")?
+    .header(JwsHeader {
+      typ: Some(SD_JWT_VC_TYP.to_string()),
+      ..Default::default()
+    })
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let error = SdJwtVc::parse(&sd_jwt.presentation()).unwrap_err();
+  assert!(matches!(error, Error::InvalidSdJwtVc(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_vc_builder_supports_every_concealment_method() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let builder = || {
+    SdJwtVcBuilder::new(json!({
+      "address": { "street": "Main St", "locality": "Anytown" },
+      "nationalities": ["DE", "US"],
+      "given_name": "Alice",
+      "age": 42
+    }))
+    .unwrap()
+    .iss("https://issuer.example.com")
+    .unwrap()
+    .vct("identity_credential")
+    .unwrap()
+  };
+
+  let mut plan = ConcealmentPlan::new();
+  plan.conceal("/given_name");
+  let sd_jwt_vc = builder()
+    .non_concealable_paths(["/age"])
+    .make_concealable_recursive("/address")?
+    .make_concealable_where(|_, value| value.is_number())
+    .apply_plan(&plan)?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let claims = sd_jwt_vc.claims();
+  assert!(claims.contains_key("age"));
+  assert!(claims.contains_key("vct"));
+  assert!(!claims.contains_key("address"));
+  assert!(!claims.contains_key("given_name"));
+  assert_eq!(sd_jwt_vc.disclosures().len(), 4);
+
+  // The claims required by the profile stay non-concealable.
+  let error = builder()
+    .non_concealable_paths(["/age"])
+    .make_concealable("/iss")
+    .unwrap_err();
+  assert!(matches!(error, Error::NonConcealableClaim(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn sd_jwt_vc_key_binding_requires_cnf() -> anyhow::Result<()> {
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let hasher = Sha256Hasher::new();
+  let sd_jwt_vc = SdJwtVcBuilder::new(json!({ "given_name": "Alice" }))?
+    .iss("https://issuer.example.com")?
+    .vct("identity_credential")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+  let validation = KeyBindingJwtValidation::new("https://example.com", "abcdefghi");
+  let error = sd_jwt_vc
+    .verify_key_binding(&hmac_verifier(), &hasher, &validation)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidSdJwtVc(_)));
+
+  // A KB-JWT can't be attached to an SD-JWT VC without `cnf`.
+  let kb_jwt = make_kb_jwt(&sd_jwt_vc, &hasher).await;
+  let (sd_jwt, _) = SdJwt::from(sd_jwt_vc)
+    .into_presentation(&hasher)?
+    .attach_key_binding_jwt(kb_jwt)
+    .finish()?;
+  let error = SdJwtVc::try_from(sd_jwt).unwrap_err();
+  assert!(matches!(error, Error::InvalidSdJwtVc(_)));
+
+  Ok(())
+}
+
+fn sri(document: &[u8]) -> String {
+  format!(
+    "sha256-{}",
+    multibase::Base::Base64Pad.encode(Sha256Hasher::new().digest(document))
+  )
+}
+
+#[tokio::test]
+async fn type_metadata_is_resolved_along_extends_chain() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let base_schema = serde_json::to_vec(&json!({
+    "type": "object",
+    "properties": { "given_name": { "type": "string" } },
+    "required": ["given_name"]
+  }))?;
+  let base = serde_json::to_vec(&json!({
+    "vct": "https://example.com/base",
+    "schema_uri": "https://example.com/base/schema",
+    "schema_uri#integrity": sri(&base_schema),
+    "claims": [{ "path": ["given_name"], "sd": "allowed" }]
+  }))?;
+  let identity = serde_json::to_vec(&json!({
+    "vct": "https://example.com/identity",
+    "name": "Identity Credential",
+    "extends": "https://example.com/base",
+    "extends#integrity": sri(&base),
+    "schema": { "type": "object", "properties": { "age": { "type": "integer", "minimum": 0 } } },
+    "claims": [{ "path": ["given_name"], "sd": "always" }, { "path": ["age"], "sd": "never" }]
+  }))?;
+  let mut registry = TypeMetadataRegistry::new();
+  registry.insert("https://example.com/base", base.clone());
+  let schema_path = std::env::temp_dir().join(format!("sd-jwt-vc-schema-{}.json", std::process::id()));
+  std::fs::write(&schema_path, base_schema)?;
+  registry.insert_file("https://example.com/base/schema", &schema_path);
+  registry.insert("https://example.com/identity", identity.clone());
+
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+  let sd_jwt_vc = SdJwtVcBuilder::new(json!({ "given_name": "Alice", "age": 42 }))?
+    .iss("https://issuer.example.com")?
+    .vct("https://example.com/identity")?
+    .vct_integrity(sri(&identity))?
+    .make_concealable("/given_name")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  let metadata = sd_jwt_vc.resolve_type_metadata(&registry, &hasher).await?;
+  assert_eq!(metadata.chain().len(), 2);
+  assert_eq!(metadata.schemas().len(), 2);
+  let claims = metadata.claims();
+  assert_eq!(claims.len(), 2);
+  assert_eq!(claims[0].sd, ClaimDisclosability::Always);
+
+  #[cfg(feature = "json-schema")]
+  {
+    let disclosed = sd_jwt_vc.verify(&hmac_verifier(), &hasher).await?;
+    metadata.validate(&disclosed)?;
+    let (presentation, _) = SdJwt::from(sd_jwt_vc.clone())
+      .into_presentation(&hasher)?
+      .conceal("/given_name")?
+      .finish()?;
+    let error = metadata
+      .validate(&presentation.into_disclosed_object(&hasher)?)
+      .unwrap_err();
+    assert!(matches!(error, Error::SchemaValidationFailure(_)));
+  }
+
+  std::fs::remove_file(schema_path)?;
+
+  // A tampered parent type is detected through `extends#integrity`.
+  registry.insert(
+    "https://example.com/base",
+    b"{\"vct\": \"https://example.com/base\"}".to_vec(),
+  );
+  let error = sd_jwt_vc.resolve_type_metadata(&registry, &hasher).await.unwrap_err();
+  assert!(matches!(error, Error::IntegrityCheckFailure(_)));
+
+  // Cycles are rejected.
+  let mut registry = TypeMetadataRegistry::new();
+  registry.insert(
+    "https://example.com/a",
+    br#"{"extends": "https://example.com/b"}"#.to_vec(),
+  );
+  registry.insert(
+    "https://example.com/b",
+    br#"{"extends": "https://example.com/a"}"#.to_vec(),
+  );
+  let error = ResolvedTypeMetadata::resolve(&registry, "https://example.com/a", None, &hasher)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::TypeMetadataResolutionFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn type_metadata_sd_rules_are_enforced() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let mut registry = TypeMetadataRegistry::new();
+  registry.insert(
+    "https://example.com/identity",
+    serde_json::to_vec(&json!({
+      "vct": "https://example.com/identity",
+      "claims": [
+        { "path": ["given_name"], "sd": "always" },
+        { "path": ["nationalities", null], "sd": "always" },
+        { "path": ["age"], "sd": "never" }
+      ]
+    }))?,
+  );
+  let metadata = ResolvedTypeMetadata::resolve(&registry, "https://example.com/identity", None, &hasher).await?;
+  let object = json!({ "given_name": "Alice", "age": 42, "nationalities": ["DE", "US"], "locale": "de" });
+  let signer = HmacSignerAdapter(HS256.signer_from_bytes(HMAC_SECRET).unwrap());
+
+  // A claim marked `always` must be made concealable, including every element of an array.
+  let error = SdJwtBuilder::new(object.clone())?
+    .type_metadata(&metadata)
+    .make_concealable("/given_name")?
+    .make_concealable("/nationalities/0")?
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await
+    .unwrap_err();
+  assert_eq!(
+    error,
+    Error::DisclosabilityViolation("claim \"/nationalities/1\" must be selectively disclosable".to_string())
+  );
+
+  // A claim marked `never` must not be made concealable.
+  let error = SdJwtBuilder::new(object.clone())?
+    .make_concealable("/given_name")?
+    .make_concealable("/nationalities/0")?
+    .make_concealable("/nationalities/1")?
+    .make_concealable("/age")?
+    .type_metadata(&metadata)
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::DisclosabilityViolation(_)));
+
+  let sd_jwt = SdJwtBuilder::new(object)?
+    .make_concealable("/nationalities/1")?
+    .make_concealable("/nationalities/0")?
+    .make_concealable("/nationalities")?
+    .make_concealable("/given_name")?
+    .make_concealable("/locale")?
+    .type_metadata(&metadata)
+    .finish(&signer, JwsAlgorithm::HS256)
+    .await?;
+
+  // The holder is told which claims it can't hide.
+  let error = sd_jwt
+    .clone()
+    .into_presentation(&hasher)?
+    .type_metadata(&metadata)
+    .conceal("/age")
+    .unwrap_err();
+  assert_eq!(
+    error,
+    Error::DisclosabilityViolation("claim \"/age\" must not be selectively disclosable".to_string())
+  );
+  let error = sd_jwt.clone().into_presentation(&hasher)?.conceal("/age").unwrap_err();
+  assert_eq!(
+    error,
+    Error::DisclosabilityViolation("claim \"/age\" is not selectively disclosable".to_string())
+  );
+  let error = sd_jwt
+    .clone()
+    .into_presentation(&hasher)?
+    .conceal("/email")
+    .unwrap_err();
+  assert!(matches!(error, Error::InvalidPath(_)));
+
+  let (presentation, removed) = sd_jwt
+    .into_presentation(&hasher)?
+    .type_metadata(&metadata)
+    .conceal("/nationalities/1")?
+    .conceal("/locale")?
+    .finish()?;
+  assert_eq!(removed.len(), 2);
+  let disclosed = presentation.into_disclosed_object(&hasher)?;
+  assert_eq!(disclosed.get("nationalities"), Some(&json!(["DE"])));
+
+  Ok(())
+}
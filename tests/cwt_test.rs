@@ -0,0 +1,252 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "cwt")]
+
+use async_trait::async_trait;
+use josekit::jws::HS256;
+use sd_jwt_payload::cwt::CborValue;
+use sd_jwt_payload::cwt::CoseSigner;
+use sd_jwt_payload::cwt::CoseVerifier;
+use sd_jwt_payload::cwt::KeyBindingToken;
+use sd_jwt_payload::cwt::SdCwt;
+use sd_jwt_payload::cwt::SdCwtBuilder;
+use sd_jwt_payload::cwt::REDACTED_CLAIM_KEYS;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::FixedClock;
+use sd_jwt_payload::KeyBindingJwtValidation;
+use sd_jwt_payload::SeededSaltGenerator;
+use sd_jwt_payload::Sha256Hasher;
+
+const ISSUER_SECRET: &[u8; 32] = b"0123456789ABCDEF0123456789ABCDEF";
+const HOLDER_SECRET: &[u8; 32] = b"FEDCBA9876543210FEDCBA9876543210";
+/// COSE algorithm identifier of HMAC 256/256.
+const HMAC_256: i64 = 5;
+const NOW: i64 = 1_725_000_000;
+
+struct HmacSigner(&'static [u8]);
+
+#[async_trait]
+impl CoseSigner for HmacSigner {
+  type Error = josekit::JoseError;
+  async fn sign(&self, _alg: i64, to_be_signed: &[u8]) -> Result<Vec<u8>, Self::Error> {
+    josekit::jws::JwsSigner::sign(&HS256.signer_from_bytes(self.0)?, to_be_signed)
+  }
+}
+
+/// Verifies HMAC signatures with the issuer's secret, or with the secret of the symmetric `COSE_Key` it's given.
+struct HmacVerifier;
+
+#[async_trait]
+impl CoseVerifier for HmacVerifier {
+  type Error = josekit::JoseError;
+  async fn verify(
+    &self,
+    _alg: i64,
+    to_be_signed: &[u8],
+    signature: &[u8],
+    key: Option<&CborValue>,
+  ) -> Result<(), Self::Error> {
+    // COSE_Key parameter `k`.
+    let secret = key
+      .and_then(|key| key.get(&CborValue::from(-1)))
+      .and_then(CborValue::as_bytes)
+      .unwrap_or(ISSUER_SECRET);
+    josekit::jws::JwsVerifier::verify(&HS256.verifier_from_bytes(secret)?, to_be_signed, signature)
+  }
+}
+
+fn holder_key() -> CborValue {
+  // Symmetric COSE_Key: kty = 4, k = secret.
+  CborValue::Map(vec![
+    (CborValue::from(1), CborValue::from(4)),
+    (CborValue::from(-1), CborValue::from(HOLDER_SECRET.to_vec())),
+  ])
+}
+
+fn claims() -> CborValue {
+  CborValue::Map(vec![
+    (CborValue::from(1), CborValue::from("https://issuer.example")),
+    (CborValue::from(6), CborValue::from(NOW)),
+    (CborValue::from(500), CborValue::from(true)),
+    (CborValue::from(501), CborValue::from("ABCD-123456")),
+    (
+      CborValue::from("address"),
+      CborValue::Map(vec![
+        (CborValue::from("country"), CborValue::from("us")),
+        (CborValue::from("region"), CborValue::from("ca")),
+      ]),
+    ),
+    (
+      CborValue::from("nationalities"),
+      CborValue::Array(vec![CborValue::from("US"), CborValue::from("DE")]),
+    ),
+  ])
+}
+
+async fn make_sd_cwt() -> SdCwt {
+  SdCwtBuilder::new(claims())
+    .unwrap()
+    .make_concealable("/501")
+    .unwrap()
+    .make_concealable("/address/region")
+    .unwrap()
+    .make_concealable("/address")
+    .unwrap()
+    .make_concealable("/nationalities/1")
+    .unwrap()
+    .add_decoys("", 2)
+    .unwrap()
+    .add_decoys("/nationalities", 1)
+    .unwrap()
+    .require_key_binding(holder_key())
+    .finish(&HmacSigner(ISSUER_SECRET), HMAC_256)
+    .await
+    .unwrap()
+}
+
+/// Sorts map entries by their encoded keys, recursively, to compare claims regardless of entry order.
+fn normalize(value: &CborValue) -> CborValue {
+  match value {
+    CborValue::Map(map) => {
+      let mut map: Vec<_> = map.iter().map(|(key, value)| (key.clone(), normalize(value))).collect();
+      map.sort_by_key(|(key, _)| key.to_vec().unwrap());
+      CborValue::Map(map)
+    }
+    CborValue::Array(array) => CborValue::Array(array.iter().map(normalize).collect()),
+    value => value.clone(),
+  }
+}
+
+#[tokio::test]
+async fn sd_cwt_round_trip() -> anyhow::Result<()> {
+  let sd_cwt = make_sd_cwt().await;
+  assert_eq!(sd_cwt.disclosures().len(), 4);
+  assert!(sd_cwt.claims().get(&CborValue::from(501)).is_none());
+  let redacted = sd_cwt.claims().get(&REDACTED_CLAIM_KEYS).and_then(CborValue::as_array);
+  // The digests of `501`, `address` and two decoys.
+  assert_eq!(redacted.map(Vec::len), Some(4));
+
+  let parsed = SdCwt::parse(&sd_cwt.to_vec())?;
+  assert_eq!(parsed, sd_cwt);
+
+  let disclosed = parsed.verify(&HmacVerifier, &Sha256Hasher::new()).await?;
+  let mut expected = claims();
+  let cnf = CborValue::Map(vec![(CborValue::from(1), holder_key())]);
+  expected.as_map_mut().unwrap().push((CborValue::from(8), cnf));
+  assert_eq!(normalize(&disclosed), normalize(&expected));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn concealment_doesnt_depend_on_the_order_of_paths() -> anyhow::Result<()> {
+  async fn make_sd_cwt(paths: &[&str]) -> SdCwt {
+    paths
+      .iter()
+      .fold(SdCwtBuilder::new(claims()).unwrap(), |builder, path| {
+        builder.make_concealable(path).unwrap()
+      })
+      .salt_generator(SeededSaltGenerator::new(42))
+      .add_decoys("/address", 1)
+      .unwrap()
+      .finish(&HmacSigner(ISSUER_SECRET), HMAC_256)
+      .await
+      .unwrap()
+  }
+
+  let parent_first = make_sd_cwt(&["/address", "/address/region"]).await;
+  let child_first = make_sd_cwt(&["/address/region", "/address"]).await;
+  assert_eq!(parent_first.to_vec(), child_first.to_vec());
+  assert_eq!(parent_first.disclosures().len(), 2);
+
+  let disclosed = parent_first.verify(&HmacVerifier, &Sha256Hasher::new()).await?;
+  assert_eq!(normalize(&disclosed), normalize(&claims()));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn tampered_sd_cwt_is_rejected() -> anyhow::Result<()> {
+  let sd_cwt = make_sd_cwt().await;
+  let mut bytes = sd_cwt.to_vec();
+  let position = bytes.windows(4).position(|window| window == b"http").unwrap();
+  bytes[position] = b'f';
+
+  let error = SdCwt::parse(&bytes)?
+    .verify(&HmacVerifier, &Sha256Hasher::new())
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::CoseVerificationFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn concealing_removes_sub_disclosures() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let (sd_cwt, removed) = make_sd_cwt()
+    .await
+    .into_presentation(&hasher)?
+    .conceal("/address")?
+    .conceal("/nationalities/1")?
+    .finish();
+  assert_eq!(removed.len(), 3);
+
+  let disclosed = SdCwt::parse(&sd_cwt.to_vec())?.verify(&HmacVerifier, &hasher).await?;
+  assert!(disclosed.get(&CborValue::from("address")).is_none());
+  assert_eq!(
+    disclosed.get(&CborValue::from(501)),
+    Some(&CborValue::from("ABCD-123456"))
+  );
+  assert_eq!(
+    disclosed.get(&CborValue::from("nationalities")),
+    Some(&CborValue::Array(vec![CborValue::from("US")]))
+  );
+
+  let error = sd_cwt.into_presentation(&hasher)?.conceal("/1").unwrap_err();
+  assert!(matches!(error, Error::InvalidPath(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn key_binding_token_round_trip() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let (sd_cwt, _) = make_sd_cwt()
+    .await
+    .into_presentation(&hasher)?
+    .conceal("/501")?
+    .finish();
+  let kbt = KeyBindingToken::builder()
+    .aud("https://verifier.example")
+    .iat(NOW)
+    .cnonce(b"abcdefghi".to_vec())
+    .finish(&sd_cwt, &HmacSigner(HOLDER_SECRET), HMAC_256)
+    .await?;
+
+  let parsed = KeyBindingToken::parse(&kbt.to_vec())?;
+  assert_eq!(parsed, kbt);
+  assert_eq!(parsed.sd_cwt(), &sd_cwt);
+
+  let validation = KeyBindingJwtValidation::new("https://verifier.example", "abcdefghi").clock(FixedClock::new(NOW));
+  parsed.verify(&HmacVerifier, &validation).await?;
+  let disclosed = parsed.sd_cwt().verify(&HmacVerifier, &hasher).await?;
+  assert!(disclosed.get(&CborValue::from(501)).is_none());
+
+  let validation = KeyBindingJwtValidation::new("https://verifier.example", "other").clock(FixedClock::new(NOW));
+  let error = parsed.verify(&HmacVerifier, &validation).await.unwrap_err();
+  assert!(matches!(error, Error::InvalidKeyBindingToken(_)));
+
+  let forged = KeyBindingToken::builder()
+    .aud("https://verifier.example")
+    .iat(NOW)
+    .cnonce(b"abcdefghi".to_vec())
+    .finish(&sd_cwt, &HmacSigner(ISSUER_SECRET), HMAC_256)
+    .await?;
+  let validation = KeyBindingJwtValidation::new("https://verifier.example", "abcdefghi").clock(FixedClock::new(NOW));
+  let error = forged.verify(&HmacVerifier, &validation).await.unwrap_err();
+  assert!(matches!(error, Error::CoseVerificationFailure(_)));
+
+  Ok(())
+}
@@ -0,0 +1,169 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "derive")]
+
+use async_trait::async_trait;
+use josekit::jws::JwsHeader;
+use josekit::jws::HS256;
+use josekit::jwt;
+use josekit::jwt::JwtPayload;
+use sd_jwt_payload::ConcealmentPlan;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwsAlgorithm;
+use sd_jwt_payload::JwsSigner;
+use sd_jwt_payload::SdJwtBuilder;
+use sd_jwt_payload::SelectivelyDisclosable;
+use sd_jwt_payload::Sha256Hasher;
+use serde::Deserialize;
+use serde::Serialize;
+
+const HMAC_SECRET: &[u8; 32] = b"0123456789ABCDEF0123456789ABCDEF";
+
+struct HmacSigner;
+
+#[async_trait]
+impl JwsSigner for HmacSigner {
+  type Error = josekit::JoseError;
+  async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    let signer = HS256.signer_from_bytes(HMAC_SECRET)?;
+    let header = JwsHeader::from_map(header.clone())?;
+    let payload = JwtPayload::from_map(payload.clone())?;
+
+    jwt::encode_with_signer(&payload, &header, &signer).map(String::into_bytes)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SelectivelyDisclosable)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+  iss: String,
+  #[sd(decoys = 2)]
+  given_name: String,
+  #[sd]
+  #[serde(rename = "birth/date")]
+  birthdate: String,
+  #[sd(each, decoys = 1)]
+  nationalities: Vec<String>,
+  #[sd]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  email: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SelectivelyDisclosable)]
+struct Resident {
+  #[sd(nested)]
+  address: Address,
+  #[sd(nested)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  previous_address: Option<Address>,
+  #[sd(nested)]
+  #[serde(flatten)]
+  contact: Contact,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SelectivelyDisclosable)]
+struct Address {
+  #[sd(decoys = 1)]
+  street_address: String,
+  country: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SelectivelyDisclosable)]
+struct Contact {
+  #[sd]
+  phone_number: String,
+}
+
+fn identity() -> Identity {
+  Identity {
+    iss: "https://issuer.example.com".to_string(),
+    given_name: "Alice".to_string(),
+    birthdate: "1940-01-01".to_string(),
+    nationalities: vec!["DE".to_string(), "US".to_string()],
+    email: None,
+  }
+}
+
+#[test]
+fn concealment_plan_follows_field_attributes() {
+  let mut expected = ConcealmentPlan::new();
+  expected
+    .conceal("/givenName")
+    .add_decoys("", 2)
+    .conceal("/birth~1date")
+    .conceal("/nationalities/0")
+    .conceal("/nationalities/1")
+    .add_decoys("/nationalities", 1)
+    .conceal("/email");
+
+  assert_eq!(identity().concealment_plan(), expected);
+}
+
+#[tokio::test]
+async fn nested_concealment_plans_are_composed() -> anyhow::Result<()> {
+  let resident = Resident {
+    address: Address {
+      street_address: "Schulstr. 12".to_string(),
+      country: "DE".to_string(),
+    },
+    previous_address: None,
+    contact: Contact {
+      phone_number: "+49 123 456".to_string(),
+    },
+  };
+  let mut expected = ConcealmentPlan::new();
+  expected
+    .conceal("/address/street_address")
+    .add_decoys("/address", 1)
+    .conceal("/phone_number");
+  assert_eq!(resident.concealment_plan(), expected);
+
+  let resident = Resident {
+    previous_address: Some(resident.address.clone()),
+    ..resident
+  };
+  let mut expected = ConcealmentPlan::new();
+  expected
+    .conceal("/address/street_address")
+    .add_decoys("/address", 1)
+    .conceal("/previous_address/street_address")
+    .add_decoys("/previous_address", 1)
+    .conceal("/phone_number");
+  assert_eq!(resident.concealment_plan(), expected);
+
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = SdJwtBuilder::new(&resident)?
+    .apply_plan(&resident.concealment_plan())?
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+  assert_eq!(sd_jwt.disclosures().len(), 3);
+  let decoded = Resident::from_disclosed_object(sd_jwt.into_disclosed_object(&hasher)?)?;
+  assert_eq!(decoded, resident);
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn typed_payload_round_trip() -> anyhow::Result<()> {
+  let hasher = Sha256Hasher::new();
+  let identity = identity();
+  let sd_jwt = SdJwtBuilder::new(&identity)?
+    .apply_plan(&identity.concealment_plan())?
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+
+  assert_eq!(sd_jwt.disclosures().len(), 4);
+  assert_eq!(sd_jwt.claims()._sd.len(), 4);
+  assert!(sd_jwt.claims().get("iss").is_some());
+  let decoded = Identity::from_disclosed_object(sd_jwt.clone().into_disclosed_object(&hasher)?)?;
+  assert_eq!(decoded, identity);
+
+  // Concealing a required claim makes the typed decoding fail.
+  let (presentation, _) = sd_jwt.into_presentation(&hasher)?.conceal("/givenName")?.finish()?;
+  let error = Identity::from_disclosed_object(presentation.into_disclosed_object(&hasher)?).unwrap_err();
+  assert!(matches!(error, Error::DeserializationError(_)));
+
+  Ok(())
+}
@@ -0,0 +1,130 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "ecdh-es")]
+
+use async_trait::async_trait;
+use josekit::jwe::JweHeader;
+use josekit::jwe::ECDH_ES;
+use josekit::jwk::alg::ec::EcCurve;
+use josekit::jwk::Jwk;
+use josekit::jws::JwsHeader;
+use josekit::jws::HS256;
+use josekit::jwt;
+use josekit::jwt::JwtPayload;
+use sd_jwt_payload::EcdhEsDecrypter;
+use sd_jwt_payload::EcdhEsEncrypter;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwsAlgorithm;
+use sd_jwt_payload::JwsSigner;
+use sd_jwt_payload::SdJwt;
+use sd_jwt_payload::SdJwtBuilder;
+use sd_jwt_payload::Sha256Hasher;
+use serde_json::json;
+
+const HMAC_SECRET: &[u8; 32] = b"0123456789ABCDEF0123456789ABCDEF";
+
+struct HmacSigner;
+
+#[async_trait]
+impl JwsSigner for HmacSigner {
+  type Error = josekit::JoseError;
+  async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    let signer = HS256.signer_from_bytes(HMAC_SECRET)?;
+    let header = JwsHeader::from_map(header.clone())?;
+    let payload = JwtPayload::from_map(payload.clone())?;
+
+    jwt::encode_with_signer(&payload, &header, &signer).map(String::into_bytes)
+  }
+}
+
+async fn make_presentation() -> SdJwt {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = SdJwtBuilder::new(json!({ "iss": "https://issuer.example", "given_name": "Alice", "age": 42 }))
+    .unwrap()
+    .make_concealable("/given_name")
+    .unwrap()
+    .make_concealable("/age")
+    .unwrap()
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await
+    .unwrap();
+  sd_jwt
+    .into_presentation(&hasher)
+    .unwrap()
+    .conceal("/age")
+    .unwrap()
+    .finish()
+    .unwrap()
+    .0
+}
+
+fn jwe_header(enc: &str) -> JsonObject {
+  json!({ "alg": "ECDH-ES", "enc": enc }).as_object().unwrap().clone()
+}
+
+#[tokio::test]
+async fn encrypted_presentation_round_trip() -> anyhow::Result<()> {
+  let sd_jwt = make_presentation().await;
+  let decrypter = EcdhEsDecrypter::generate();
+  let mut verifier_jwk = decrypter.public_jwk();
+  verifier_jwk.insert("kid".to_string(), "verifier-key".into());
+  let encrypter = EcdhEsEncrypter::from_jwk(&verifier_jwk)?;
+
+  for enc in ["A128GCM", "A256GCM"] {
+    let jwe = sd_jwt.encrypted_presentation(&encrypter, &jwe_header(enc)).await?;
+    assert_eq!(jwe.split('.').count(), 5);
+    assert!(!jwe.contains(&sd_jwt.presentation()));
+
+    let decrypted = SdJwt::parse_encrypted(&jwe, &decrypter).await?;
+    assert_eq!(decrypted.presentation(), sd_jwt.presentation());
+  }
+
+  let error = SdJwt::parse_encrypted(
+    &sd_jwt
+      .encrypted_presentation(&encrypter, &jwe_header("A128GCM"))
+      .await?,
+    &EcdhEsDecrypter::generate(),
+  )
+  .await
+  .unwrap_err();
+  assert!(matches!(error, Error::JweDecryptionFailure(_)));
+
+  let error = sd_jwt
+    .encrypted_presentation(&encrypter, &jwe_header("A192CBC-HS384"))
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::JweEncryptionFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn encrypted_presentation_interoperates_with_josekit() -> anyhow::Result<()> {
+  let sd_jwt = make_presentation().await;
+  let josekit_key = Jwk::generate_ec_key(EcCurve::P256)?;
+  let decrypter = EcdhEsDecrypter::from_jwk(josekit_key.as_ref())?;
+  let encrypter = EcdhEsEncrypter::from_jwk(&decrypter.public_jwk())?;
+
+  // Encrypted here, decrypted by josekit.
+  let jwe = sd_jwt
+    .encrypted_presentation(&encrypter, &jwe_header("A256GCM"))
+    .await?;
+  let (plaintext, header) = josekit::jwe::deserialize_compact(&jwe, &ECDH_ES.decrypter_from_jwk(&josekit_key)?)?;
+  assert_eq!(plaintext, sd_jwt.presentation().into_bytes());
+  assert_eq!(header.content_encryption(), Some("A256GCM"));
+
+  // Encrypted by josekit, decrypted here.
+  let mut header = JweHeader::new();
+  header.set_content_encryption("A128GCM");
+  let jwe = josekit::jwe::serialize_compact(
+    sd_jwt.presentation().as_bytes(),
+    &header,
+    &ECDH_ES.encrypter_from_jwk(&josekit_key.to_public_key()?)?,
+  )?;
+  let decrypted = SdJwt::parse_encrypted(&jwe, &decrypter).await?;
+  assert_eq!(decrypted.presentation(), sd_jwt.presentation());
+
+  Ok(())
+}
@@ -0,0 +1,315 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "status-list")]
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use josekit::jws::HS256;
+use josekit::jwt;
+use josekit::jwt::JwtPayload;
+use sd_jwt_payload::CheckKind;
+use sd_jwt_payload::CheckStatus;
+use sd_jwt_payload::Clock;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::InMemoryStatusListStorage;
+use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwsAlgorithm;
+use sd_jwt_payload::JwsHeader;
+use sd_jwt_payload::JwsSigner;
+use sd_jwt_payload::JwsVerifier;
+use sd_jwt_payload::SdJwt;
+use sd_jwt_payload::SdJwtBuilder;
+use sd_jwt_payload::SdJwtVcBuilder;
+use sd_jwt_payload::Sha256Hasher;
+use sd_jwt_payload::StatusList;
+use sd_jwt_payload::StatusListReference;
+use sd_jwt_payload::StatusListResolver;
+use sd_jwt_payload::StatusListStorage;
+use sd_jwt_payload::StatusListToken;
+use sd_jwt_payload::StatusListTokenBuilder;
+use sd_jwt_payload::StatusListTokenFetcher;
+use sd_jwt_payload::StatusType;
+use sd_jwt_payload::VerificationOptions;
+use sd_jwt_payload::STATUS_LIST_JWT_TYP;
+use serde_json::json;
+
+const HMAC_SECRET: &[u8; 32] = b"0123456789ABCDEF0123456789ABCDEF";
+const STATUS_LIST_URI: &str = "https://issuer.example.com/statuslists/1";
+
+struct HmacSigner;
+
+#[async_trait]
+impl JwsSigner for HmacSigner {
+  type Error = josekit::JoseError;
+  async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    let signer = HS256.signer_from_bytes(HMAC_SECRET)?;
+    let header = josekit::jws::JwsHeader::from_map(header.clone())?;
+    let payload = JwtPayload::from_map(payload.clone())?;
+
+    jwt::encode_with_signer(&payload, &header, &signer).map(String::into_bytes)
+  }
+}
+
+struct HmacVerifier;
+
+#[async_trait]
+impl JwsVerifier for HmacVerifier {
+  type Error = josekit::JoseError;
+  async fn verify(
+    &self,
+    _header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    _key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    let verifier = HS256.verifier_from_bytes(HMAC_SECRET)?;
+    josekit::jws::JwsVerifier::verify(&verifier, signing_input, signature)
+  }
+}
+
+/// A verifier rejecting every signature.
+struct RejectingVerifier;
+
+#[async_trait]
+impl JwsVerifier for RejectingVerifier {
+  type Error = Error;
+  async fn verify(
+    &self,
+    _header: &JwsHeader,
+    _signing_input: &[u8],
+    _signature: &[u8],
+    _key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    Err(Error::JwsVerificationFailure("untrusted issuer".to_string()))
+  }
+}
+
+/// A [`StatusListTokenFetcher`] counting how many tokens it fetched.
+#[derive(Clone, Default)]
+struct CountingFetcher {
+  tokens: HashMap<String, String>,
+  fetched: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl StatusListTokenFetcher for CountingFetcher {
+  type Error = Error;
+  async fn fetch(&self, uri: &str) -> Result<String, Self::Error> {
+    self.fetched.fetch_add(1, Ordering::SeqCst);
+    self.tokens.fetch(uri).await
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SharedClock(Arc<AtomicI64>);
+
+impl Clock for SharedClock {
+  fn now(&self) -> i64 {
+    self.0.load(Ordering::SeqCst)
+  }
+}
+
+#[tokio::test]
+async fn issued_credentials_reference_allocated_entries() -> anyhow::Result<()> {
+  let storage = InMemoryStatusListStorage::new();
+  storage.insert(STATUS_LIST_URI, StatusList::new(1, 2)?);
+
+  let sd_jwt_vc = SdJwtVcBuilder::new(json!({ "given_name": "Alice" }))?
+    .iss("https://issuer.example.com")?
+    .vct("https://credentials.example.com/identity_credential")?
+    .allocate_status(&storage, STATUS_LIST_URI)
+    .await?
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+  let status = sd_jwt_vc.status().and_then(|status| status.status_list.as_ref());
+  assert_eq!(
+    status,
+    Some(&StatusListReference {
+      idx: 0,
+      uri: STATUS_LIST_URI.to_string()
+    })
+  );
+
+  // Other status mechanisms are preserved.
+  let sd_jwt = SdJwtBuilder::new(json!({ "status": { "other_mechanism": {} } }))?
+    .allocate_status(&storage, STATUS_LIST_URI)
+    .await?
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+  assert_eq!(
+    sd_jwt.claims().get("status"),
+    Some(&json!({ "other_mechanism": {}, "status_list": { "idx": 1, "uri": STATUS_LIST_URI } }))
+  );
+
+  let error = SdJwtBuilder::new(json!({}))?
+    .allocate_status(&storage, STATUS_LIST_URI)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::StatusListFailure(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn allocated_statuses_cannot_be_concealed() -> anyhow::Result<()> {
+  let storage = InMemoryStatusListStorage::new();
+  storage.insert(STATUS_LIST_URI, StatusList::new(4, 2)?);
+
+  // Even without any non-concealable path, the allocated status stays disclosed.
+  let sd_jwt = SdJwtBuilder::new(json!({ "given_name": "Alice" }))?
+    .non_concealable_paths(Vec::<String>::new())
+    .allocate_status(&storage, STATUS_LIST_URI)
+    .await?
+    .make_concealable_recursive("")?
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+  assert!(sd_jwt.claims().get("status").is_some());
+  assert!(sd_jwt.claims().get("given_name").is_none());
+
+  let error = SdJwtBuilder::new(json!({ "status": { "other_mechanism": {} } }))?
+    .non_concealable_paths(Vec::<String>::new())
+    .make_concealable("/status")?
+    .allocate_status(&storage, STATUS_LIST_URI)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::NonConcealableClaim(_)));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn status_list_token_publishes_the_stored_list() -> anyhow::Result<()> {
+  let storage = InMemoryStatusListStorage::new();
+  storage.insert(STATUS_LIST_URI, StatusList::new(2, 100)?);
+  let revoked = storage.allocate_index(STATUS_LIST_URI).await?;
+  let suspended = storage.allocate_index(STATUS_LIST_URI).await?;
+  storage
+    .set_status(STATUS_LIST_URI, revoked, StatusType::Invalid)
+    .await?;
+  storage
+    .set_status(STATUS_LIST_URI, suspended, StatusType::Suspended)
+    .await?;
+
+  let token = StatusListTokenBuilder::new(STATUS_LIST_URI, storage.status_list(STATUS_LIST_URI).await?)
+    .iat(1_700_000_000)
+    .exp(1_700_086_400)
+    .ttl(43_200)
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+  let token = StatusListToken::parse(&token.to_string())?;
+
+  assert_eq!(token.header().typ.as_deref(), Some(STATUS_LIST_JWT_TYP));
+  assert_eq!(token.claims().sub, STATUS_LIST_URI);
+  assert_eq!(token.claims().ttl, Some(43_200));
+  let status_list = token.status_list();
+  assert_eq!(status_list.bits(), 2);
+  assert_eq!(status_list.get(revoked), Some(StatusType::Invalid));
+  assert_eq!(status_list.get(suspended), Some(StatusType::Suspended));
+  assert_eq!(status_list.get(2), Some(StatusType::Valid));
+
+  Ok(())
+}
+
+#[tokio::test]
+async fn revoked_and_suspended_credentials_are_rejected() -> anyhow::Result<()> {
+  const NOW: i64 = 1_700_000_000;
+  let storage = InMemoryStatusListStorage::new();
+  storage.insert(STATUS_LIST_URI, StatusList::new(2, 16)?);
+  let mut credentials: Vec<SdJwt> = vec![];
+  for _ in 0..3 {
+    let sd_jwt = SdJwtBuilder::new(json!({ "iss": "https://issuer.example.com" }))?
+      .allocate_status(&storage, STATUS_LIST_URI)
+      .await?
+      .finish(&HmacSigner, JwsAlgorithm::HS256)
+      .await?;
+    credentials.push(sd_jwt);
+  }
+  storage.set_status(STATUS_LIST_URI, 1, StatusType::Invalid).await?;
+  storage.set_status(STATUS_LIST_URI, 2, StatusType::Suspended).await?;
+  let token = StatusListTokenBuilder::new(STATUS_LIST_URI, storage.status_list(STATUS_LIST_URI).await?)
+    .iat(NOW)
+    .ttl(300)
+    .finish(&HmacSigner, JwsAlgorithm::HS256)
+    .await?;
+
+  let fetcher = CountingFetcher {
+    tokens: HashMap::from([
+      (STATUS_LIST_URI.to_string(), token.to_string()),
+      (
+        "https://issuer.example.com/statuslists/2".to_string(),
+        token.to_string(),
+      ),
+    ]),
+    ..Default::default()
+  };
+  let clock = SharedClock::default();
+  clock.0.store(NOW, Ordering::SeqCst);
+  let resolver = StatusListResolver::new(fetcher.clone()).clock(clock.clone());
+
+  credentials[0].verify_status(&resolver, &HmacVerifier).await?;
+  assert_eq!(
+    credentials[1].verify_status(&resolver, &HmacVerifier).await,
+    Err(Error::CredentialRevoked)
+  );
+  assert_eq!(
+    credentials[2].verify_status(&resolver, &HmacVerifier).await,
+    Err(Error::CredentialSuspended)
+  );
+  // The token is cached until its `ttl` elapses.
+  assert_eq!(fetcher.fetched.load(Ordering::SeqCst), 1);
+  clock.0.store(NOW + 300, Ordering::SeqCst);
+  credentials[0].verify_status(&resolver, &HmacVerifier).await?;
+  assert_eq!(fetcher.fetched.load(Ordering::SeqCst), 2);
+  // Cached tokens are verified again by each verifier.
+  let error = credentials[0]
+    .verify_status(&resolver, &RejectingVerifier)
+    .await
+    .unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+  assert_eq!(fetcher.fetched.load(Ordering::SeqCst), 2);
+
+  // A token must be published at the URI it names in `sub`.
+  let reference = StatusListReference {
+    idx: 0,
+    uri: "https://issuer.example.com/statuslists/2".to_string(),
+  };
+  let error = resolver.status(&reference, &HmacVerifier).await.unwrap_err();
+  assert!(matches!(error, Error::StatusListFailure(_)));
+
+  // The status check is part of the report.
+  let hasher = Sha256Hasher::new();
+  let options = VerificationOptions::new().clock(clock).status_list(resolver);
+  let report = credentials[1]
+    .verify_with_report(&HmacVerifier, &hasher, &options)
+    .await;
+  let status_check = report
+    .checks()
+    .iter()
+    .find(|check| check.kind == CheckKind::Status)
+    .unwrap();
+  assert_eq!(status_check.status, CheckStatus::Failed);
+  assert_eq!(report.into_result(), Err(Error::CredentialRevoked));
+  let report = credentials[0]
+    .verify_with_report(&HmacVerifier, &hasher, &options)
+    .await;
+  assert!(report.is_valid());
+
+  // And of the verification returning the disclosed object.
+  assert_eq!(
+    credentials[2]
+      .verify_with_options(&HmacVerifier, &hasher, &options)
+      .await,
+    Err(Error::CredentialSuspended)
+  );
+  credentials[0]
+    .verify_with_options(&HmacVerifier, &hasher, &options)
+    .await?;
+
+  Ok(())
+}
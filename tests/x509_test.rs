@@ -0,0 +1,149 @@
+// Copyright 2020-2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+#![cfg(feature = "x509")]
+
+use async_trait::async_trait;
+use josekit::jwk::Jwk;
+use josekit::jws::ES256;
+use josekit::jwt;
+use josekit::jwt::JwtPayload;
+use sd_jwt_payload::Error;
+use sd_jwt_payload::FixedClock;
+use sd_jwt_payload::JsonObject;
+use sd_jwt_payload::JwsAlgorithm;
+use sd_jwt_payload::JwsHeader;
+use sd_jwt_payload::JwsSigner;
+use sd_jwt_payload::JwsVerifier;
+use sd_jwt_payload::KeyResolvingVerifier;
+use sd_jwt_payload::SdJwt;
+use sd_jwt_payload::SdJwtBuilder;
+use sd_jwt_payload::Sha256Hasher;
+use sd_jwt_payload::X509KeyResolver;
+use serde_json::json;
+
+const ROOT: &str = include_str!("fixtures/x509/root.pem");
+const INTERMEDIATE: &str = include_str!("fixtures/x509/int.pem");
+const LEAF: &str = include_str!("fixtures/x509/leaf.pem");
+const LEAF_KEY: &str = include_str!("fixtures/x509/leaf.key");
+const OTHER_ROOT: &str = include_str!("fixtures/x509/other_root.pem");
+
+/// Some time within the validity period of all fixture certificates.
+const NOW: i64 = 1792198202 + 86400;
+
+struct Es256Signer;
+
+#[async_trait]
+impl JwsSigner for Es256Signer {
+  type Error = josekit::JoseError;
+  async fn sign(&self, header: &JsonObject, payload: &JsonObject) -> Result<Vec<u8>, Self::Error> {
+    let signer = ES256.signer_from_pem(LEAF_KEY)?;
+    let header = josekit::jws::JwsHeader::from_map(header.clone())?;
+    let payload = JwtPayload::from_map(payload.clone())?;
+
+    jwt::encode_with_signer(&payload, &header, &signer).map(String::into_bytes)
+  }
+}
+
+struct Es256JwkVerifier;
+
+#[async_trait]
+impl JwsVerifier for Es256JwkVerifier {
+  type Error = josekit::JoseError;
+  async fn verify(
+    &self,
+    _header: &JwsHeader,
+    signing_input: &[u8],
+    signature: &[u8],
+    key: Option<&JsonObject>,
+  ) -> Result<(), Self::Error> {
+    let jwk = Jwk::from_map(key.cloned().unwrap_or_default())?;
+    let verifier = ES256.verifier_from_jwk(&jwk)?;
+    josekit::jws::JwsVerifier::verify(&verifier, signing_input, signature)
+  }
+}
+
+/// Returns the base64-encoded DER of a PEM certificate, i.e. its `x5c` representation.
+fn x5c_entry(pem: &str) -> String {
+  pem.lines().filter(|line| !line.starts_with("-----")).collect()
+}
+
+async fn make_sd_jwt(iss: &str, x5c: &[&str]) -> SdJwt {
+  let x5c: Vec<String> = x5c.iter().map(|pem| x5c_entry(pem)).collect();
+  SdJwtBuilder::new(json!({ "iss": iss, "given_name": "Alice" }))
+    .unwrap()
+    .make_concealable("/given_name")
+    .unwrap()
+    .header(JwsHeader {
+      x5c: Some(x5c),
+      ..Default::default()
+    })
+    .finish(&Es256Signer, JwsAlgorithm::ES256)
+    .await
+    .unwrap()
+}
+
+fn verifier(trust_anchors: &str, now: i64) -> KeyResolvingVerifier<X509KeyResolver, Es256JwkVerifier> {
+  let resolver = X509KeyResolver::from_pem(trust_anchors)
+    .unwrap()
+    .clock(FixedClock::new(now));
+  KeyResolvingVerifier::new(resolver, Es256JwkVerifier)
+}
+
+#[tokio::test]
+async fn sd_jwt_signed_with_a_trusted_chain_is_verifiable() {
+  let hasher = Sha256Hasher::new();
+  for iss in ["https://issuer.example.com", "https://issuer.example.com/credentials"] {
+    let sd_jwt = make_sd_jwt(iss, &[LEAF, INTERMEDIATE]).await;
+    let disclosed = sd_jwt.verify(&verifier(ROOT, NOW), &hasher).await.unwrap();
+    assert_eq!(disclosed.get("given_name").unwrap(), "Alice");
+  }
+
+  // The trust anchor can be part of the chain too.
+  let sd_jwt = make_sd_jwt("https://issuer.example.com", &[LEAF, INTERMEDIATE, ROOT]).await;
+  sd_jwt.verify(&verifier(ROOT, NOW), &hasher).await.unwrap();
+}
+
+#[tokio::test]
+async fn chains_not_leading_to_a_trust_anchor_are_rejected() {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt("https://issuer.example.com", &[LEAF, INTERMEDIATE]).await;
+  let error = sd_jwt.verify(&verifier(OTHER_ROOT, NOW), &hasher).await.unwrap_err();
+  assert!(matches!(error, Error::JwsVerificationFailure(_)));
+
+  // Missing intermediate.
+  let sd_jwt = make_sd_jwt("https://issuer.example.com", &[LEAF]).await;
+  assert!(sd_jwt.verify(&verifier(ROOT, NOW), &hasher).await.is_err());
+}
+
+#[tokio::test]
+async fn chains_are_rejected_outside_their_validity_period() {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt("https://issuer.example.com", &[LEAF, INTERMEDIATE]).await;
+
+  assert!(sd_jwt.verify(&verifier(ROOT, NOW - 2 * 86400), &hasher).await.is_err());
+  assert!(sd_jwt.verify(&verifier(ROOT, 2107558202 + 1), &hasher).await.is_err());
+}
+
+#[tokio::test]
+async fn issuer_must_match_the_leaf_subject_alternative_name() {
+  let hasher = Sha256Hasher::new();
+  let sd_jwt = make_sd_jwt("https://attacker.example.com", &[LEAF, INTERMEDIATE]).await;
+
+  let error = sd_jwt.verify(&verifier(ROOT, NOW), &hasher).await.unwrap_err();
+  assert!(error.to_string().contains("Subject Alternative Name"));
+}
+
+#[test]
+fn validate_chain_returns_the_leaf_key() {
+  let resolver = X509KeyResolver::from_pem(ROOT).unwrap().clock(FixedClock::new(NOW));
+  let jwk = resolver
+    .validate_chain(
+      &[x5c_entry(LEAF), x5c_entry(INTERMEDIATE)],
+      "https://issuer.example.com",
+    )
+    .unwrap();
+
+  assert_eq!(jwk.get("kty").unwrap(), "EC");
+  assert_eq!(jwk.get("crv").unwrap(), "P-256");
+}